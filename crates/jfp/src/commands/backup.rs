@@ -0,0 +1,104 @@
+//! Backup command implementation
+//!
+//! Writes every prompt in the database to a JSONL file with a `_meta`
+//! header (see `storage::jsonl`):
+//! - `jfp backup <path>` writes to the given file
+//! - `jfp backup` writes an automatic backup to `backups/` in the cache dir
+//!   and keeps only the newest `--keep` automatic backups
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use crate::storage::{self, Database};
+
+/// Automatic backups kept by default
+pub const DEFAULT_KEEP: usize = 10;
+
+#[derive(Serialize)]
+struct BackupOutput {
+    backed_up: bool,
+    path: String,
+    prompt_count: usize,
+    automatic: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rotated: Vec<String>,
+}
+
+pub fn run(path: Option<PathBuf>, keep: usize, use_json: bool) -> ExitCode {
+    // Open without syncing: a backup records what is stored right now
+    let db = match Database::open() {
+        Ok(db) => db,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let automatic = path.is_none();
+    let (path, prompt_count, rotated) = match write_backup(&db, path.as_deref(), keep) {
+        Ok(written) => written,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "backup_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error writing backup: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = BackupOutput {
+        backed_up: true,
+        path: path.display().to_string(),
+        prompt_count,
+        automatic,
+        rotated: rotated.iter().map(|p| p.display().to_string()).collect(),
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        println!("Backed up {} prompts to {}", output.prompt_count, output.path);
+        if !output.rotated.is_empty() {
+            println!("Removed {} old automatic backups", output.rotated.len());
+        }
+        println!("\nRestore with 'jfp restore {}'", output.path);
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Write a backup to `path`, or an automatic backup rotated to `keep`
+///
+/// Returns the file written, the prompt count and any rotated-out backups.
+pub fn write_backup(
+    db: &Database,
+    path: Option<&Path>,
+    keep: usize,
+) -> anyhow::Result<(PathBuf, usize, Vec<PathBuf>)> {
+    match path {
+        Some(path) => {
+            let count = storage::export_jsonl(db, path)?;
+            Ok((path.to_path_buf(), count, Vec::new()))
+        }
+        None => {
+            let dir = storage::backups_dir();
+            let path = storage::auto_backup_path(&dir);
+            let count = storage::export_jsonl(db, &path)?;
+            let rotated = storage::rotate_backups(&dir, keep.max(1))?;
+            Ok((path, count, rotated))
+        }
+    }
+}
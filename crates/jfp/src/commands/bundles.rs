@@ -3,13 +3,20 @@
 //! From EXISTING_JFP_STRUCTURE.md sections for bundles/bundle:
 //! - bundles: List available bundles
 //! - bundle: Show bundle details
+//!
+//! Bundles come from the registry and are stored in `bundles` /
+//! `bundle_prompts`; the embedded definitions are only used until a
+//! registry has provided some.
 
 use std::collections::HashMap;
 use std::process::ExitCode;
 
+use anyhow::Result;
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
+use crate::registry;
+use crate::storage::Database;
+use crate::types::{Bundle, BundleSummary};
 
 #[derive(Serialize)]
 struct BundlesOutput {
@@ -17,21 +24,17 @@ struct BundlesOutput {
     count: usize,
 }
 
-#[derive(Serialize)]
-struct BundleSummary {
-    id: String,
-    title: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
-    prompt_count: usize,
-}
-
 #[derive(Serialize)]
 struct BundleOutput {
     id: String,
     title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    featured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
     prompts: Vec<BundlePrompt>,
 }
 
@@ -69,61 +72,116 @@ const BUNDLE_DEFINITIONS: &[BundleDefinition] = &[
     },
 ];
 
-fn prompt_title_map() -> HashMap<String, String> {
-    bundled_prompts()
-        .into_iter()
-        .map(|prompt| (prompt.id, prompt.title))
+/// The embedded bundle definitions
+fn embedded_bundles() -> Vec<Bundle> {
+    BUNDLE_DEFINITIONS
+        .iter()
+        .map(|bundle| {
+            let prompts: Vec<String> = bundle.prompt_ids.iter().map(|id| id.to_string()).collect();
+            Bundle {
+                description: Some(bundle.description.to_string()),
+                prompt_count: prompts.len(),
+                prompts,
+                ..Bundle::new(bundle.id, bundle.title)
+            }
+        })
         .collect()
 }
 
-fn build_bundle_summary(
-    bundle: &BundleDefinition,
-    titles_by_id: &HashMap<String, String>,
-) -> BundleSummary {
-    let prompt_count = bundle
-        .prompt_ids
-        .iter()
-        .filter(|id| titles_by_id.contains_key(**id))
-        .count();
-
-    BundleSummary {
-        id: bundle.id.to_string(),
-        title: bundle.title.to_string(),
-        description: Some(bundle.description.to_string()),
-        prompt_count,
+/// Bundles stored from the registry, or the embedded definitions when
+/// none have been fetched
+///
+/// Embedded bundles are trimmed to the prompts in the database so their
+/// counts match what `jfp bundle <id>` shows.
+fn load_bundles(db: &Database) -> Result<Vec<Bundle>> {
+    let stored = db.bundles()?;
+    if !stored.is_empty() {
+        return Ok(stored);
+    }
+
+    let mut bundles = embedded_bundles();
+    for bundle in &mut bundles {
+        let mut present = Vec::with_capacity(bundle.prompts.len());
+        for id in &bundle.prompts {
+            if db.get_prompt(id)?.is_some() {
+                present.push(id.clone());
+            }
+        }
+        bundle.prompt_count = present.len();
+        bundle.prompts = present;
+    }
+    Ok(bundles)
+}
+
+fn prompt_title_map(db: &Database, bundle: &Bundle) -> Result<HashMap<String, String>> {
+    let mut titles = HashMap::new();
+    for id in &bundle.prompts {
+        if let Some(prompt) = db.get_prompt(id)? {
+            titles.insert(id.clone(), prompt.title);
+        }
     }
+    Ok(titles)
 }
 
-fn build_bundle_output(
-    bundle: &BundleDefinition,
-    titles_by_id: &HashMap<String, String>,
-) -> BundleOutput {
+fn build_bundle_output(bundle: &Bundle, titles_by_id: &HashMap<String, String>) -> BundleOutput {
     let prompts = bundle
-        .prompt_ids
+        .prompts
         .iter()
         .filter_map(|prompt_id| {
-            titles_by_id.get(*prompt_id).map(|title| BundlePrompt {
-                id: (*prompt_id).to_string(),
+            titles_by_id.get(prompt_id).map(|title| BundlePrompt {
+                id: prompt_id.clone(),
                 title: title.clone(),
             })
         })
         .collect();
 
     BundleOutput {
-        id: bundle.id.to_string(),
-        title: bundle.title.to_string(),
-        description: Some(bundle.description.to_string()),
+        id: bundle.id.clone(),
+        title: bundle.title.clone(),
+        description: bundle.description.clone(),
+        version: bundle.version.clone(),
+        featured: bundle.featured,
+        author: bundle.author.clone(),
         prompts,
     }
 }
 
+/// Open the database, syncing from the registry when empty or stale
+fn open_database(use_json: bool) -> Option<Database> {
+    match registry::open_database() {
+        Ok((db, sync)) => {
+            sync.report(use_json);
+            Some(db)
+        }
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            None
+        }
+    }
+}
+
+fn database_error(e: &anyhow::Error, use_json: bool) -> ExitCode {
+    if use_json {
+        println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+    } else {
+        eprintln!("Error loading bundles: {}", e);
+    }
+    ExitCode::FAILURE
+}
+
 /// List all available bundles
 pub fn list_bundles(use_json: bool) -> ExitCode {
-    let titles_by_id = prompt_title_map();
-    let bundles: Vec<BundleSummary> = BUNDLE_DEFINITIONS
-        .iter()
-        .map(|bundle| build_bundle_summary(bundle, &titles_by_id))
-        .collect();
+    let Some(db) = open_database(use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let bundles: Vec<BundleSummary> = match load_bundles(&db) {
+        Ok(bundles) => bundles.iter().map(BundleSummary::from).collect(),
+        Err(e) => return database_error(&e, use_json),
+    };
 
     if use_json {
         let output = BundlesOutput {
@@ -144,10 +202,22 @@ pub fn list_bundles(use_json: bool) -> ExitCode {
             println!("No bundles available yet.");
         } else {
             for bundle in &bundles {
-                println!("  {} - {} ({} prompts)", bundle.id, bundle.title, bundle.prompt_count);
+                let featured = if bundle.featured { "★ " } else { "" };
+                let version = bundle
+                    .version
+                    .as_ref()
+                    .map(|v| format!(" v{}", v))
+                    .unwrap_or_default();
+                println!(
+                    "  {}{}{} - {} ({} prompts)",
+                    featured, bundle.id, version, bundle.title, bundle.prompt_count
+                );
                 if let Some(desc) = &bundle.description {
                     println!("    {}", desc);
                 }
+                if let Some(author) = &bundle.author {
+                    println!("    by {}", author);
+                }
             }
         }
 
@@ -159,8 +229,14 @@ pub fn list_bundles(use_json: bool) -> ExitCode {
 
 /// Show details for a specific bundle
 pub fn show_bundle(id: &str, use_json: bool) -> ExitCode {
-    let titles_by_id = prompt_title_map();
-    let Some(bundle) = BUNDLE_DEFINITIONS.iter().find(|bundle| bundle.id == id) else {
+    let Some(db) = open_database(use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let bundles = match load_bundles(&db) {
+        Ok(bundles) => bundles,
+        Err(e) => return database_error(&e, use_json),
+    };
+    let Some(bundle) = bundles.iter().find(|bundle| bundle.id == id) else {
         if use_json {
             println!(r#"{{"error": "not_found", "id": "{}"}}"#, id);
         } else {
@@ -170,6 +246,10 @@ pub fn show_bundle(id: &str, use_json: bool) -> ExitCode {
         return ExitCode::FAILURE;
     };
 
+    let titles_by_id = match prompt_title_map(&db, bundle) {
+        Ok(titles) => titles,
+        Err(e) => return database_error(&e, use_json),
+    };
     let output = build_bundle_output(bundle, &titles_by_id);
 
     if use_json {
@@ -185,6 +265,19 @@ pub fn show_bundle(id: &str, use_json: bool) -> ExitCode {
         if let Some(desc) = &output.description {
             println!("{}\n", desc);
         }
+        let mut details = Vec::new();
+        if let Some(version) = &output.version {
+            details.push(format!("Version: {}", version));
+        }
+        if let Some(author) = &output.author {
+            details.push(format!("Author: {}", author));
+        }
+        if output.featured {
+            details.push("Featured".to_string());
+        }
+        if !details.is_empty() {
+            println!("{}\n", details.join("  "));
+        }
         println!("Prompts ({}):\n", output.prompts.len());
         for prompt in &output.prompts {
             println!("  - {} ({})", prompt.title, prompt.id);
@@ -201,6 +294,17 @@ mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    use crate::registry::bundled_prompts;
+    use crate::types::Prompt;
+
+    fn seeded_db() -> Result<Database> {
+        let db = Database::in_memory()?;
+        for prompt in bundled_prompts() {
+            db.upsert_prompt(&prompt)?;
+        }
+        Ok(db)
+    }
+
     #[test]
     fn bundle_ids_are_unique() {
         let mut seen = HashSet::new();
@@ -215,11 +319,11 @@ mod tests {
 
     #[test]
     fn bundle_prompt_ids_exist_in_embedded_prompts() {
-        let titles_by_id = prompt_title_map();
+        let ids: HashSet<String> = bundled_prompts().into_iter().map(|p| p.id).collect();
         for bundle in BUNDLE_DEFINITIONS {
             for prompt_id in bundle.prompt_ids {
                 assert!(
-                    titles_by_id.contains_key(*prompt_id),
+                    ids.contains(*prompt_id),
                     "bundle '{}' references missing prompt id '{}'",
                     bundle.id,
                     prompt_id
@@ -229,13 +333,46 @@ mod tests {
     }
 
     #[test]
-    fn bundle_output_prompt_count_matches_definition() {
-        let titles_by_id = prompt_title_map();
-        let bundle = BUNDLE_DEFINITIONS
-            .iter()
+    fn bundle_output_prompt_count_matches_definition() -> Result<()> {
+        let db = seeded_db()?;
+        let bundle = load_bundles(&db)?
+            .into_iter()
             .find(|bundle| bundle.id == "getting-started")
             .expect("missing getting-started bundle");
-        let output = build_bundle_output(bundle, &titles_by_id);
-        assert_eq!(output.prompts.len(), bundle.prompt_ids.len());
+        let output = build_bundle_output(&bundle, &prompt_title_map(&db, &bundle)?);
+        assert_eq!(output.prompts.len(), bundle.prompt_count);
+        assert_eq!(bundle.prompt_count, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn stored_bundles_replace_embedded_definitions() -> Result<()> {
+        let mut db = seeded_db()?;
+        db.upsert_prompt(&Prompt::new("team-review", "Team Review", "Review as a team"))?;
+
+        let fetched = Bundle {
+            version: Some("2.0.0".to_string()),
+            featured: true,
+            author: Some("Jeffrey".to_string()),
+            prompts: vec![
+                "team-review".to_string(),
+                "no-such-prompt".to_string(),
+                "debug".to_string(),
+            ],
+            ..Bundle::new("team", "Team")
+        };
+        db.replace_bundles(&[fetched])?;
+
+        let bundles = load_bundles(&db)?;
+        assert_eq!(bundles.len(), 1);
+        let output = build_bundle_output(&bundles[0], &prompt_title_map(&db, &bundles[0])?);
+        assert_eq!(output.version.as_deref(), Some("2.0.0"));
+        assert!(output.featured);
+        let ids: Vec<_> = output.prompts.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["team-review", "debug"]);
+
+        db.replace_bundles(&[])?;
+        assert_eq!(load_bundles(&db)?.len(), BUNDLE_DEFINITIONS.len());
+        Ok(())
     }
 }
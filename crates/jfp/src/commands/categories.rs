@@ -7,8 +7,7 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry;
 
 #[derive(Serialize)]
 struct CategoryOutput {
@@ -23,9 +22,9 @@ struct CategoriesOutput {
 }
 
 pub fn run(use_json: bool) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -36,14 +35,7 @@ pub fn run(use_json: bool) -> ExitCode {
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Get category counts
     let categories = match db.category_counts() {
@@ -0,0 +1,533 @@
+//! Collection command implementation
+//!
+//! Local, ordered sets of prompts (the premium server's collections, offline):
+//! - `jfp collection create <name> [--description ...]`
+//! - `jfp collection add|rm <name> <id>...`, `move <name> <id> <position>`
+//! - `jfp collection show <name>`, `list`, `delete <name>`
+//! - `jfp collection export <name> --format md|skill` writes through the
+//!   `jfp export` writer, into `./<name>/` by default
+
+use std::process::ExitCode;
+
+use chrono::Utc;
+use serde::Serialize;
+
+use super::export::{self, ExportedPrompt};
+use crate::registry;
+use crate::storage::{Collection, Database};
+
+#[derive(Serialize)]
+struct CollectionListOutput {
+    collections: Vec<CollectionSummary>,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct CollectionSummary {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    prompt_count: usize,
+    updated_at: String,
+}
+
+#[derive(Serialize)]
+struct CollectionShowOutput {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    created_at: String,
+    updated_at: String,
+    prompts: Vec<CollectionEntry>,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct CollectionEntry {
+    position: usize,
+    id: String,
+    /// None when the prompt is no longer in the database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CollectionChangeOutput {
+    collection: String,
+    action: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    prompts: Vec<String>,
+    changed: bool,
+}
+
+#[derive(Serialize)]
+struct CollectionExportOutput {
+    collection: String,
+    exported: Vec<ExportedPrompt>,
+    count: usize,
+    format: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dir: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing: Vec<String>,
+}
+
+pub fn run(
+    action: &str,
+    name: Option<&str>,
+    args: &[String],
+    description: Option<&str>,
+    use_json: bool,
+) -> ExitCode {
+    if !matches!(action, "create" | "add" | "rm" | "move" | "show" | "list" | "delete") {
+        if use_json {
+            println!(r#"{{"error": "invalid_action", "action": "{}"}}"#, action);
+        } else {
+            eprintln!(
+                "Invalid action: {}. Use: create, add, rm, move, show, list, delete, export",
+                action
+            );
+        }
+        return ExitCode::FAILURE;
+    }
+
+    let name = name.unwrap_or_default();
+    if action != "list" && name.trim().is_empty() {
+        if use_json {
+            println!(r#"{{"error": "missing_name"}}"#);
+        } else {
+            eprintln!("Error: Collection name required for `jfp collection {}`", action);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    let Some((mut db, sync)) = open(use_json) else {
+        return ExitCode::FAILURE;
+    };
+    sync.report(use_json);
+
+    if action == "list" {
+        return list(&db, use_json);
+    }
+
+    if action == "create" {
+        if let Err(e) = validate_name(name) {
+            if use_json {
+                println!(r#"{{"error": "invalid_name", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Invalid collection name '{}': {}", name, e);
+            }
+            return ExitCode::FAILURE;
+        }
+        return match db.create_collection(name, description, &Utc::now().to_rfc3339()) {
+            Ok(true) => print_change(name, "created", Vec::new(), true, use_json),
+            Ok(false) => {
+                if use_json {
+                    println!(r#"{{"error": "collection_exists", "name": "{}"}}"#, name);
+                } else {
+                    eprintln!("Collection already exists: {}", name);
+                }
+                ExitCode::FAILURE
+            }
+            Err(e) => database_error(&e, use_json),
+        };
+    }
+
+    let Some(collection) = load(&db, name, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let now = Utc::now().to_rfc3339();
+
+    match action {
+        "show" => show(&db, collection, use_json),
+        "delete" => match db.delete_collection(name) {
+            Ok(changed) => print_change(name, "deleted", Vec::new(), changed, use_json),
+            Err(e) => database_error(&e, use_json),
+        },
+        "add" => {
+            if args.is_empty() {
+                return missing_ids(action, use_json);
+            }
+            // Store the resolved ids, e.g. `team/code-review` -> its stored id
+            let mut ids = Vec::with_capacity(args.len());
+            for id in args {
+                match db.get_prompt(id) {
+                    Ok(Some(prompt)) => ids.push(prompt.id),
+                    Ok(None) => {
+                        if use_json {
+                            println!(r#"{{"error": "not_found", "id": "{}"}}"#, id);
+                        } else {
+                            eprintln!("Prompt not found: {}", id);
+                        }
+                        return ExitCode::FAILURE;
+                    }
+                    Err(e) => return database_error(&e, use_json),
+                }
+            }
+            match db.add_to_collection(name, &ids, &now) {
+                Ok(added) => {
+                    let changed = !added.is_empty();
+                    print_change(name, "added", added, changed, use_json)
+                }
+                Err(e) => database_error(&e, use_json),
+            }
+        }
+        "rm" => {
+            if args.is_empty() {
+                return missing_ids(action, use_json);
+            }
+            match db.remove_from_collection(name, args, &now) {
+                Ok(removed) => {
+                    let changed = !removed.is_empty();
+                    print_change(name, "removed", removed, changed, use_json)
+                }
+                Err(e) => database_error(&e, use_json),
+            }
+        }
+        _ => {
+            // move <id> <position>, 1-based like `show`
+            let position = match args {
+                [_, position] => position.parse::<usize>().ok().filter(|p| *p > 0),
+                _ => None,
+            };
+            let Some(position) = position else {
+                if use_json {
+                    println!(r#"{{"error": "invalid_position"}}"#);
+                } else {
+                    eprintln!("Usage: jfp collection move <name> <id> <position> (1-based)");
+                }
+                return ExitCode::FAILURE;
+            };
+            match db.move_in_collection(name, &args[0], position - 1, &now) {
+                Ok(true) => print_change(name, "moved", vec![args[0].clone()], true, use_json),
+                Ok(false) => {
+                    if use_json {
+                        println!(r#"{{"error": "not_in_collection", "id": "{}"}}"#, args[0]);
+                    } else {
+                        eprintln!("{} is not in collection {}", args[0], name);
+                    }
+                    ExitCode::FAILURE
+                }
+                Err(e) => database_error(&e, use_json),
+            }
+        }
+    }
+}
+
+/// `jfp collection export <name>`: the collection's prompts, in order
+pub fn export(
+    name: Option<&str>,
+    format: &str,
+    output_dir: Option<String>,
+    stdout: bool,
+    use_json: bool,
+) -> ExitCode {
+    if format != "md" && format != "skill" {
+        if use_json {
+            println!(r#"{{"error": "invalid_format", "format": "{}"}}"#, format);
+        } else {
+            eprintln!("Invalid format '{}'. Use 'md' or 'skill'", format);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    let Some(name) = name.filter(|name| !name.trim().is_empty()) else {
+        if use_json {
+            println!(r#"{{"error": "missing_name"}}"#);
+        } else {
+            eprintln!("Error: Collection name required for `jfp collection export`");
+        }
+        return ExitCode::FAILURE;
+    };
+
+    let Some((db, sync)) = open(use_json) else {
+        return ExitCode::FAILURE;
+    };
+    sync.report(use_json);
+
+    let Some(collection) = load(&db, name, use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    let mut prompts = Vec::new();
+    let mut missing = Vec::new();
+    for id in &collection.prompt_ids {
+        match db.get_prompt(id) {
+            Ok(Some(prompt)) => prompts.push(prompt),
+            Ok(None) => missing.push(id.clone()),
+            Err(e) => return database_error(&e, use_json),
+        }
+    }
+    if !use_json {
+        for id in &missing {
+            eprintln!("Warning: Prompt '{}' not found, skipping", id);
+        }
+    }
+
+    if prompts.is_empty() {
+        if use_json {
+            println!(r#"{{"error": "no_prompts"}}"#);
+        } else {
+            eprintln!("No prompts to export");
+        }
+        return ExitCode::FAILURE;
+    }
+
+    // Names are validated at create, so the default directory is safe
+    let output_dir = match output_dir {
+        Some(dir) => Some(dir),
+        None if !stdout => Some(collection.name.clone()),
+        None => None,
+    };
+    let Some(exported) = export::write_prompts(&prompts, format, output_dir.as_deref(), stdout, use_json)
+    else {
+        return ExitCode::FAILURE;
+    };
+
+    if use_json {
+        print_json(&CollectionExportOutput {
+            collection: collection.name,
+            count: exported.len(),
+            exported,
+            format: format.to_string(),
+            output_dir,
+            missing,
+        })
+    } else {
+        if !stdout {
+            println!("\nExported {} prompt(s) from {}", exported.len(), collection.name);
+        }
+        ExitCode::SUCCESS
+    }
+}
+
+/// Collection names double as export directory names
+fn validate_name(name: &str) -> Result<(), String> {
+    export::build_safe_export_filename(name, "md")
+        .map(|_| ())
+        .map_err(|e| e.replacen("prompt id", "name", 1))
+}
+
+fn list(db: &Database, use_json: bool) -> ExitCode {
+    let collections = match db.collections() {
+        Ok(collections) => collections,
+        Err(e) => return database_error(&e, use_json),
+    };
+
+    if use_json {
+        let collections: Vec<CollectionSummary> = collections
+            .into_iter()
+            .map(|c| CollectionSummary {
+                name: c.name,
+                description: c.description,
+                prompt_count: c.prompt_ids.len(),
+                updated_at: c.updated_at,
+            })
+            .collect();
+        return print_json(&CollectionListOutput {
+            count: collections.len(),
+            collections,
+        });
+    }
+
+    if collections.is_empty() {
+        println!("No collections yet. Create one with `jfp collection create <name>`");
+        return ExitCode::SUCCESS;
+    }
+
+    for collection in &collections {
+        print!("{} ({} prompts)", collection.name, collection.prompt_ids.len());
+        if let Some(description) = &collection.description {
+            print!(" - {}", description);
+        }
+        println!();
+    }
+    ExitCode::SUCCESS
+}
+
+fn show(db: &Database, collection: Collection, use_json: bool) -> ExitCode {
+    let mut prompts = Vec::with_capacity(collection.prompt_ids.len());
+    for (index, id) in collection.prompt_ids.into_iter().enumerate() {
+        let title = match db.get_prompt(&id) {
+            Ok(prompt) => prompt.map(|p| p.title),
+            Err(e) => return database_error(&e, use_json),
+        };
+        prompts.push(CollectionEntry {
+            position: index + 1,
+            id,
+            title,
+        });
+    }
+
+    let output = CollectionShowOutput {
+        name: collection.name,
+        description: collection.description,
+        created_at: collection.created_at,
+        updated_at: collection.updated_at,
+        count: prompts.len(),
+        prompts,
+    };
+
+    if use_json {
+        return print_json(&output);
+    }
+
+    println!("# {}", output.name);
+    if let Some(description) = &output.description {
+        println!("{}", description);
+    }
+    println!();
+    if output.prompts.is_empty() {
+        println!("Empty. Add prompts with `jfp collection add {} <id>...`", output.name);
+    }
+    for entry in &output.prompts {
+        match &entry.title {
+            Some(title) => println!("{:>3}. {} - {}", entry.position, entry.id, title),
+            None => println!("{:>3}. {} (missing)", entry.position, entry.id),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn open(use_json: bool) -> Option<(Database, registry::SyncStatus)> {
+    // Open database, syncing from the registry when empty or stale
+    match registry::open_database() {
+        Ok(opened) => Some(opened),
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            None
+        }
+    }
+}
+
+fn load(db: &Database, name: &str, use_json: bool) -> Option<Collection> {
+    match db.collection(name) {
+        Ok(Some(collection)) => Some(collection),
+        Ok(None) => {
+            if use_json {
+                println!(r#"{{"error": "collection_not_found", "name": "{}"}}"#, name);
+            } else {
+                eprintln!("Collection not found: {}", name);
+            }
+            None
+        }
+        Err(e) => {
+            database_error(&e, use_json);
+            None
+        }
+    }
+}
+
+fn missing_ids(action: &str, use_json: bool) -> ExitCode {
+    if use_json {
+        println!(r#"{{"error": "missing_ids"}}"#);
+    } else {
+        eprintln!("Usage: jfp collection {} <name> <id>...", action);
+    }
+    ExitCode::FAILURE
+}
+
+fn print_change(
+    name: &str,
+    action: &str,
+    prompts: Vec<String>,
+    changed: bool,
+    use_json: bool,
+) -> ExitCode {
+    if use_json {
+        return print_json(&CollectionChangeOutput {
+            collection: name.to_string(),
+            action: action.to_string(),
+            prompts,
+            changed,
+        });
+    }
+
+    match (action, changed) {
+        ("created", _) => println!("Created collection {}", name),
+        ("deleted", _) => println!("Deleted collection {}", name),
+        ("moved", _) => println!("Moved {} in {}", prompts.join(", "), name),
+        (_, false) => println!("Collection {} unchanged", name),
+        (action, true) => println!(
+            "{} {} {} {}",
+            if action == "added" { "Added" } else { "Removed" },
+            prompts.join(", "),
+            if action == "added" { "to" } else { "from" },
+            name
+        ),
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_json<T: Serialize>(output: &T) -> ExitCode {
+    match serde_json::to_string_pretty(output) {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn database_error(e: &anyhow::Error, use_json: bool) -> ExitCode {
+    if use_json {
+        println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+    } else {
+        eprintln!("Error updating collection: {}", e);
+    }
+    ExitCode::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_collection_ordering() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let at = "2026-01-01T00:00:00Z";
+        assert!(db.create_collection("backend", Some("API work"), at)?);
+        assert!(!db.create_collection("backend", None, at)?);
+
+        let added = db.add_to_collection("backend", &ids(&["a", "b", "c", "a"]), at)?;
+        assert_eq!(added, ids(&["a", "b", "c"]));
+        assert!(db.add_to_collection("backend", &ids(&["b"]), at)?.is_empty());
+
+        assert!(db.move_in_collection("backend", "c", 0, at)?);
+        assert!(db.move_in_collection("backend", "a", 99, at)?);
+        assert!(!db.move_in_collection("backend", "zzz", 0, at)?);
+        let collection = db.collection("backend")?.expect("collection");
+        assert_eq!(collection.prompt_ids, ids(&["c", "b", "a"]));
+        assert_eq!(collection.description.as_deref(), Some("API work"));
+
+        // Removing renumbers, so appends land at the end
+        assert_eq!(db.remove_from_collection("backend", &ids(&["b", "x"]), at)?, ids(&["b"]));
+        db.add_to_collection("backend", &ids(&["d"]), at)?;
+        assert_eq!(db.collection("backend")?.expect("collection").prompt_ids, ids(&["c", "a", "d"]));
+
+        assert!(db.delete_collection("backend")?);
+        assert!(db.collection("backend")?.is_none());
+        assert!(db.collections()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_collection_names_are_safe_directories() {
+        assert!(validate_name("project-x_2").is_ok());
+        assert!(validate_name("../escape").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("..").is_err());
+    }
+}
@@ -11,15 +11,7 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-/// Get the config directory
-fn config_dir() -> Option<PathBuf> {
-    dirs::config_dir().map(|d| d.join("jfp"))
-}
-
-/// Get the config file path
-fn config_path() -> Option<PathBuf> {
-    config_dir().map(|d| d.join("config.toml"))
-}
+use crate::config::config_path;
 
 #[derive(Serialize)]
 struct ConfigOutput {
@@ -63,10 +55,10 @@ fn write_config_atomically(path: &PathBuf, content: &str) -> Result<(), std::io:
     temp_file.sync_all()?;
     fs::rename(&temp_path, path)?;
 
-    if let Some(parent) = path.parent() {
-        if let Ok(dir_handle) = fs::File::open(parent) {
-            let _ = dir_handle.sync_all();
-        }
+    if let Some(parent) = path.parent()
+        && let Ok(dir_handle) = fs::File::open(parent)
+    {
+        let _ = dir_handle.sync_all();
     }
 
     Ok(())
@@ -303,15 +295,15 @@ fn set_config(key: &str, value: &str, use_json: bool) -> ExitCode {
     };
 
     // Ensure config directory exists
-    if let Some(dir) = path.parent() {
-        if let Err(e) = fs::create_dir_all(dir) {
-            if use_json {
-                println!(r#"{{"error": "mkdir_error", "message": "{}"}}"#, e);
-            } else {
-                eprintln!("Error creating config directory: {}", e);
-            }
-            return ExitCode::FAILURE;
+    if let Some(dir) = path.parent()
+        && let Err(e) = fs::create_dir_all(dir)
+    {
+        if use_json {
+            println!(r#"{{"error": "mkdir_error", "message": "{}"}}"#, e);
+        } else {
+            eprintln!("Error creating config directory: {}", e);
         }
+        return ExitCode::FAILURE;
     }
 
     // Load existing config or create new
@@ -396,40 +388,6 @@ fn set_config(key: &str, value: &str, use_json: bool) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
-
-    use super::{parse_config_table, write_config_atomically};
-    use tempfile::tempdir;
-
-    #[test]
-    fn parse_config_table_accepts_table_root() {
-        let parsed = parse_config_table("foo = 1\nbar = \"baz\"").expect("table root should parse");
-        assert_eq!(parsed.get("foo").and_then(|v| v.as_integer()), Some(1));
-        assert_eq!(parsed.get("bar").and_then(|v| v.as_str()), Some("baz"));
-    }
-
-    #[test]
-    fn parse_config_table_rejects_invalid_toml() {
-        let err = parse_config_table("foo = [").expect_err("invalid TOML should fail");
-        assert!(err.starts_with("parse_error: "));
-    }
-
-    #[test]
-    fn write_config_atomically_replaces_existing_file() {
-        let dir = tempdir().expect("temp dir");
-        let path = dir.path().join("config.toml");
-        fs::write(&path, "foo = 1\n").expect("seed config");
-
-        write_config_atomically(&path, "foo = 2\nbar = true\n").expect("atomic write should succeed");
-
-        let content = fs::read_to_string(&path).expect("read updated config");
-        assert_eq!(content, "foo = 2\nbar = true\n");
-    }
-
-}
-
 fn reset_config(use_json: bool) -> ExitCode {
     let path = match config_path() {
         Some(p) => p,
@@ -443,15 +401,15 @@ fn reset_config(use_json: bool) -> ExitCode {
         }
     };
 
-    if path.exists() {
-        if let Err(e) = fs::remove_file(&path) {
-            if use_json {
-                println!(r#"{{"error": "remove_error", "message": "{}"}}"#, e);
-            } else {
-                eprintln!("Error removing config: {}", e);
-            }
-            return ExitCode::FAILURE;
+    if path.exists()
+        && let Err(e) = fs::remove_file(&path)
+    {
+        if use_json {
+            println!(r#"{{"error": "remove_error", "message": "{}"}}"#, e);
+        } else {
+            eprintln!("Error removing config: {}", e);
         }
+        return ExitCode::FAILURE;
     }
 
     if use_json {
@@ -470,3 +428,36 @@ fn reset_config(use_json: bool) -> ExitCode {
 
     ExitCode::SUCCESS
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{parse_config_table, write_config_atomically};
+    use tempfile::tempdir;
+
+    #[test]
+    fn parse_config_table_accepts_table_root() {
+        let parsed = parse_config_table("foo = 1\nbar = \"baz\"").expect("table root should parse");
+        assert_eq!(parsed.get("foo").and_then(|v| v.as_integer()), Some(1));
+        assert_eq!(parsed.get("bar").and_then(|v| v.as_str()), Some("baz"));
+    }
+
+    #[test]
+    fn parse_config_table_rejects_invalid_toml() {
+        let err = parse_config_table("foo = [").expect_err("invalid TOML should fail");
+        assert!(err.starts_with("parse_error: "));
+    }
+
+    #[test]
+    fn write_config_atomically_replaces_existing_file() {
+        let dir = tempdir().expect("temp dir");
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "foo = 1\n").expect("seed config");
+
+        write_config_atomically(&path, "foo = 2\nbar = true\n").expect("atomic write should succeed");
+
+        let content = fs::read_to_string(&path).expect("read updated config");
+        assert_eq!(content, "foo = 2\nbar = true\n");
+    }
+}
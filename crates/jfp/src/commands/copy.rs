@@ -4,14 +4,16 @@
 //! - Copies prompt content to clipboard
 //! - Optional --fill for interactive variable substitution
 //! - Uses platform clipboard tools
+//! - Unknown ids get "did you mean" suggestions
 
 use std::io::{self, Write};
 use std::process::{Command, ExitCode, Stdio};
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::commands::show::{print_did_you_mean, similar_ids};
+use crate::registry;
+use crate::storage::UsageAction;
 use crate::types::Prompt;
 
 #[derive(Serialize)]
@@ -32,9 +34,9 @@ struct FilledVariable {
 }
 
 pub fn run(id: &str, fill: bool, use_json: bool) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -45,23 +47,22 @@ pub fn run(id: &str, fill: bool, use_json: bool) -> ExitCode {
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Get prompt
     let prompt = match db.get_prompt(id) {
         Ok(Some(p)) => p,
         Ok(None) => {
+            let suggestions = similar_ids(&db, id);
             if use_json {
-                println!(r#"{{"error": "not_found", "id": "{}"}}"#, id);
+                let mut output = serde_json::json!({ "error": "not_found", "id": id });
+                if !suggestions.is_empty() {
+                    output["suggestions"] = suggestions.into();
+                }
+                println!("{}", output);
             } else {
                 eprintln!("Prompt '{}' not found.", id);
+                print_did_you_mean(&suggestions);
             }
             return ExitCode::FAILURE;
         }
@@ -82,6 +83,8 @@ pub fn run(id: &str, fill: bool, use_json: bool) -> ExitCode {
         (prompt.content.clone(), None)
     };
 
+    super::stats::record_usage(&db, &prompt.id, UsageAction::Copy);
+
     // Copy to clipboard
     let copied = match copy_to_clipboard(&content) {
         Ok(()) => true,
@@ -0,0 +1,348 @@
+//! Db command implementation
+//!
+//! Database maintenance without the sqlite3 binary:
+//! - `jfp db check`: `PRAGMA integrity_check` plus search index and foreign
+//!   key consistency
+//! - `jfp db vacuum`, `jfp db analyze`, `jfp db rebuild-fts` (other commands
+//!   repair missing or extra prompt index rows when they open the database;
+//!   `jfp db` opens without repairing so `check` can report them)
+//! - `jfp db reset --yes`: drop the registry-derived tables (after an
+//!   automatic backup) and keep stars, notes, collections, usage and
+//!   history; the next command re-seeds the prompts
+//! - `jfp db path`, `jfp db size`
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use crate::commands::backup::{write_backup, DEFAULT_KEEP};
+use crate::registry;
+use crate::storage::{db_path, Database};
+
+#[derive(Serialize)]
+struct PathOutput {
+    path: String,
+    exists: bool,
+}
+
+#[derive(Serialize)]
+struct SizeOutput {
+    path: String,
+    file_bytes: u64,
+    wal_bytes: u64,
+    used_bytes: i64,
+    free_bytes: i64,
+    tables: Vec<TableRows>,
+}
+
+#[derive(Serialize)]
+struct TableRows {
+    table: String,
+    rows: i64,
+}
+
+#[derive(Serialize)]
+struct CheckOutput {
+    ok: bool,
+    integrity: bool,
+    issues: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct VacuumOutput {
+    vacuumed: bool,
+    bytes_before: u64,
+    bytes_after: u64,
+}
+
+#[derive(Serialize)]
+struct RebuildOutput {
+    rebuilt: bool,
+    prompts: usize,
+    notes: usize,
+}
+
+#[derive(Serialize)]
+struct ResetOutput {
+    reset: bool,
+    backup: String,
+    /// Local data left in place
+    kept: &'static [&'static str],
+}
+
+/// What `jfp db reset` keeps (see `storage::DROP_REGISTRY_TABLES`)
+const RESET_KEEPS: &[&str] = &["stars", "notes", "collections", "usage", "history"];
+
+pub fn run(action: &str, yes: bool, use_json: bool) -> ExitCode {
+    match action {
+        "path" => path(use_json),
+        "size" => with_db(use_json, size),
+        "check" => with_db(use_json, check),
+        "vacuum" => with_db(use_json, vacuum),
+        "analyze" => with_db(use_json, |db, use_json| match db.analyze() {
+            Ok(()) => print_json_or(&serde_json::json!({ "analyzed": true }), use_json, || {
+                println!("Analyzed {}", db_path().display())
+            }),
+            Err(e) => database_error(&e, use_json),
+        }),
+        "rebuild-fts" => with_db(use_json, |db, use_json| match db.rebuild_fts() {
+            Ok((prompts, notes)) => {
+                let output = RebuildOutput {
+                    rebuilt: true,
+                    prompts,
+                    notes,
+                };
+                print_json_or(&output, use_json, || {
+                    println!("Rebuilt search indexes: {} prompts, {} notes", prompts, notes)
+                })
+            }
+            Err(e) => database_error(&e, use_json),
+        }),
+        "reset" => {
+            if !yes {
+                if use_json {
+                    println!(r#"{{"error": "confirmation_required"}}"#);
+                } else {
+                    eprintln!(
+                        "This deletes the stored prompts, bundles and search indexes in {}.",
+                        db_path().display()
+                    );
+                    eprintln!("Stars, notes, collections, usage and history are kept.");
+                    eprintln!("An automatic backup is taken first. Re-run with --yes to continue.");
+                }
+                return ExitCode::FAILURE;
+            }
+            with_db(use_json, reset)
+        }
+        _ => {
+            if use_json {
+                println!(r#"{{"error": "invalid_action", "action": "{}"}}"#, action);
+            } else {
+                eprintln!(
+                    "Invalid action: {}. Use: check, vacuum, analyze, rebuild-fts, reset, path, size",
+                    action
+                );
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Open the database without syncing or index repair and run `action` on it
+fn with_db(use_json: bool, action: impl FnOnce(&mut Database, bool) -> ExitCode) -> ExitCode {
+    match Database::open_for_maintenance() {
+        Ok(mut db) => action(&mut db, use_json),
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {:#}", e);
+                eprintln!("See RECOVERY_RUNBOOK.md, Scenario 1: Database Corruption");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn path(use_json: bool) -> ExitCode {
+    let path = db_path();
+    let output = PathOutput {
+        path: path.display().to_string(),
+        exists: path.exists(),
+    };
+    print_json_or(&output, use_json, || println!("{}", output.path))
+}
+
+fn size(db: &mut Database, use_json: bool) -> ExitCode {
+    let path = db_path();
+    let (used_bytes, free_bytes) = match db.page_usage() {
+        Ok(usage) => usage,
+        Err(e) => return database_error(&e, use_json),
+    };
+    let tables = match db.table_counts() {
+        Ok(counts) => counts
+            .into_iter()
+            .map(|(table, rows)| TableRows {
+                table: table.to_string(),
+                rows,
+            })
+            .collect(),
+        Err(e) => return database_error(&e, use_json),
+    };
+
+    let output = SizeOutput {
+        path: path.display().to_string(),
+        file_bytes: file_size(&path),
+        wal_bytes: file_size(&path.with_extension("db-wal")),
+        used_bytes,
+        free_bytes,
+        tables,
+    };
+
+    print_json_or(&output, use_json, || {
+        println!("{}", output.path);
+        println!(
+            "  File: {}  WAL: {}  Free: {}",
+            human_bytes(output.file_bytes),
+            human_bytes(output.wal_bytes),
+            human_bytes(output.free_bytes.max(0) as u64)
+        );
+        println!();
+        for table in &output.tables {
+            println!("  {:<20} {:>8}", table.table, table.rows);
+        }
+    })
+}
+
+fn check(db: &mut Database, use_json: bool) -> ExitCode {
+    let integrity = match db.integrity_check() {
+        Ok(ok) => ok,
+        Err(e) => return database_error(&e, use_json),
+    };
+    let mut issues = match db.consistency_issues() {
+        Ok(issues) => issues,
+        Err(e) => return database_error(&e, use_json),
+    };
+    if !integrity {
+        issues.insert(0, "PRAGMA integrity_check failed".to_string());
+    }
+
+    let output = CheckOutput {
+        ok: issues.is_empty(),
+        integrity,
+        issues,
+    };
+
+    if use_json {
+        let code = print_json_or(&output, true, || {});
+        return if output.ok { code } else { ExitCode::FAILURE };
+    }
+
+    if output.ok {
+        println!("Database OK: {}", db_path().display());
+        return ExitCode::SUCCESS;
+    }
+    for issue in &output.issues {
+        println!("  ✗ {}", issue);
+    }
+    if output.integrity {
+        println!("\nRun `jfp db rebuild-fts` to rebuild the search indexes.");
+    } else {
+        println!("\nSee RECOVERY_RUNBOOK.md, Scenario 1: Database Corruption.");
+    }
+    ExitCode::FAILURE
+}
+
+fn vacuum(db: &mut Database, use_json: bool) -> ExitCode {
+    let path = db_path();
+    let bytes_before = file_size(&path) + file_size(&path.with_extension("db-wal"));
+    if let Err(e) = db.vacuum() {
+        return database_error(&e, use_json);
+    }
+    let bytes_after = file_size(&path) + file_size(&path.with_extension("db-wal"));
+
+    let output = VacuumOutput {
+        vacuumed: true,
+        bytes_before,
+        bytes_after,
+    };
+    print_json_or(&output, use_json, || {
+        println!(
+            "Vacuumed {}: {} -> {}",
+            path.display(),
+            human_bytes(bytes_before),
+            human_bytes(bytes_after)
+        )
+    })
+}
+
+fn reset(db: &mut Database, use_json: bool) -> ExitCode {
+    // Nothing else may write while the tables are gone
+    let _lock = match registry::hold_refresh_lock(db) {
+        Ok(lock) => lock,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "refresh_locked", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let backup = match write_backup(db, None, DEFAULT_KEEP) {
+        Ok((backup, _, _)) => backup.display().to_string(),
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "backup_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error backing up current database: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Err(e) = db.reset() {
+        return database_error(&e, use_json);
+    }
+
+    let output = ResetOutput {
+        reset: true,
+        backup,
+        kept: RESET_KEEPS,
+    };
+    print_json_or(&output, use_json, || {
+        println!("Reset {}", db_path().display());
+        println!("Backup of the old data: {}", output.backup);
+        println!("Kept: {}", RESET_KEEPS.join(", "));
+        println!("The next command re-seeds registry prompts; `jfp restore <backup>` brings back");
+        println!("saved and local prompts (the backup holds prompts, stars and notes).");
+    })
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Print `output` as JSON, or run `human` to print it for people
+fn print_json_or<T: Serialize>(output: &T, use_json: bool, human: impl FnOnce()) -> ExitCode {
+    if !use_json {
+        human();
+        return ExitCode::SUCCESS;
+    }
+    match serde_json::to_string_pretty(output) {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn database_error(e: &anyhow::Error, use_json: bool) -> ExitCode {
+    if use_json {
+        println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+    } else {
+        eprintln!("Database error: {:#}", e);
+    }
+    ExitCode::FAILURE
+}
@@ -0,0 +1,262 @@
+//! Diff command implementation
+//!
+//! Prints a unified diff of a prompt's content between two revisions:
+//! - `jfp diff <id>` compares the latest revision with the one before it
+//! - `jfp diff <id> <rev-a>` compares `rev-a` with the latest revision
+//! - `jfp diff <id> <rev-a> <rev-b>` compares two specific revisions
+
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use super::history::load_revisions;
+use crate::registry;
+use crate::storage::PromptRevision;
+
+/// Lines of context around each change
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Serialize)]
+struct RevisionRef {
+    revision: i64,
+    recorded_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry_version: Option<String>,
+}
+
+impl From<&PromptRevision> for RevisionRef {
+    fn from(rev: &PromptRevision) -> Self {
+        Self {
+            revision: rev.revision,
+            recorded_at: rev.recorded_at.clone(),
+            version: rev.version.clone(),
+            registry_version: rev.registry_version.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DiffOutput {
+    id: String,
+    from: RevisionRef,
+    to: RevisionRef,
+    changed: bool,
+    diff: String,
+}
+
+pub fn run(id: &str, rev_a: Option<i64>, rev_b: Option<i64>, use_json: bool) -> ExitCode {
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(use_json);
+
+    let (id, revisions) = match load_revisions(&db, id) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error loading history: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(latest) = revisions.last() else {
+        if use_json {
+            println!(r#"{{"error": "not_found"}}"#);
+        } else {
+            eprintln!("No history for prompt: {}", id);
+        }
+        return ExitCode::FAILURE;
+    };
+
+    let (a, b) = match (rev_a, rev_b) {
+        (Some(a), Some(b)) => (a, b),
+        (Some(a), None) => (a, latest.revision),
+        _ => ((latest.revision - 1).max(1), latest.revision),
+    };
+
+    let find = |revision: i64| revisions.iter().find(|r| r.revision == revision);
+    let (from, to) = match (find(a), find(b)) {
+        (Some(from), Some(to)) => (from, to),
+        (from, _) => {
+            let missing = if from.is_none() { a } else { b };
+            if use_json {
+                println!(
+                    r#"{{"error": "revision_not_found", "revision": {}, "latest": {}}}"#,
+                    missing, latest.revision
+                );
+            } else {
+                eprintln!(
+                    "Revision {} of {} not found (revisions 1-{}; see 'jfp history {}')",
+                    missing, id, latest.revision, id
+                );
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let diff = unified_diff(
+        &from.content,
+        &to.content,
+        &format!("{}@r{}", id, from.revision),
+        &format!("{}@r{}", id, to.revision),
+    );
+
+    let output = DiffOutput {
+        id,
+        from: RevisionRef::from(from),
+        to: RevisionRef::from(to),
+        changed: !diff.is_empty(),
+        diff,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if output.changed {
+        print!("{}", output.diff);
+    } else {
+        println!(
+            "No content changes between r{} and r{} of {}",
+            output.from.revision, output.to.revision, output.id
+        );
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// A diffed line: whether it is in the old text, the new text, or both
+#[derive(Clone, Copy, PartialEq)]
+enum Line<'a> {
+    Both(&'a str),
+    Old(&'a str),
+    New(&'a str),
+}
+
+/// Unified diff (`---`/`+++` headers, `@@` hunks) of two texts, or an empty
+/// string if they are the same
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines: Vec<Line> = diff::slice(&old_lines, &new_lines)
+        .into_iter()
+        .map(|line| match line {
+            diff::Result::Both(text, _) => Line::Both(text),
+            diff::Result::Left(text) => Line::Old(text),
+            diff::Result::Right(text) => Line::New(text),
+        })
+        .collect();
+
+    let changes: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Both(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Group changes whose context would overlap into one hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + CONTEXT_LINES + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    // Line numbers (0-based) of the next old and new line
+    let (mut old_line, mut new_line, mut pos) = (0usize, 0usize, 0usize);
+
+    for (start, end) in hunks {
+        for line in &lines[pos..start] {
+            match line {
+                Line::Both(_) => {
+                    old_line += 1;
+                    new_line += 1;
+                }
+                Line::Old(_) => old_line += 1,
+                Line::New(_) => new_line += 1,
+            }
+        }
+
+        let hunk = &lines[start..end];
+        let old_len = hunk.iter().filter(|l| !matches!(l, Line::New(_))).count();
+        let new_len = hunk.iter().filter(|l| !matches!(l, Line::Old(_))).count();
+        // An empty range is numbered by the line before it
+        let old_start = if old_len == 0 { old_line } else { old_line + 1 };
+        let new_start = if new_len == 0 { new_line } else { new_line + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_len, new_start, new_len
+        ));
+
+        for line in hunk {
+            let (prefix, text) = match line {
+                Line::Both(text) => (' ', text),
+                Line::Old(text) => ('-', text),
+                Line::New(text) => ('+', text),
+            };
+            out.push(prefix);
+            out.push_str(text);
+            out.push('\n');
+        }
+
+        old_line += old_len;
+        new_line += new_len;
+        pos = end;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_groups_changes_into_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+
+        let diff = unified_diff(old, new, "p@r1", "p@r2");
+
+        assert_eq!(
+            diff,
+            "--- p@r1\n+++ p@r2\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n"
+        );
+        assert!(unified_diff(old, old, "a", "b").is_empty());
+    }
+
+    #[test]
+    fn unified_diff_numbers_insertions_into_empty_text() {
+        assert_eq!(
+            unified_diff("", "new line", "a", "b"),
+            "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+new line\n"
+        );
+    }
+}
@@ -3,11 +3,15 @@
 //! From EXISTING_JFP_STRUCTURE.md section 17 (doctor):
 //! - Runs environment diagnostics
 //! - Checks database, config, network connectivity, etc.
+//! - Reports the effective network setup (proxy, CA bundle, retries and
+//!   registry header names) and whether an HTTP client can be built from it
 
 use std::process::ExitCode;
 
 use serde::Serialize;
 
+use crate::config;
+use crate::http::{self, HttpClient};
 use crate::storage::Database;
 
 #[derive(Serialize)]
@@ -42,22 +46,20 @@ impl std::fmt::Display for CheckStatus {
 }
 
 pub fn run(use_json: bool) -> ExitCode {
-    let mut checks = Vec::new();
-
-    // Check 1: Database
-    checks.push(check_database());
-
-    // Check 2: Bundled prompts
-    checks.push(check_bundled_prompts());
-
-    // Check 3: Data directory
-    checks.push(check_data_dir());
-
-    // Check 4: Clipboard tools
-    checks.push(check_clipboard());
-
-    // Check 5: Browser opener
-    checks.push(check_browser_opener());
+    let checks = vec![
+        // Check 1: Database
+        check_database(),
+        // Check 2: Bundled prompts
+        check_bundled_prompts(),
+        // Check 3: Data directory
+        check_data_dir(),
+        // Check 4: Clipboard tools
+        check_clipboard(),
+        // Check 5: Browser opener
+        check_browser_opener(),
+        // Check 6: Network configuration
+        check_network(),
+    ];
 
     let all_passed = checks.iter().all(|c| c.status == CheckStatus::Pass);
 
@@ -222,3 +224,73 @@ fn check_browser_opener() -> Check {
         },
     }
 }
+
+fn check_network() -> Check {
+    let config = config::load_config();
+    let network = &config.network;
+    let mut parts = Vec::new();
+
+    let proxy = match network.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(proxy) => Some(format!("proxy {} (config)", http::redact_proxy(proxy))),
+        None => http::PROXY_ENV_VARS
+            .iter()
+            .step_by(2)
+            .find_map(|var| http::proxy_env(var))
+            .map(|(var, url)| format!("proxy {} (${})", http::redact_proxy(&url), var)),
+    };
+    parts.push(proxy.unwrap_or_else(|| "no proxy".to_string()));
+
+    let no_proxy = network
+        .no_proxy
+        .clone()
+        .or_else(|| http::proxy_env("NO_PROXY").map(|(_, list)| list));
+    if let Some(list) = no_proxy {
+        parts.push(format!("no proxy for {}", list));
+    }
+
+    if let Some(path) = &network.ca_bundle {
+        match http::load_ca_bundle(path) {
+            Ok(certs) => parts.push(format!("CA bundle {} ({} certs)", path.display(), certs.len())),
+            Err(e) => {
+                return Check {
+                    name: "Network".to_string(),
+                    status: CheckStatus::Fail,
+                    message: format!("{:#}", e),
+                };
+            }
+        }
+    }
+
+    parts.push(format!(
+        "{} retries ({}-{}ms backoff)",
+        network.retries, network.retry_base_ms, network.retry_max_ms
+    ));
+
+    // Header values may be secrets: only names are shown
+    let registries = std::iter::once(("default", &config.registry.headers)).chain(
+        config
+            .registries
+            .iter()
+            .filter(|r| r.enabled)
+            .map(|r| (r.name.as_str(), &r.headers)),
+    );
+    for (name, headers) in registries {
+        if let Err(e) = HttpClient::new(network, std::time::Duration::from_secs(1), headers) {
+            return Check {
+                name: "Network".to_string(),
+                status: CheckStatus::Fail,
+                message: format!("Registry '{}': {:#}", name, e),
+            };
+        }
+        if !headers.is_empty() {
+            let names: Vec<&str> = headers.keys().map(String::as_str).collect();
+            parts.push(format!("{} headers: {}", name, names.join(", ")));
+        }
+    }
+
+    Check {
+        name: "Network".to_string(),
+        status: CheckStatus::Pass,
+        message: parts.join("; "),
+    }
+}
@@ -11,8 +11,8 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry;
+use crate::storage::UsageAction;
 use crate::types::Prompt;
 
 #[derive(Serialize)]
@@ -25,13 +25,13 @@ struct ExportOutput {
 }
 
 #[derive(Serialize)]
-struct ExportedPrompt {
-    id: String,
+pub(crate) struct ExportedPrompt {
+    pub id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    file: Option<String>,
+    pub file: Option<String>,
 }
 
-fn build_safe_export_filename(prompt_id: &str, ext: &str) -> Result<String, String> {
+pub(crate) fn build_safe_export_filename(prompt_id: &str, ext: &str) -> Result<String, String> {
     let id = prompt_id.trim();
 
     if id.is_empty() {
@@ -111,9 +111,9 @@ pub fn run(
         return ExitCode::FAILURE;
     }
 
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -124,19 +124,13 @@ pub fn run(
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Get prompts to export
-    let prompts: Vec<Prompt> = if ids.is_empty() || (ids.len() == 1 && ids[0] == "all") {
+    let export_all = ids.is_empty() || (ids.len() == 1 && ids[0] == "all");
+    let prompts: Vec<Prompt> = if export_all {
         // Export all
-        match db.list_prompts_filtered(None, None, false) {
+        match db.list_prompts_filtered(None, None, false, false, None) {
             Ok(p) => p,
             Err(e) => {
                 if use_json {
@@ -181,12 +175,55 @@ pub fn run(
         return ExitCode::FAILURE;
     }
 
-    // Export
+    let Some(exported) = write_prompts(&prompts, format, output_dir.as_deref(), stdout, use_json)
+    else {
+        return ExitCode::FAILURE;
+    };
+
+    // Bulk exports say nothing about which prompts are in use
+    if !export_all {
+        for prompt in &exported {
+            super::stats::record_usage(&db, &prompt.id, UsageAction::Export);
+        }
+    }
+
+    if use_json {
+        let output = ExportOutput {
+            count: exported.len(),
+            exported,
+            format: format.to_string(),
+            output_dir,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if !stdout {
+        println!("\nExported {} prompt(s)", exported.len());
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Write prompts to `output_dir` (default: the current directory) or stdout
+///
+/// Unsafe ids and failed writes are reported and skipped. `None` means the
+/// output directory could not be used; the error has been reported.
+pub(crate) fn write_prompts(
+    prompts: &[Prompt],
+    format: &str,
+    output_dir: Option<&str>,
+    stdout: bool,
+    use_json: bool,
+) -> Option<Vec<ExportedPrompt>> {
     let mut exported = Vec::new();
 
     if stdout {
         // Write to stdout
-        for prompt in &prompts {
+        for prompt in prompts {
             let content = format_prompt(prompt, format);
             if !use_json {
                 println!("{}", content);
@@ -201,19 +238,19 @@ pub fn run(
         }
     } else {
         // Write to files
-        let dir = output_dir.as_deref().unwrap_or(".");
+        let dir = output_dir.unwrap_or(".");
         let dir_path = Path::new(dir);
 
         // Create directory if needed
-        if !dir_path.exists() {
-            if let Err(e) = fs::create_dir_all(dir_path) {
-                if use_json {
-                    println!(r#"{{"error": "mkdir_error", "message": "{}"}}"#, e);
-                } else {
-                    eprintln!("Error creating directory: {}", e);
-                }
-                return ExitCode::FAILURE;
+        if !dir_path.exists()
+            && let Err(e) = fs::create_dir_all(dir_path)
+        {
+            if use_json {
+                println!(r#"{{"error": "mkdir_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error creating directory: {}", e);
             }
+            return None;
         }
 
         let canonical_dir = match fs::canonicalize(dir_path) {
@@ -224,13 +261,13 @@ pub fn run(
                 } else {
                     eprintln!("Error resolving output directory: {}", e);
                 }
-                return ExitCode::FAILURE;
+                return None;
             }
         };
 
-        for prompt in &prompts {
-            let ext = if format == "skill" { "md" } else { "md" };
-            let filename = match build_safe_export_filename(&prompt.id, ext) {
+        for prompt in prompts {
+            // Both formats are markdown on disk
+            let filename = match build_safe_export_filename(&prompt.id, "md") {
                 Ok(name) => name,
                 Err(err) => {
                     if use_json {
@@ -287,29 +324,11 @@ pub fn run(
         }
     }
 
-    if use_json {
-        let output = ExportOutput {
-            count: exported.len(),
-            exported,
-            format: format.to_string(),
-            output_dir,
-        };
-        match serde_json::to_string_pretty(&output) {
-            Ok(json) => println!("{}", json),
-            Err(e) => {
-                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
-                return ExitCode::FAILURE;
-            }
-        }
-    } else if !stdout {
-        println!("\nExported {} prompt(s)", exported.len());
-    }
-
-    ExitCode::SUCCESS
+    Some(exported)
 }
 
 /// Format a prompt for export
-fn format_prompt(prompt: &Prompt, format: &str) -> String {
+pub(crate) fn format_prompt(prompt: &Prompt, format: &str) -> String {
     let mut output = String::new();
 
     match format {
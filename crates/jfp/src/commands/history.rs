@@ -0,0 +1,202 @@
+//! History command implementation
+//!
+//! Lists the recorded revisions of a prompt, newest first:
+//! - A revision is stored whenever a sync, restore or local edit changes
+//!   the prompt's content or metadata (`prompt_revisions`)
+//! - Revisions outlive prompts removed from the registry
+//! - JSON output includes each revision's full content
+
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use crate::registry;
+use crate::storage::{Database, PromptRevision};
+
+#[derive(Serialize)]
+struct HistoryEntry {
+    #[serde(flatten)]
+    revision: PromptRevision,
+    /// Fields that differ from the previous revision
+    changed: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct HistoryOutput {
+    id: String,
+    revisions: Vec<HistoryEntry>,
+    total: usize,
+}
+
+pub fn run(id: &str, use_json: bool) -> ExitCode {
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(use_json);
+
+    let (id, revisions) = match load_revisions(&db, id) {
+        Ok((_, revisions)) if revisions.is_empty() => {
+            if use_json {
+                println!(r#"{{"error": "not_found"}}"#);
+            } else {
+                eprintln!("No history for prompt: {}", id);
+            }
+            return ExitCode::FAILURE;
+        }
+        Ok(loaded) => loaded,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error loading history: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut entries: Vec<HistoryEntry> = Vec::with_capacity(revisions.len());
+    for revision in revisions {
+        let changed = entries
+            .last()
+            .map(|previous| changed_fields(&previous.revision, &revision))
+            .unwrap_or_default();
+        entries.push(HistoryEntry { revision, changed });
+    }
+    entries.reverse();
+
+    let output = HistoryOutput {
+        id,
+        total: entries.len(),
+        revisions: entries,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        println!("History of {} ({} revisions):\n", output.id, output.total);
+        for entry in &output.revisions {
+            let rev = &entry.revision;
+            let registry = rev
+                .registry_version
+                .as_deref()
+                .map(|v| format!("registry {}", v))
+                .unwrap_or_default();
+            let changed = if entry.changed.is_empty() {
+                "initial".to_string()
+            } else {
+                entry.changed.join(", ")
+            };
+            let recorded = rev.recorded_at.get(..19).unwrap_or(&rev.recorded_at);
+            println!(
+                "  r{:<4} {:<20} {:<16} {:<30} {}",
+                rev.revision,
+                recorded.replace('T', " "),
+                registry,
+                rev.title,
+                changed
+            );
+        }
+        if output.total > 1 {
+            let latest = output.revisions[0].revision.revision;
+            println!(
+                "\nRun 'jfp diff {} {} {}' to compare revisions",
+                output.id,
+                latest - 1,
+                latest
+            );
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Resolve `id` (including `<registry>/<id>`) and load its revisions
+///
+/// Ids of removed prompts are looked up as given.
+pub fn load_revisions(db: &Database, id: &str) -> anyhow::Result<(String, Vec<PromptRevision>)> {
+    let id = match db.get_prompt(id)? {
+        Some(prompt) => prompt.id,
+        None => id.to_string(),
+    };
+    let revisions = db.prompt_revisions(&id)?;
+    Ok((id, revisions))
+}
+
+/// Names of the fields that differ between two revisions
+fn changed_fields(old: &PromptRevision, new: &PromptRevision) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if old.content != new.content {
+        changed.push("content");
+    }
+    if old.title != new.title {
+        changed.push("title");
+    }
+    if old.description != new.description {
+        changed.push("description");
+    }
+    if old.category != new.category {
+        changed.push("category");
+    }
+    if old.tags != new.tags {
+        changed.push("tags");
+    }
+    if old.featured != new.featured {
+        changed.push("featured");
+    }
+    if old.version != new.version {
+        changed.push("version");
+    }
+    if old.author != new.author {
+        changed.push("author");
+    }
+    if old.source != new.source {
+        changed.push("source");
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::types::Prompt;
+
+    #[test]
+    fn history_records_only_distinct_versions() -> Result<()> {
+        let db = Database::in_memory()?;
+        let mut prompt = Prompt::new("p", "Prompt", "first wording");
+        db.upsert_prompt(&prompt)?;
+        db.upsert_prompt(&prompt)?;
+
+        prompt.content = "second wording".to_string();
+        prompt.version = Some("1.1.0".to_string());
+        db.upsert_prompt(&prompt)?;
+
+        let (id, revisions) = load_revisions(&db, "p")?;
+        assert_eq!(id, "p");
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].content, "first wording");
+        assert_eq!(
+            changed_fields(&revisions[0], &revisions[1]),
+            vec!["content", "version"]
+        );
+        Ok(())
+    }
+}
@@ -1,12 +1,14 @@
 //! Interactive mode fallback implementation.
 //!
-//! This provides a simple searchable terminal picker for bundled prompts
-//! without requiring the full Phase-5 TUI stack.
+//! This provides a simple searchable terminal picker over the synced prompt
+//! database without requiring the full Phase-5 TUI stack. Starred prompts
+//! are listed first.
 
+use std::collections::HashSet;
 use std::io::{self, IsTerminal, Write};
 use std::process::ExitCode;
 
-use crate::registry::bundled_prompts;
+use crate::registry;
 use crate::types::Prompt;
 
 fn prompt_line(prompt: &str) -> io::Result<String> {
@@ -44,6 +46,13 @@ fn filter_prompts<'a>(prompts: &'a [Prompt], query: &str) -> Vec<&'a Prompt> {
         .collect()
 }
 
+/// Starred prompts first, then alphabetical by title
+fn sort_starred_first(prompts: &mut [Prompt], starred: &HashSet<String>) {
+    prompts.sort_by_cached_key(|prompt| {
+        (!starred.contains(&prompt.id), prompt.title.to_lowercase())
+    });
+}
+
 fn render_prompt_details(prompt: &Prompt) -> String {
     let description = prompt
         .description
@@ -85,12 +94,35 @@ pub fn run(use_json: bool) -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    let mut prompts = bundled_prompts();
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            eprintln!("Error opening database: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(false);
+
+    let mut prompts = match db.list_prompts() {
+        Ok(prompts) => prompts,
+        Err(e) => {
+            eprintln!("Error listing prompts: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
     if prompts.is_empty() {
         eprintln!("No prompts available.");
         return ExitCode::FAILURE;
     }
-    prompts.sort_by_cached_key(|prompt| prompt.title.to_lowercase());
+    let starred: HashSet<String> = match db.stars() {
+        Ok(stars) => stars.into_iter().map(|star| star.id).collect(),
+        Err(e) => {
+            eprintln!("Error reading stars: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    sort_starred_first(&mut prompts, &starred);
 
     println!("jfp interactive mode");
     println!("Type a search query and pick a prompt by number.");
@@ -119,7 +151,8 @@ pub fn run(use_json: bool) -> ExitCode {
         println!("\nMatches:");
         for (index, prompt) in matches.iter().enumerate() {
             let category = prompt.category.as_deref().unwrap_or("uncategorized");
-            println!("{:>2}. {} [{}]", index + 1, prompt.title, category);
+            let star = if starred.contains(&prompt.id) { "* " } else { "" };
+            println!("{:>2}. {}{} [{}]", index + 1, star, prompt.title, category);
         }
 
         let selection = match prompt_line("\nSelect # (b = back, q = quit): ") {
@@ -169,7 +202,9 @@ pub fn run(use_json: bool) -> ExitCode {
 
 #[cfg(test)]
 mod tests {
-    use super::{filter_prompts, render_prompt_details};
+    use std::collections::HashSet;
+
+    use super::{filter_prompts, render_prompt_details, sort_starred_first};
     use crate::types::Prompt;
 
     fn sample_prompts() -> Vec<Prompt> {
@@ -210,4 +245,15 @@ mod tests {
         assert!(rendered.contains("Tags: brainstorm"));
         assert!(rendered.contains("Generate ideas"));
     }
+
+    #[test]
+    fn starred_prompts_sort_first() {
+        let mut prompts = sample_prompts();
+        sort_starred_first(&mut prompts, &HashSet::new());
+        assert_eq!(prompts[0].id, "debug-helper");
+
+        let starred = HashSet::from(["idea-wizard".to_string()]);
+        sort_starred_first(&mut prompts, &starred);
+        assert_eq!(prompts[0].id, "idea-wizard");
+    }
 }
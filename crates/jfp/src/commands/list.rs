@@ -3,14 +3,20 @@
 //! From EXISTING_JFP_STRUCTURE.md section 10 (list):
 //! - Options: --category, --tag, --mine, --saved, --json
 //! - JSON output: { prompts, count, offline?, offlineAge? }
+//! - Rust port reports the registry `source` and `last_sync` alongside `offline`
+//! - `--registry <name>` limits the list to one named registry (or `local`)
+//! - `--starred` lists only prompts starred with `jfp star`
+//! - `--sort recent|frequent` puts used prompts first (by last use or use
+//!   count, from local usage events); unused prompts follow in normal order
 
+use std::collections::HashMap;
 use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::PromptSummary;
+use crate::registry;
+use crate::storage::{UsageStat, DEFAULT_SOURCE};
+use crate::types::{Prompt, PromptSummary};
 
 /// JSON output for list command
 #[derive(Serialize)]
@@ -19,17 +25,33 @@ struct ListOutput {
     count: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offline: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_sync: Option<String>,
 }
 
 pub fn run(
     category: Option<String>,
     tag: Option<String>,
     featured: bool,
+    starred: bool,
+    registry_name: Option<String>,
+    sort: Option<&str>,
     use_json: bool,
 ) -> ExitCode {
-    // Try to open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    if let Some(sort) = sort.filter(|s| !matches!(*s, "recent" | "frequent")) {
+        if use_json {
+            println!(r#"{{"error": "invalid_sort", "sort": "{}"}}"#, sort);
+        } else {
+            eprintln!("Invalid sort '{}'. Use 'recent' or 'frequent'", sort);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -40,22 +62,15 @@ pub fn run(
         }
     };
 
-    // Check if database has prompts, if not, seed with bundled
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            if let Err(e) = db.upsert_prompt(prompt) {
-                eprintln!("Warning: Failed to seed prompt {}: {}", prompt.id, e);
-            }
-        }
-    }
+    sync.report(use_json);
 
     // List prompts with filters
-    let prompts = match db.list_prompts_filtered(
+    let mut prompts = match db.list_prompts_filtered(
         category.as_deref(),
         tag.as_deref(),
         featured,
+        starred,
+        registry_name.as_deref(),
     ) {
         Ok(p) => p,
         Err(e) => {
@@ -68,13 +83,29 @@ pub fn run(
         }
     };
 
+    if let Some(sort) = sort {
+        match db.usage_stats(None) {
+            Ok(stats) => sort_by_usage(&mut prompts, &stats, sort == "recent"),
+            Err(e) => {
+                if use_json {
+                    println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                } else {
+                    eprintln!("Error reading usage: {}", e);
+                }
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
     let count = prompts.len();
 
     if use_json {
         let output = ListOutput {
             prompts: prompts.iter().map(PromptSummary::from).collect(),
             count,
-            source: Some("local".to_string()),
+            source: Some(sync.source.as_str().to_string()),
+            offline: sync.offline().then_some(true),
+            last_sync: sync.last_sync.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -86,7 +117,14 @@ pub fn run(
     } else {
         if prompts.is_empty() {
             println!("No prompts found.");
-            if category.is_some() || tag.is_some() || featured {
+            if starred && category.is_none() && tag.is_none() && !featured {
+                println!("Star prompts with 'jfp star <id>'.");
+            } else if category.is_some()
+                || tag.is_some()
+                || featured
+                || starred
+                || registry_name.is_some()
+            {
                 println!("Try different filters or run without filters.");
             }
         } else {
@@ -97,6 +135,9 @@ pub fn run(
                 if prompt.featured {
                     print!(" [featured]");
                 }
+                if let Some(source) = prompt.source.as_deref().filter(|s| *s != DEFAULT_SOURCE) {
+                    print!(" ({})", source);
+                }
                 println!();
 
                 if let Some(desc) = &prompt.description {
@@ -121,3 +162,51 @@ pub fn run(
 
     ExitCode::SUCCESS
 }
+
+/// Order used prompts first, by last use (`recent`) or use count; the sort
+/// is stable so unused prompts keep their listed order
+fn sort_by_usage(prompts: &mut [Prompt], stats: &[UsageStat], recent: bool) {
+    let by_id: HashMap<&str, &UsageStat> = stats.iter().map(|s| (s.id.as_str(), s)).collect();
+    prompts.sort_by(|a, b| {
+        match (by_id.get(a.id.as_str()), by_id.get(b.id.as_str())) {
+            (Some(a), Some(b)) if recent => b.last_used.cmp(&a.last_used),
+            (Some(a), Some(b)) => b
+                .uses
+                .cmp(&a.uses)
+                .then_with(|| b.last_used.cmp(&a.last_used)),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(id: &str, uses: usize, last_used: &str) -> UsageStat {
+        UsageStat {
+            id: id.to_string(),
+            title: None,
+            uses,
+            last_used: last_used.to_string(),
+        }
+    }
+
+    #[test]
+    fn sort_by_usage_puts_used_prompts_first() {
+        let ids = |prompts: &[Prompt]| prompts.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        let mut prompts: Vec<Prompt> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|&id| Prompt::new(id, id, "body"))
+            .collect();
+        let stats = vec![stat("c", 5, "2026-01-01"), stat("b", 1, "2026-02-01")];
+
+        sort_by_usage(&mut prompts, &stats, false);
+        assert_eq!(ids(&prompts), vec!["c", "b", "a", "d"]);
+
+        sort_by_usage(&mut prompts, &stats, true);
+        assert_eq!(ids(&prompts), vec!["b", "c", "a", "d"]);
+    }
+}
@@ -1,22 +1,33 @@
 //! Command implementations
 
 pub mod about;
+pub mod backup;
 pub mod bundles;
 pub mod categories;
+pub mod collection;
 pub mod completion;
 pub mod config;
 pub mod copy;
+pub mod db;
+pub mod diff;
 pub mod doctor;
 pub mod export;
+pub mod history;
 pub mod interactive;
 pub mod list;
+pub mod note;
 pub mod open;
 pub mod random;
 pub mod refresh;
+pub mod registry;
 pub mod render;
+pub mod restore;
 pub mod search;
 pub mod show;
+pub mod star;
+pub mod stats;
 pub mod status;
 pub mod suggest;
 pub mod tags;
 pub mod update_cli;
+pub mod whatsnew;
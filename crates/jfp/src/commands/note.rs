@@ -0,0 +1,363 @@
+//! Note command implementation
+//!
+//! Private annotations on prompts, stored offline:
+//! - `jfp note add <id> [text]` (text from stdin or `$EDITOR` when omitted)
+//! - `jfp note list [id]`
+//! - `jfp note edit <id> [text] [--note N]` / `jfp note rm <id> [--note N]`
+//! - Notes live in `prompt_notes`, are shown by `jfp show`, matched by
+//!   `jfp search` and included in `jfp backup`
+
+use std::io::{self, IsTerminal, Read};
+use std::process::{Command, ExitCode};
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::registry;
+use crate::storage::{Database, Note};
+
+#[derive(Serialize)]
+struct NoteOutput {
+    action: String,
+    note: Note,
+}
+
+#[derive(Serialize)]
+struct NoteListOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    count: usize,
+    notes: Vec<Note>,
+}
+
+#[derive(Serialize)]
+struct NoteRemovedOutput {
+    action: String,
+    id: String,
+    note: i64,
+}
+
+pub fn run(
+    action: &str,
+    id: Option<&str>,
+    text: &[String],
+    note: Option<i64>,
+    use_json: bool,
+) -> ExitCode {
+    if !matches!(action, "add" | "list" | "edit" | "rm") {
+        if use_json {
+            println!(r#"{{"error": "invalid_action", "action": "{}"}}"#, action);
+        } else {
+            eprintln!("Invalid action: {}. Use: add, list, edit, rm", action);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    if action != "list" && id.is_none_or(|id| id.trim().is_empty()) {
+        if use_json {
+            println!(r#"{{"error": "invalid_id"}}"#);
+        } else {
+            eprintln!("Error: Prompt ID required for `jfp note {}`", action);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    // Open database, syncing from the registry when empty or stale
+    let (mut db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(use_json);
+
+    // Notes are keyed by the stored id; notes on a removed prompt stay reachable
+    let resolved = match id {
+        Some(id) => match db.get_prompt(id) {
+            Ok(Some(prompt)) => Some(prompt.id),
+            Ok(None) if action != "add" => Some(id.to_string()),
+            Ok(None) => {
+                if use_json {
+                    println!(r#"{{"error": "not_found"}}"#);
+                } else {
+                    eprintln!("Prompt not found: {}", id);
+                }
+                return ExitCode::FAILURE;
+            }
+            Err(e) => {
+                if use_json {
+                    println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                } else {
+                    eprintln!("Error loading prompt: {}", e);
+                }
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    match action {
+        "list" => list(&db, resolved.as_deref(), use_json),
+        "add" => {
+            let Some(body) = note_text(text, "", use_json) else {
+                return ExitCode::FAILURE;
+            };
+            let prompt_id = resolved.unwrap_or_default();
+            match db.add_note(&prompt_id, &body, &Utc::now().to_rfc3339()) {
+                Ok(note) => print_note("added", note, use_json),
+                Err(e) => database_error(&e, use_json),
+            }
+        }
+        _ => {
+            let prompt_id = resolved.unwrap_or_default();
+            let existing = match pick_note(&db, &prompt_id, note) {
+                Ok(existing) => existing,
+                Err(e) => {
+                    let code = if e.to_string().contains("has several notes") {
+                        "ambiguous_note"
+                    } else {
+                        "note_not_found"
+                    };
+                    if use_json {
+                        println!(r#"{{"error": "{}", "message": "{}"}}"#, code, e);
+                    } else {
+                        eprintln!("Error: {}", e);
+                    }
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            if action == "rm" {
+                return match db.delete_note(existing.id) {
+                    Ok(_) => {
+                        let output = NoteRemovedOutput {
+                            action: "removed".to_string(),
+                            id: prompt_id,
+                            note: existing.id,
+                        };
+                        if use_json {
+                            print_json(&output)
+                        } else {
+                            println!("Removed note {} from {}", output.note, output.id);
+                            ExitCode::SUCCESS
+                        }
+                    }
+                    Err(e) => database_error(&e, use_json),
+                };
+            }
+
+            let Some(body) = note_text(text, &existing.body, use_json) else {
+                return ExitCode::FAILURE;
+            };
+            let now = Utc::now().to_rfc3339();
+            match db.update_note(existing.id, &body, &now) {
+                Ok(_) => print_note(
+                    "edited",
+                    Note {
+                        body,
+                        updated_at: now,
+                        ..existing
+                    },
+                    use_json,
+                ),
+                Err(e) => database_error(&e, use_json),
+            }
+        }
+    }
+}
+
+fn list(db: &Database, id: Option<&str>, use_json: bool) -> ExitCode {
+    let notes = match db.notes(id) {
+        Ok(notes) => notes,
+        Err(e) => return database_error(&e, use_json),
+    };
+
+    if use_json {
+        return print_json(&NoteListOutput {
+            id: id.map(str::to_string),
+            count: notes.len(),
+            notes,
+        });
+    }
+
+    if notes.is_empty() {
+        match id {
+            Some(id) => println!("No notes on {}", id),
+            None => println!("No notes yet. Add one with `jfp note add <id> <text>`"),
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    for note in &notes {
+        println!("[{}] {} ({})", note.id, note.prompt_id, short_date(&note.updated_at));
+        for line in note.body.lines() {
+            println!("    {}", line);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Choose the note to edit or remove: `--note N`, or the prompt's only note
+fn pick_note(db: &Database, prompt_id: &str, note: Option<i64>) -> Result<Note> {
+    let notes = db.notes(Some(prompt_id))?;
+    match note {
+        Some(n) => notes
+            .into_iter()
+            .find(|existing| existing.id == n)
+            .with_context(|| format!("note {} not found on {}", n, prompt_id)),
+        None if notes.len() > 1 => bail!(
+            "{} has several notes; pick one with --note ({})",
+            prompt_id,
+            notes
+                .iter()
+                .map(|n| n.id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => notes
+            .into_iter()
+            .next()
+            .with_context(|| format!("{} has no notes", prompt_id)),
+    }
+}
+
+/// Note text from the arguments, else piped stdin, else `$VISUAL`/`$EDITOR`
+fn note_text(args: &[String], initial: &str, use_json: bool) -> Option<String> {
+    let text = if !args.is_empty() {
+        Ok(args.join(" "))
+    } else if !io::stdin().is_terminal() {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input).map(|_| input).map_err(Into::into)
+    } else {
+        edit_in_editor(initial)
+    };
+
+    match text {
+        Ok(text) if !text.trim().is_empty() => Some(text.trim_end().to_string()),
+        Ok(_) => {
+            if use_json {
+                println!(r#"{{"error": "empty_note"}}"#);
+            } else {
+                eprintln!("Error: Note text is empty");
+            }
+            None
+        }
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "editor_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error reading note: {}", e);
+            }
+            None
+        }
+    }
+}
+
+fn edit_in_editor(initial: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let path = std::env::temp_dir().join(format!("jfp-note-{}.md", std::process::id()));
+    std::fs::write(&path, initial)?;
+
+    // The editor may carry arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("empty $EDITOR")?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("failed to run {}", editor));
+    let text = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    if !status?.success() {
+        bail!("{} exited with an error", editor);
+    }
+    Ok(text?)
+}
+
+fn print_note(action: &str, note: Note, use_json: bool) -> ExitCode {
+    if use_json {
+        print_json(&NoteOutput {
+            action: action.to_string(),
+            note,
+        })
+    } else {
+        let verb = if action == "added" { "Added" } else { "Edited" };
+        println!("{} note {} on {}", verb, note.id, note.prompt_id);
+        ExitCode::SUCCESS
+    }
+}
+
+fn print_json<T: Serialize>(output: &T) -> ExitCode {
+    match serde_json::to_string_pretty(output) {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn database_error(e: &anyhow::Error, use_json: bool) -> ExitCode {
+    if use_json {
+        println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+    } else {
+        eprintln!("Error updating notes: {}", e);
+    }
+    ExitCode::FAILURE
+}
+
+/// `2026-01-28T12:00:00+00:00` → `2026-01-28 12:00`
+pub fn short_date(timestamp: &str) -> String {
+    timestamp.get(..16).unwrap_or(timestamp).replacen('T', " ", 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Prompt;
+
+    #[test]
+    fn test_note_crud_and_search() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        db.upsert_prompt(&Prompt::new("code-review", "Code Review", "Review this code"))?;
+        db.upsert_prompt(&Prompt::new("refactor", "Refactor", "Refactor this module"))?;
+
+        let first = db.add_note("code-review", "Works well with zanzibar diffs", "2026-01-01T00:00:00Z")?;
+        assert!(pick_note(&db, "code-review", None)?.id == first.id);
+
+        let second = db.add_note("code-review", "Second thought", "2026-01-02T00:00:00Z")?;
+        let err = pick_note(&db, "code-review", None).unwrap_err();
+        assert!(err.to_string().contains("several notes"));
+        assert_eq!(pick_note(&db, "code-review", Some(second.id))?.body, "Second thought");
+        assert!(pick_note(&db, "refactor", Some(second.id)).is_err());
+
+        // Note text is searchable and finds the prompt it belongs to
+        let results = db.search("zanzibar", 10, None, false)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, "code-review");
+
+        assert!(db.update_note(first.id, "Pairs with marrakesh reviews", "2026-01-03T00:00:00Z")?);
+        assert!(db.search("zanzibar", 10, None, false)?.is_empty());
+        assert_eq!(db.search("marrakesh", 10, None, false)?.len(), 1);
+
+        assert!(db.delete_note(first.id)?);
+        assert!(!db.delete_note(first.id)?);
+        assert!(db.search("marrakesh", 10, None, false)?.is_empty());
+        assert_eq!(db.notes(None)?, vec![second]);
+
+        Ok(())
+    }
+}
@@ -8,8 +8,7 @@ use std::process::{Command, ExitCode};
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry;
 
 #[derive(Serialize)]
 struct OpenOutput {
@@ -18,9 +17,9 @@ struct OpenOutput {
 }
 
 pub fn run(id: &str, use_json: bool) -> ExitCode {
-    // Open database and verify prompt exists
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -31,14 +30,7 @@ pub fn run(id: &str, use_json: bool) -> ExitCode {
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Check if prompt exists
     let prompt = match db.get_prompt(id) {
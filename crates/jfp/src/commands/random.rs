@@ -11,8 +11,7 @@ use std::process::ExitCode;
 use rand::prelude::IndexedRandom;
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry;
 use crate::types::Prompt;
 
 #[derive(Serialize)]
@@ -50,9 +49,9 @@ pub fn run(
     copy: bool,
     use_json: bool,
 ) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -63,20 +62,15 @@ pub fn run(
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Get filtered prompts
     let prompts = match db.list_prompts_filtered(
         category.as_deref(),
         tag.as_deref(),
         false,
+        false,
+        None,
     ) {
         Ok(p) => p,
         Err(e) => {
@@ -2,29 +2,49 @@
 //!
 //! From EXISTING_JFP_STRUCTURE.md section 14 (refresh):
 //! - Refreshes local registry cache from remote
+//! - Refreshes every enabled named registry, each with its own cache
 //! - Falls back to bundled prompts if network fails
+//! - Local prompts are merged over the refreshed registry
+//! - Prompts removed upstream are pruned; added/updated/removed are reported
+//! - `--dry-run` fetches and diffs against the database without writing
+//! - Holds the refresh lock, so it never overlaps another refresh
+//! - `--background` (hidden) is the detached stale-while-revalidate child
+//!   spawned by read commands; it prints nothing
 
 use std::process::ExitCode;
 
-use chrono::Utc;
 use serde::Serialize;
 
-use crate::registry::{bundled_prompts, RegistryLoader};
-use crate::storage::Database;
+use crate::config;
+use crate::registry::{
+    self, load_configured_local_prompts, LoadMode, LocalPrompts, MergedLoad, RegistrySet,
+};
+use crate::storage::{ChangeKind, ChangeSet, Database, PromptChange};
 use crate::types::RegistrySource;
 
 #[derive(Serialize)]
 struct RefreshOutput {
     refreshed: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    dry_run: bool,
     prompt_count: usize,
+    added: usize,
+    updated: usize,
+    removed: usize,
     source: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     message: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<PromptChange>,
 }
 
-pub fn run(use_json: bool) -> ExitCode {
+pub fn run(dry_run: bool, background: bool, use_json: bool) -> ExitCode {
+    if background {
+        return run_background();
+    }
+
     // Open database
-    let db = match Database::open() {
+    let mut db = match Database::open() {
         Ok(db) => db,
         Err(e) => {
             if use_json {
@@ -36,50 +56,79 @@ pub fn run(use_json: bool) -> ExitCode {
         }
     };
 
-    let loader = RegistryLoader::new();
-    let refresh = loader.refresh();
-
-    let (prompts, source, message) = match refresh {
-        Ok(result) => {
-            let source = match result.source {
-                RegistrySource::Remote => "remote",
-                RegistrySource::Cache => "cache",
-                RegistrySource::Bundled => "bundled",
-                RegistrySource::Local => "local",
-            };
-            let message = (result.source == RegistrySource::Cache && result.stale)
-                .then_some("Remote refresh failed; using cached registry".to_string());
-            (result.registry.prompts, source.to_string(), message)
+    // A dry run writes nothing, so only a real refresh takes the lock
+    let _lock = if dry_run {
+        None
+    } else {
+        match registry::hold_refresh_lock(&db) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                if use_json {
+                    println!(r#"{{"error": "refresh_locked", "message": "{}"}}"#, e);
+                } else {
+                    eprintln!("Error: {}", e);
+                }
+                return ExitCode::FAILURE;
+            }
         }
-        Err(e) => (
-            bundled_prompts(),
-            "bundled".to_string(),
-            Some(format!(
-                "Remote refresh failed; loaded bundled prompts instead ({})",
-                e
-            )),
-        ),
     };
 
-    let mut loaded_count = 0;
+    let config = config::load_config();
+    let registries = RegistrySet::from_config(&config);
+    let merged = registries.load(if dry_run {
+        LoadMode::Preview
+    } else {
+        LoadMode::Refresh
+    });
 
-    for prompt in &prompts {
-        if db.upsert_prompt(prompt).is_ok() {
-            loaded_count += 1;
+    let result = &merged.result;
+    let message = result.fallback_reason.as_deref().map(|reason| {
+        if result.source == RegistrySource::Bundled {
+            format!("Remote refresh failed; loaded bundled prompts instead ({})", reason)
+        } else {
+            format!("Remote refresh failed; using cached registry ({})", reason)
         }
+    });
+
+    let loaded_count = result.registry.prompts.len();
+    let source = result.source.as_str().to_string();
+    let mut local = load_configured_local_prompts(&config.local_prompts);
+    local.warnings.splice(0..0, registries.warnings.iter().cloned());
+
+    if dry_run {
+        return preview(&db, &merged, &local, source, message, use_json);
     }
 
-    // Update sync timestamp
-    let _ = db.set_meta("last_sync", &Utc::now().to_rfc3339());
+    // Store prompts and record where they came from
+    let (status, changes) = match registry::apply_load_result(&mut db, merged, local) {
+        Ok(applied) => applied,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error updating database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for warning in &status.warnings {
+        eprintln!("Warning: {}", warning);
+    }
 
     let prompt_count = db.prompt_count().unwrap_or(loaded_count);
 
     if use_json {
         let output = RefreshOutput {
             refreshed: true,
+            dry_run: false,
             prompt_count,
+            added: changes.added(),
+            updated: changes.updated(),
+            removed: changes.removed(),
             source,
             message,
+            changes: changes.changes,
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -90,6 +139,12 @@ pub fn run(use_json: bool) -> ExitCode {
         }
     } else {
         println!("Refreshed registry with {} prompts", prompt_count);
+        println!(
+            "Changes: {} added, {} updated, {} removed",
+            changes.added(),
+            changes.updated(),
+            changes.removed()
+        );
         println!("Source: {}", source);
         if let Some(message) = message {
             println!("{}", message);
@@ -98,3 +153,113 @@ pub fn run(use_json: bool) -> ExitCode {
 
     ExitCode::SUCCESS
 }
+
+/// Detached refresh spawned by `open_database` when the stored prompts are
+/// due; exits quietly if another refresh holds the lock
+///
+/// Outcomes are recorded in `registry_meta` exactly like a foreground sync.
+fn run_background() -> ExitCode {
+    let Ok(mut db) = Database::open() else {
+        return ExitCode::FAILURE;
+    };
+
+    let attempt = match registry::try_lock_refresh(&registry::refresh_lock_path()) {
+        Ok(attempt) => attempt,
+        Err(_) => return ExitCode::FAILURE,
+    };
+    let _ = registry::record_lock_attempt(&db, &attempt);
+    if attempt.lock.is_none() {
+        return ExitCode::SUCCESS;
+    }
+
+    let config = config::load_config();
+    let registries = RegistrySet::from_config(&config);
+    let local = load_configured_local_prompts(&config.local_prompts);
+    let merged = registries.load(LoadMode::Sync);
+
+    match registry::apply_load_result(&mut db, merged, local) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+/// Report what a refresh would change without writing anything
+fn preview(
+    db: &Database,
+    merged: &MergedLoad,
+    local: &LocalPrompts,
+    source: String,
+    message: Option<String>,
+    use_json: bool,
+) -> ExitCode {
+    let changes: ChangeSet = match registry::preview_load_result(db, merged, local) {
+        Ok(changes) => changes,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error reading database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for warning in &local.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let current = db.prompt_count().unwrap_or(0);
+    let prompt_count = (current + changes.added()).saturating_sub(changes.removed());
+
+    if use_json {
+        let output = RefreshOutput {
+            refreshed: false,
+            dry_run: true,
+            prompt_count,
+            added: changes.added(),
+            updated: changes.updated(),
+            removed: changes.removed(),
+            source,
+            message,
+            changes: changes.changes,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    println!("Dry run: no changes written (source: {})", source);
+    if let Some(message) = message {
+        println!("{}", message);
+    }
+
+    if changes.changes.is_empty() {
+        println!("Already up to date ({} prompts)", prompt_count);
+        return ExitCode::SUCCESS;
+    }
+
+    println!();
+    for change in &changes.changes {
+        let (marker, note) = match change.kind {
+            ChangeKind::Added => ("+", ""),
+            ChangeKind::Removed => ("-", ""),
+            ChangeKind::ContentChanged => ("~", " (content)"),
+            ChangeKind::MetadataChanged => ("~", " (metadata)"),
+        };
+        println!("  {} {:<30} {}{}", marker, change.id, change.title, note);
+    }
+    println!(
+        "\n{} added, {} updated, {} removed ({} prompts after refresh)",
+        changes.added(),
+        changes.updated(),
+        changes.removed(),
+        prompt_count
+    );
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,251 @@
+//! Registry command implementation
+//!
+//! Offline transfer of the registry for air-gapped machines:
+//! - `jfp registry pack <file>` writes the synced registry prompts, a
+//!   manifest with checksums, bundles and metadata to one JSON archive
+//! - `jfp registry unpack <file>` verifies an archive and imports it like
+//!   `refresh` would, recording the source as `packed` and keeping the
+//!   original fetch time so staleness is reported truthfully
+//! - The stored registry bundles travel in the archive and are stored again
+//!   on import
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use crate::config;
+use crate::registry::{
+    self, load_configured_local_prompts, PackBundle, RegistryPack, RegistrySet, META_LAST_SYNC,
+};
+use crate::storage::{Database, META_REGISTRY_VERSION};
+
+#[derive(Serialize)]
+struct PackOutput {
+    packed: bool,
+    path: String,
+    prompt_count: usize,
+    bundle_count: usize,
+    registries: Vec<String>,
+    fetched_at: String,
+    checksum: Option<String>,
+}
+
+#[derive(Serialize)]
+struct UnpackOutput {
+    unpacked: bool,
+    path: String,
+    prompt_count: usize,
+    added: usize,
+    updated: usize,
+    removed: usize,
+    source: String,
+    fetched_at: String,
+    stale: bool,
+    bundle_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_registries: Vec<String>,
+}
+
+pub fn run(action: &str, file: &Path, use_json: bool) -> ExitCode {
+    match action {
+        "pack" => pack(file, use_json),
+        "unpack" => unpack(file, use_json),
+        _ => {
+            if use_json {
+                println!(r#"{{"error": "invalid_action", "action": "{}"}}"#, action);
+            } else {
+                eprintln!("Invalid action: {}. Use: pack, unpack", action);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn pack(file: &Path, use_json: bool) -> ExitCode {
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(use_json);
+
+    // Bundled fallback data was never fetched, so there is nothing to carry
+    let Ok(fetched_at) = db.get_meta(META_LAST_SYNC) else {
+        let message = "The database has never synced from a registry. Run 'jfp refresh' first.";
+        if use_json {
+            println!(r#"{{"error": "no_registry_data", "message": "{}"}}"#, message);
+        } else {
+            eprintln!("Error: {}", message);
+        }
+        return ExitCode::FAILURE;
+    };
+
+    let pack = match db
+        .export_prompts()
+        .and_then(|prompts| Ok((prompts, db.bundles()?)))
+        .and_then(|(prompts, bundles)| {
+            let bundles = bundles.iter().map(PackBundle::from).collect();
+            let version = db.get_meta(META_REGISTRY_VERSION).ok();
+            RegistryPack::new(prompts, bundles, version, &fetched_at, sync.source)
+        })
+        .and_then(|pack| pack.write(file).map(|()| pack))
+    {
+        Ok(pack) => pack,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "pack_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error writing pack: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = PackOutput {
+        packed: true,
+        path: file.display().to_string(),
+        prompt_count: pack.metadata.prompt_count,
+        bundle_count: pack.bundles.len(),
+        registries: pack.metadata.registries,
+        fetched_at: pack.metadata.fetched_at,
+        checksum: pack.manifest.checksums.registry,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        println!(
+            "Packed {} prompts and {} bundles to {}",
+            output.prompt_count, output.bundle_count, output.path
+        );
+        println!("Registries: {}", output.registries.join(", "));
+        println!("Fetched at: {}", output.fetched_at);
+        println!("\nCopy it to the offline machine and run 'jfp registry unpack <file>'");
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn unpack(file: &Path, use_json: bool) -> ExitCode {
+    let pack = match RegistryPack::read(file).and_then(|pack| pack.verify().map(|()| pack)) {
+        Ok(pack) => pack,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "invalid_pack", "message": "{:#}"}}"#, e);
+            } else {
+                eprintln!("Error: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Open without syncing: an unpack must not wait on the network
+    let mut db = match Database::open() {
+        Ok(db) => db,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Rewrites the registry caches, so it must not overlap a refresh
+    let _lock = match registry::hold_refresh_lock(&db) {
+        Ok(lock) => lock,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "refresh_locked", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let config = config::load_config();
+    let registries = RegistrySet::from_config(&config);
+    let mut local = load_configured_local_prompts(&config.local_prompts);
+    local.warnings.splice(0..0, registries.warnings.iter().cloned());
+
+    let imported = pack
+        .import(&registries, config.registry.cache_ttl)
+        .and_then(|(merged, skipped)| {
+            registry::apply_load_result(&mut db, merged, local)
+                .map(|(status, changes)| (status, changes, skipped))
+        });
+    let (status, changes, skipped) = match imported {
+        Ok(imported) => imported,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error importing pack: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for warning in &status.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    for name in &skipped {
+        eprintln!(
+            "Warning: Skipped prompts from registry '{}' (not configured on this machine)",
+            name
+        );
+    }
+
+    let output = UnpackOutput {
+        unpacked: true,
+        path: file.display().to_string(),
+        prompt_count: db.prompt_count().unwrap_or(0),
+        added: changes.added(),
+        updated: changes.updated(),
+        removed: changes.removed(),
+        source: status.source.as_str().to_string(),
+        fetched_at: pack.metadata.fetched_at,
+        stale: status.stale,
+        bundle_count: pack.bundles.len(),
+        skipped_registries: skipped,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        println!("Imported {} prompts from {}", output.prompt_count, output.path);
+        println!(
+            "Changes: {} added, {} updated, {} removed",
+            output.added, output.updated, output.removed
+        );
+        println!("Source: {} (fetched {})", output.source, output.fetched_at);
+        if output.stale {
+            println!("Note: the packed data is older than registry.cacheTtl");
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -4,6 +4,7 @@
 //! - Renders prompt with variable substitution
 //! - Optional --fill for interactive substitution
 //! - Optional --context for file-based context
+//! - Unknown ids get "did you mean" suggestions
 
 use std::collections::HashMap;
 use std::fs;
@@ -12,8 +13,9 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::commands::show::{print_did_you_mean, similar_ids};
+use crate::registry;
+use crate::storage::UsageAction;
 use crate::types::Prompt;
 
 #[derive(Serialize)]
@@ -37,9 +39,9 @@ pub fn run(
     context: Option<String>,
     use_json: bool,
 ) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -50,23 +52,22 @@ pub fn run(
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Get prompt
     let prompt = match db.get_prompt(id) {
         Ok(Some(p)) => p,
         Ok(None) => {
+            let suggestions = similar_ids(&db, id);
             if use_json {
-                println!(r#"{{"error": "not_found", "id": "{}"}}"#, id);
+                let mut output = serde_json::json!({ "error": "not_found", "id": id });
+                if !suggestions.is_empty() {
+                    output["suggestions"] = suggestions.into();
+                }
+                println!("{}", output);
             } else {
                 eprintln!("Prompt '{}' not found.", id);
+                print_did_you_mean(&suggestions);
             }
             return ExitCode::FAILURE;
         }
@@ -121,6 +122,8 @@ pub fn run(
         (prompt.content.clone(), None)
     };
 
+    super::stats::record_usage(&db, &prompt.id, UsageAction::Render);
+
     if use_json {
         let output = RenderOutput {
             id: prompt.id.clone(),
@@ -0,0 +1,234 @@
+//! Restore command implementation
+//!
+//! Restores prompts from a `jfp backup` JSONL file:
+//! - `--mode replace` (default) makes the database match the file
+//! - `--mode merge` adds and updates prompts, keeping everything else
+//! - `--verify` only checks every line and the `_meta` count
+//! - The file is always verified first; nothing is written if it is invalid
+//! - A replace first takes an automatic backup of the current database
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use crate::commands::backup::{write_backup, DEFAULT_KEEP};
+use crate::registry;
+use crate::storage::{self, Database, RestoreMode};
+
+#[derive(Serialize)]
+struct VerifyOutput {
+    valid: bool,
+    path: String,
+    prompt_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<storage::MetaInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RestoreOutput {
+    restored: bool,
+    path: String,
+    mode: RestoreMode,
+    prompt_count: usize,
+    star_count: usize,
+    note_count: usize,
+    added: usize,
+    updated: usize,
+    removed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    safety_backup: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+pub fn run(path: &Path, mode: &str, verify_only: bool, use_json: bool) -> ExitCode {
+    let Some(mode) = RestoreMode::parse(mode) else {
+        if use_json {
+            println!(r#"{{"error": "invalid_mode", "mode": "{}"}}"#, mode);
+        } else {
+            eprintln!("Invalid mode '{}'. Use 'replace' or 'merge'", mode);
+        }
+        return ExitCode::FAILURE;
+    };
+
+    let verification = match storage::verify_jsonl(path) {
+        Ok(verification) => verification,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "read_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if verify_only {
+        return report_verification(path, verification, use_json);
+    }
+    if !verification.is_valid() {
+        if use_json {
+            let output = serde_json::json!({
+                "error": "invalid_backup",
+                "path": path.display().to_string(),
+                "errors": verification.errors,
+            });
+            println!("{}", output);
+        } else {
+            eprintln!("{} is not a valid backup; nothing was restored:", path.display());
+            for error in &verification.errors {
+                eprintln!("  {}", error);
+            }
+        }
+        return ExitCode::FAILURE;
+    }
+
+    // Open without syncing; the lock keeps a refresh from writing meanwhile
+    let mut db = match Database::open() {
+        Ok(db) => db,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+    let _lock = match registry::hold_refresh_lock(&db) {
+        Ok(lock) => lock,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "refresh_locked", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let safety_backup = if mode == RestoreMode::Replace {
+        match write_backup(&db, None, DEFAULT_KEEP) {
+            Ok((backup, _, _)) => Some(backup.display().to_string()),
+            Err(e) => {
+                if use_json {
+                    println!(r#"{{"error": "backup_error", "message": "{}"}}"#, e);
+                } else {
+                    eprintln!("Error backing up current database: {:#}", e);
+                }
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let changes = match storage::import_jsonl(&mut db, path, mode) {
+        Ok(changes) => changes,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "restore_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error restoring backup: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = RestoreOutput {
+        restored: true,
+        path: path.display().to_string(),
+        mode,
+        prompt_count: verification.prompts.len(),
+        star_count: verification.stars.len(),
+        note_count: verification.notes.len(),
+        added: changes.added(),
+        updated: changes.updated(),
+        removed: changes.removed(),
+        safety_backup,
+        warnings: verification.warnings,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        for warning in &output.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        println!(
+            "Restored {} prompts, {} stars and {} notes from {}",
+            output.prompt_count, output.star_count, output.note_count, output.path
+        );
+        println!(
+            "Changes: {} added, {} updated, {} removed",
+            output.added, output.updated, output.removed
+        );
+        if let Some(backup) = &output.safety_backup {
+            println!("Previous contents saved to {}", backup);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Print a verification report; fails when the file is invalid
+fn report_verification(
+    path: &Path,
+    verification: storage::JsonlVerification,
+    use_json: bool,
+) -> ExitCode {
+    let valid = verification.is_valid();
+    let output = VerifyOutput {
+        valid,
+        path: path.display().to_string(),
+        prompt_count: verification.prompts.len(),
+        meta: verification.meta,
+        errors: verification.errors,
+        warnings: verification.warnings,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        if valid {
+            println!("{} is a valid backup ({} prompts)", output.path, output.prompt_count);
+        } else {
+            println!("{} is not a valid backup:", output.path);
+        }
+        if let Some(meta) = &output.meta {
+            println!(
+                "  Exported {} (schema {})",
+                meta.exported_at, meta.schema_version
+            );
+        }
+        for error in &output.errors {
+            println!("  Error: {}", error);
+        }
+        for warning in &output.warnings {
+            println!("  Warning: {}", warning);
+        }
+    }
+
+    if valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -3,14 +3,36 @@
 //! From EXISTING_JFP_STRUCTURE.md section 10 (search):
 //! - Uses BM25 index from core (buildIndex, searchPrompts)
 //! - JSON output: { results, query, authenticated, offline?, warning? }
+//! - `--registry <name>` limits results to one named registry (or `local`)
+//! - With usage recording on, frequently and recently used prompts rank higher
+//! - Queries use the language in `storage::query` (`tag:`, `category:`,
+//!   `featured:`, `"phrases"`, `-exclude`, `OR`); parse errors are reported
+//!   as `invalid_query` with the offending position
+//! - When BM25 finds fewer than `FUZZY_FALLBACK_BELOW` prompts, typo-tolerant
+//!   matches on ids, titles and tags are appended; each result's
+//!   `match_kind` is `keyword` or `fuzzy`
+//! - Words are expanded with synonyms (built-in plus `[search.synonyms]`),
+//!   which rank below literal matches; `--no-expand` or `search.expand =
+//!   false` turns this off
+//! - Each keyword hit carries an FTS5 `snippet` and the `matches` (field and
+//!   term) that explain it; human output highlights matched terms unless
+//!   color is off (`--no-color`, `NO_COLOR`, `output.color = false`)
 
 use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::PromptSummary;
+use crate::config;
+use crate::registry;
+use crate::storage::{render_marked, QueryError, SearchHighlight, SearchQuery, Synonyms};
+use crate::types::{PromptSummary, SearchMatch, SearchResult};
+
+/// Keyword hit count below which fuzzy matches are added
+const FUZZY_FALLBACK_BELOW: usize = 3;
+
+/// ANSI style of matched terms (bold yellow)
+const HIGHLIGHT_STYLE: &str = "\x1b[1;33m";
+const RESET_STYLE: &str = "\x1b[0m";
 
 /// Search result for JSON output
 #[derive(Serialize)]
@@ -18,6 +40,21 @@ struct SearchResultOutput {
     #[serde(flatten)]
     prompt: PromptSummary,
     score: f64,
+    match_kind: &'static str,
+    /// Excerpt around the match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<Vec<SearchMatch>>,
+}
+
+/// A result with the text that explains it
+struct Hit {
+    result: SearchResult,
+    match_kind: &'static str,
+    /// Marked excerpt and title (see `storage::highlight`)
+    snippet: Option<String>,
+    title: Option<String>,
 }
 
 /// JSON output for search command
@@ -27,11 +64,30 @@ struct SearchOutput {
     query: String,
     count: usize,
     authenticated: bool,
+    source: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     offline: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_sync: Option<String>,
+    /// Synonyms the query was expanded with
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    expanded: Vec<String>,
+}
+
+/// The synonyms to expand queries with, unless expansion is off
+pub fn query_synonyms(no_expand: bool) -> Option<Synonyms> {
+    let search = config::load_config().search;
+    (!no_expand && search.expand).then(|| Synonyms::with_extra(&search.synonyms))
 }
 
-pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
+pub fn run(
+    query: &str,
+    limit: usize,
+    registry_name: Option<&str>,
+    no_expand: bool,
+    no_color: bool,
+    use_json: bool,
+) -> ExitCode {
     // Validate limit
     if limit == 0 || limit > 100 {
         if use_json {
@@ -52,9 +108,20 @@ pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    let mut parsed = match SearchQuery::parse(query) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            report_query_error(query, &e, use_json);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(synonyms) = query_synonyms(no_expand) {
+        parsed.expand(&synonyms);
+    }
+
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -65,50 +132,83 @@ pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Search using FTS5
-    let results = match db.search(query, limit) {
+    let frecency = super::stats::frecency_enabled();
+    let results = match db.search_query(&parsed, limit, registry_name, frecency) {
         Ok(r) => r,
         Err(e) => {
-            // FTS5 query syntax error - try escaping special chars
-            let escaped_query = escape_fts_query(query);
-            match db.search(&escaped_query, limit) {
-                Ok(r) => r,
-                Err(_) => {
-                    if use_json {
-                        println!(r#"{{"error": "search_error", "message": "{}"}}"#, e);
-                    } else {
-                        eprintln!("Search error: {}", e);
+            if use_json {
+                println!(r#"{{"error": "search_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Search error: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut results: Vec<_> = results.into_iter().map(|(p, score)| (p, score, "keyword")).collect();
+    if results.len() < FUZZY_FALLBACK_BELOW.min(limit) {
+        match db.fuzzy_search(&parsed, limit, registry_name) {
+            Ok(fuzzy) => {
+                for (prompt, score) in fuzzy {
+                    if results.len() < limit && !results.iter().any(|(p, _, _)| p.id == prompt.id) {
+                        results.push((prompt, score, "fuzzy"));
                     }
-                    return ExitCode::FAILURE;
+                }
+            }
+            Err(e) => {
+                if !use_json {
+                    eprintln!("Warning: fuzzy matching failed: {}", e);
                 }
             }
         }
-    };
+    }
 
-    let result_count = results.len();
+    let ids: Vec<String> = results.iter().map(|(p, _, _)| p.id.clone()).collect();
+    let mut highlights = match db.search_highlights(&parsed, &ids) {
+        Ok(highlights) => highlights,
+        Err(e) => {
+            if !use_json {
+                eprintln!("Warning: could not highlight matches: {}", e);
+            }
+            Default::default()
+        }
+    };
+    let hits: Vec<Hit> = results
+        .into_iter()
+        .map(|(prompt, score, match_kind)| {
+            let SearchHighlight { snippet, title, matches } =
+                highlights.remove(&prompt.id).unwrap_or_default();
+            let mut result = SearchResult::new(prompt, score);
+            if !matches.is_empty() {
+                result = result.with_matches(matches);
+            }
+            Hit { result, match_kind, snippet, title }
+        })
+        .collect();
+    let result_count = hits.len();
 
     if use_json {
         let output = SearchOutput {
-            results: results
-                .iter()
-                .map(|(prompt, score)| SearchResultOutput {
-                    prompt: PromptSummary::from(prompt),
-                    score: *score,
+            results: hits
+                .into_iter()
+                .map(|hit| SearchResultOutput {
+                    prompt: PromptSummary::from(&hit.result.prompt),
+                    score: hit.result.score,
+                    match_kind: hit.match_kind,
+                    snippet: hit.snippet.map(|s| render_marked(&s, "", "")),
+                    matches: hit.result.matches,
                 })
                 .collect(),
             query: query.to_string(),
             count: result_count,
             authenticated: false,
-            offline: None,
+            source: sync.source.as_str().to_string(),
+            offline: sync.offline().then_some(true),
+            last_sync: sync.last_sync.clone(),
+            expanded: parsed.expansions.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -118,12 +218,27 @@ pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
             }
         }
     } else {
-        if results.is_empty() {
+        let color = !no_color && config::load_config().output.color;
+        let (open, close) = if color { (HIGHLIGHT_STYLE, RESET_STYLE) } else { ("", "") };
+        if hits.is_empty() {
             println!("No results found for \"{}\"", query);
         } else {
-            println!("Search results for \"{}\" ({} found):\n", query, result_count);
-            for (prompt, score) in &results {
-                println!("  {} - {} (score: {:.2})", prompt.id, prompt.title, score);
+            println!("Search results for \"{}\" ({} found):", query, result_count);
+            if !parsed.expansions.is_empty() {
+                println!("Also matching: {}", parsed.expansions.join(", "));
+            }
+            println!();
+            for hit in &hits {
+                let (prompt, score) = (&hit.result.prompt, hit.result.score);
+                let title = match &hit.title {
+                    Some(title) => render_marked(title, open, close),
+                    None => prompt.title.clone(),
+                };
+                if hit.match_kind == "fuzzy" {
+                    println!("  {} - {} (similar, {:.0}%)", prompt.id, title, score * 100.0);
+                } else {
+                    println!("  {} - {} (score: {:.2})", prompt.id, title, score);
+                }
                 if let Some(desc) = &prompt.description {
                     let truncated = if desc.len() > 60 {
                         format!("{}...", &desc[..57])
@@ -132,6 +247,13 @@ pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
                     };
                     println!("    {}", truncated);
                 }
+                // Skip snippets that only repeat the title or description
+                if let Some(snippet) = &hit.snippet {
+                    let plain = render_marked(snippet, "", "");
+                    if plain != prompt.title && Some(&plain) != prompt.description.as_ref() {
+                        println!("    {}", render_marked(snippet, open, close));
+                    }
+                }
                 println!();
             }
         }
@@ -140,9 +262,20 @@ pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-/// Escape special FTS5 characters in query
-fn escape_fts_query(query: &str) -> String {
-    // FTS5 special characters: * - + " ( ) { } [ ] ^ ~ : \
-    // For simple queries, we can just wrap in quotes
-    format!("\"{}\"", query.replace('"', "\"\""))
+/// Print a parse error, pointing at where it happened
+fn report_query_error(query: &str, error: &QueryError, use_json: bool) {
+    if use_json {
+        let output = serde_json::json!({
+            "error": "invalid_query",
+            "message": error.message,
+            "position": error.position,
+            "query": query,
+        });
+        println!("{}", output);
+    } else {
+        let column = query[..error.position.min(query.len())].chars().count();
+        eprintln!("Error: invalid query: {}", error.message);
+        eprintln!("  {}", query);
+        eprintln!("  {}^", " ".repeat(column));
+    }
 }
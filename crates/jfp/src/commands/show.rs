@@ -2,14 +2,15 @@
 //!
 //! From EXISTING_JFP_STRUCTURE.md section 10 (show):
 //! - Options: --json, --raw
-//! - Not found: JSON payload is exactly { "error": "not_found" }
+//! - Not found: JSON payload is exactly { "error": "not_found" }; the human
+//!   message suggests similar ids
 
 use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry;
+use crate::storage::{Database, Note, UsageAction};
 use crate::types::Prompt;
 
 /// Full prompt output for JSON
@@ -29,6 +30,8 @@ struct ShowOutput {
     version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     author: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<Note>,
 }
 
 impl From<&Prompt> for ShowOutput {
@@ -43,10 +46,23 @@ impl From<&Prompt> for ShowOutput {
             featured: p.featured,
             version: p.version.clone(),
             author: p.author.clone(),
+            notes: Vec::new(),
         }
     }
 }
 
+/// Up to three ids resembling an unknown `id`, for "did you mean"
+pub fn similar_ids(db: &Database, id: &str) -> Vec<String> {
+    db.similar_ids(id, 3).unwrap_or_default()
+}
+
+/// Print `Did you mean: ...?` to stderr when there are suggestions
+pub fn print_did_you_mean(suggestions: &[String]) {
+    if !suggestions.is_empty() {
+        eprintln!("Did you mean: {}?", suggestions.join(", "));
+    }
+}
+
 pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
     // Validate ID
     if id.trim().is_empty() {
@@ -58,9 +74,9 @@ pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -71,14 +87,7 @@ pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Get prompt
     let prompt = match db.get_prompt(id) {
@@ -89,6 +98,7 @@ pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
                 println!(r#"{{"error": "not_found"}}"#);
             } else {
                 eprintln!("Prompt not found: {}", id);
+                print_did_you_mean(&similar_ids(&db, id));
             }
             return ExitCode::FAILURE;
         }
@@ -102,12 +112,20 @@ pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
         }
     };
 
+    super::stats::record_usage(&db, &prompt.id, UsageAction::Show);
+
+    // Notes are extra; a failure to read them doesn't fail show
+    let notes = db.notes(Some(&prompt.id)).unwrap_or_default();
+
     // Output
     if raw {
         // Raw mode: just print content
         print!("{}", prompt.content);
     } else if use_json {
-        let output = ShowOutput::from(&prompt);
+        let output = ShowOutput {
+            notes,
+            ..ShowOutput::from(&prompt)
+        };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
             Err(e) => {
@@ -146,6 +164,16 @@ pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
         if let Some(version) = &prompt.version {
             println!("Version: {}", version);
         }
+
+        if !notes.is_empty() {
+            println!("\nNotes:");
+            for note in &notes {
+                println!("  [{}] {}", note.id, super::note::short_date(&note.updated_at));
+                for line in note.body.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
     }
 
     ExitCode::SUCCESS
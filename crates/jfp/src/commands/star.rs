@@ -0,0 +1,111 @@
+//! Star command implementation
+//!
+//! Local favorites, no account needed:
+//! - `jfp star <id>` / `jfp unstar <id>`
+//! - Stars are kept in `prompt_stars`, survive refreshes and are included
+//!   in `jfp backup`
+//! - `jfp list --starred` lists them; `jfp i` shows them first
+
+use std::process::ExitCode;
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::registry;
+
+#[derive(Serialize)]
+struct StarOutput {
+    id: String,
+    starred: bool,
+    /// False when the prompt already was (or was not) starred
+    changed: bool,
+}
+
+pub fn star(id: &str, use_json: bool) -> ExitCode {
+    run(id, true, use_json)
+}
+
+pub fn unstar(id: &str, use_json: bool) -> ExitCode {
+    run(id, false, use_json)
+}
+
+fn run(id: &str, starred: bool, use_json: bool) -> ExitCode {
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(use_json);
+
+    // Stars are keyed by the stored id; a removed prompt can still be unstarred
+    let resolved = match db.get_prompt(id) {
+        Ok(Some(prompt)) => prompt.id,
+        Ok(None) if !starred => id.to_string(),
+        Ok(None) => {
+            if use_json {
+                println!(r#"{{"error": "not_found"}}"#);
+            } else {
+                eprintln!("Prompt not found: {}", id);
+            }
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error loading prompt: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let changed = if starred {
+        db.star_prompt(&resolved, &Utc::now().to_rfc3339())
+    } else {
+        db.unstar_prompt(&resolved)
+    };
+    let changed = match changed {
+        Ok(changed) => changed,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error updating star: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = StarOutput {
+        id: resolved,
+        starred,
+        changed,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        match (starred, changed) {
+            (true, true) => println!("Starred {}", output.id),
+            (true, false) => println!("{} is already starred", output.id),
+            (false, true) => println!("Unstarred {}", output.id),
+            (false, false) => println!("{} was not starred", output.id),
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,129 @@
+//! Stats command implementation
+//!
+//! Summarizes local usage recorded by copy, render, export and show:
+//! - Most-used prompts over the last 7 days, 30 days and all recorded time
+//! - Recording is opt-in (`analytics.enabled`); events stay in the local
+//!   database and are never sent anywhere
+
+use std::process::ExitCode;
+
+use chrono::{Duration, Utc};
+use serde::Serialize;
+
+use crate::config;
+use crate::registry;
+use crate::storage::{Database, UsageAction, UsageStat};
+
+/// Windows reported by `jfp stats`: label and length in days
+const WINDOWS: &[(&str, Option<i64>)] = &[("7d", Some(7)), ("30d", Some(30)), ("all", None)];
+
+#[derive(Serialize)]
+struct WindowStats {
+    window: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<String>,
+    total_uses: usize,
+    prompts: Vec<UsageStat>,
+}
+
+#[derive(Serialize)]
+struct StatsOutput {
+    enabled: bool,
+    windows: Vec<WindowStats>,
+}
+
+pub fn run(limit: usize, use_json: bool) -> ExitCode {
+    let enabled = config::load_config().analytics.enabled;
+
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(use_json);
+
+    let now = Utc::now();
+    let mut windows = Vec::with_capacity(WINDOWS.len());
+    for (window, days) in WINDOWS {
+        let since = days.map(|days| (now - Duration::days(days)).to_rfc3339());
+        let stats = match db.usage_stats(since.as_deref()) {
+            Ok(stats) => stats,
+            Err(e) => {
+                if use_json {
+                    println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                } else {
+                    eprintln!("Error reading usage: {}", e);
+                }
+                return ExitCode::FAILURE;
+            }
+        };
+        windows.push(WindowStats {
+            window,
+            since,
+            total_uses: stats.iter().map(|stat| stat.uses).sum(),
+            prompts: stats.into_iter().take(limit).collect(),
+        });
+    }
+
+    let output = StatsOutput { enabled, windows };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        if !output.enabled {
+            println!("Usage recording is off. Enable it with 'jfp config set analytics.enabled true'");
+            println!("(events stay in the local database and are never sent anywhere)\n");
+        }
+        for window in &output.windows {
+            let label = match window.window {
+                "all" => "All time".to_string(),
+                days => format!("Last {}", days.replace('d', " days")),
+            };
+            println!("{} ({} uses):", label, window.total_uses);
+            if window.prompts.is_empty() {
+                println!("  (no usage recorded)");
+            }
+            for stat in &window.prompts {
+                println!(
+                    "  {:>4}  {:<30} {}",
+                    stat.uses,
+                    stat.id,
+                    stat.title.as_deref().unwrap_or("(removed)")
+                );
+            }
+            println!();
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Record that `prompt_id` was used, if `analytics.enabled` is set
+///
+/// Failures are ignored: usage tracking never breaks the command using the
+/// prompt.
+pub fn record_usage(db: &Database, prompt_id: &str, action: UsageAction) {
+    if config::load_config().analytics.enabled {
+        let _ = db.record_usage(prompt_id, action, &Utc::now().to_rfc3339());
+    }
+}
+
+/// Whether search ranking should include the frecency boost
+pub fn frecency_enabled() -> bool {
+    let analytics = config::load_config().analytics;
+    analytics.enabled && analytics.frecency
+}
@@ -2,19 +2,29 @@
 //!
 //! From EXISTING_JFP_STRUCTURE.md section 14 (status):
 //! - Shows registry cache status
-//! - Shows cache freshness, prompt count, last update
+//! - Shows cache freshness, prompt count, last update, and load warnings
+//!   for the stored registry data
+//! - Shows each configured registry with its stored prompt count
+//! - Shows local prompts directory and any invalid local prompt files
+//! - Shows the refresh lock holder, the last contention and the last
+//!   stale-lock recovery
 
+use std::collections::HashMap;
 use std::process::ExitCode;
 
-use chrono::{DateTime, Utc};
 use serde::Serialize;
 
+use crate::config;
+use crate::registry;
 use crate::storage::Database;
 
 #[derive(Serialize)]
 struct StatusOutput {
     database: DatabaseStatus,
     cache: CacheStatus,
+    registries: Vec<RegistryStatus>,
+    local: LocalStatus,
+    refresh_lock: LockStatus,
 }
 
 #[derive(Serialize)]
@@ -32,6 +42,45 @@ struct CacheStatus {
     last_sync: Option<String>,
     stale: bool,
     source: String,
+    /// Why the last sync fell back to cached or bundled data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+    /// Load warnings for the stored registry data
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RegistryStatus {
+    name: String,
+    prompt_count: usize,
+}
+
+#[derive(Serialize)]
+struct LockStatus {
+    path: String,
+    held: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    holder: Option<registry::LockInfo>,
+    /// Held by a process that is gone or took too long; the next refresh
+    /// takes it over
+    stale: bool,
+    /// Whether `stale` reflects the holder having exited, or only the lock's
+    /// age (platforms where the pid cannot be probed)
+    holder_checked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_contention: Option<registry::LockEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_recovery: Option<registry::LockEvent>,
+}
+
+#[derive(Serialize)]
+struct LocalStatus {
+    enabled: bool,
+    dir: String,
+    prompt_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
 }
 
 pub fn run(use_json: bool) -> ExitCode {
@@ -39,41 +88,63 @@ pub fn run(use_json: bool) -> ExitCode {
     let db_path = crate::storage::db_path();
     let db_exists = db_path.exists();
 
-    let (prompt_count, schema_version, last_sync) = if db_exists {
+    let (prompt_count, schema_version, sync, source_counts, lock_events) = if db_exists {
         match Database::open() {
             Ok(db) => {
                 let count = db.prompt_count().unwrap_or(0);
-                let version = Some(crate::storage::SCHEMA_VERSION);
-                let sync = db.get_meta("last_sync").ok();
-                (count, version, sync)
+                let version = Some(db.schema_version());
+                let sources = db.source_counts().unwrap_or_default();
+                let events = (
+                    registry::read_lock_event(&db, registry::META_LOCK_CONTENTION),
+                    registry::read_lock_event(&db, registry::META_LOCK_RECOVERED),
+                );
+                (count, version, Some(registry::read_status(&db)), sources, events)
             }
-            Err(_) => (0, None, None),
+            Err(_) => (0, None, None, HashMap::new(), (None, None)),
         }
     } else {
-        (0, None, None)
+        (0, None, None, HashMap::new(), (None, None))
     };
 
-    // Determine cache staleness (> 1 hour old or no sync)
-    let stale = match &last_sync {
-        Some(ts) => {
-            if let Ok(dt) = ts.parse::<DateTime<Utc>>() {
-                let age = Utc::now().signed_duration_since(dt);
-                age.num_hours() >= 1
-            } else {
-                true
-            }
-        }
-        None => true,
+    let lock_path = registry::refresh_lock_path();
+    let holder = registry::read_refresh_lock(&lock_path);
+    let refresh_lock = LockStatus {
+        path: lock_path.display().to_string(),
+        held: lock_path.exists(),
+        stale: holder.as_ref().is_some_and(|info| info.is_stale()),
+        holder_checked: registry::HOLDER_LIVENESS_CHECKED,
+        holder,
+        last_contention: lock_events.0,
+        last_recovery: lock_events.1,
     };
 
+    // Stale when the stored data is a fallback or older than the configured TTL
+    let config = config::load_config();
+    let cache_ttl = config.registry.cache_ttl;
+    let last_sync = sync.as_ref().and_then(|s| s.last_sync.clone());
+    let last_error = sync.as_ref().and_then(|s| s.last_error.clone());
+    let registry_warnings = sync.as_ref().map(|s| s.warnings.clone()).unwrap_or_default();
+    let stale = sync.as_ref().is_none_or(|s| s.offline())
+        || registry::is_older_than(last_sync.as_deref(), cache_ttl);
+
     // Determine source
-    let source = if !db_exists {
-        "bundled".to_string()
-    } else if stale {
-        "local (stale)".to_string()
-    } else {
-        "local".to_string()
-    };
+    let source = sync
+        .as_ref()
+        .map(|s| s.source.as_str().to_string())
+        .unwrap_or_else(|| "bundled".to_string());
+
+    let registry_set = registry::RegistrySet::from_config(&config);
+    let registries = registry_set
+        .names()
+        .into_iter()
+        .map(|name| RegistryStatus {
+            name: name.to_string(),
+            prompt_count: source_counts.get(name).copied().unwrap_or(0),
+        })
+        .collect();
+
+    let mut local = registry::load_configured_local_prompts(&config.local_prompts);
+    local.warnings.splice(0..0, registry_set.warnings.iter().cloned());
 
     let output = StatusOutput {
         database: DatabaseStatus {
@@ -86,7 +157,17 @@ pub fn run(use_json: bool) -> ExitCode {
             last_sync,
             stale,
             source,
+            last_error,
+            warnings: registry_warnings,
         },
+        registries,
+        local: LocalStatus {
+            enabled: config.local_prompts.enabled,
+            dir: config.local_prompts.dir.display().to_string(),
+            prompt_count: local.prompts.len(),
+            warnings: local.warnings,
+        },
+        refresh_lock,
     };
 
     if use_json {
@@ -116,6 +197,59 @@ pub fn run(use_json: bool) -> ExitCode {
         } else {
             println!("  Last sync: never");
         }
+        if let Some(reason) = &output.cache.last_error {
+            println!("  Last error: {}", reason);
+        }
+        for warning in &output.cache.warnings {
+            println!("  Warning: {}", warning);
+        }
+
+        println!("\nRegistries (highest priority first):");
+        for registry in &output.registries {
+            println!("  {:<20} {} prompts", registry.name, registry.prompt_count);
+        }
+
+        println!("\nLocal prompts:");
+        if output.local.enabled {
+            println!("  Dir: {}", output.local.dir);
+            println!("  Prompts: {}", output.local.prompt_count);
+            for warning in &output.local.warnings {
+                println!("  Warning: {}", warning);
+            }
+        } else {
+            println!("  Disabled");
+        }
+
+        let lock = &output.refresh_lock;
+        println!("\nRefresh lock:");
+        match (&lock.holder, lock.held) {
+            (Some(holder), _) => println!(
+                "  Held by pid {} since {}{}",
+                holder.pid,
+                holder.acquired_at,
+                if lock.stale { " (stale, will be recovered)" } else { "" }
+            ),
+            (None, true) => println!("  Held (unreadable lock file {})", lock.path),
+            (None, false) => println!("  Free"),
+        }
+        if lock.held && !lock.holder_checked {
+            println!(
+                "  Holder liveness is not checked on this platform; the lock is recovered after {}s",
+                registry::LOCK_STALE_AFTER_SECS
+            );
+        }
+        if let Some(event) = &lock.last_contention {
+            println!(
+                "  Last contention: {} (pid {} held it since {})",
+                event.at, event.pid, event.locked_since
+            );
+        }
+        if let Some(event) = &lock.last_recovery {
+            println!(
+                "  Last stale lock recovered: {} (pid {}, locked since {})",
+                event.at, event.pid, event.locked_since
+            );
+        }
 
         if output.cache.stale {
             println!("\nTip: Run 'jfp refresh' to update the cache");
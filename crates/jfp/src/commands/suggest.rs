@@ -3,14 +3,16 @@
 //! From EXISTING_JFP_STRUCTURE.md section 12 (suggest):
 //! - Suggests prompts for a task description
 //! - Uses FTS5 search as a simple relevance mechanism
-//! - Semantic search option (not yet implemented)
+//! - `--semantic` blends BM25 with offline hash-embedding similarity
+//!   (`Database::semantic_search`), so differently worded tasks still match
+//! - Task words are expanded with synonyms like `jfp search` (`--no-expand`)
 
 use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry;
+use crate::storage::SearchQuery;
 
 #[derive(Serialize)]
 struct SuggestOutput {
@@ -18,6 +20,9 @@ struct SuggestOutput {
     suggestions: Vec<Suggestion>,
     #[serde(skip_serializing_if = "Option::is_none")]
     semantic: Option<bool>,
+    source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offline: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -31,19 +36,10 @@ struct Suggestion {
     reason: Option<String>,
 }
 
-pub fn run(task: &str, limit: usize, semantic: bool, use_json: bool) -> ExitCode {
-    if semantic {
-        if use_json {
-            println!(r#"{{"error": "semantic_not_implemented", "message": "Semantic search not yet available"}}"#);
-        } else {
-            eprintln!("Semantic search not yet implemented. Using keyword search.");
-        }
-        // Continue with keyword search
-    }
-
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+pub fn run(task: &str, limit: usize, semantic: bool, no_expand: bool, use_json: bool) -> ExitCode {
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
         Err(e) => {
             if use_json {
                 println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
@@ -54,17 +50,21 @@ pub fn run(task: &str, limit: usize, semantic: bool, use_json: bool) -> ExitCode
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    sync.report(use_json);
 
     // Search for relevant prompts using FTS5
-    let results = match db.search(task, limit) {
+    // The task is free text, not a query: every word is searched literally
+    let mut query = SearchQuery::words(task);
+    if let Some(synonyms) = super::search::query_synonyms(no_expand) {
+        query.expand(&synonyms);
+    }
+    let frecency = super::stats::frecency_enabled();
+    let results = if semantic {
+        db.semantic_search(task, &query, limit, frecency)
+    } else {
+        db.search_query(&query, limit, None, frecency)
+    };
+    let results = match results {
         Ok(r) => r,
         Err(e) => {
             if use_json {
@@ -96,7 +96,9 @@ pub fn run(task: &str, limit: usize, semantic: bool, use_json: bool) -> ExitCode
         let output = SuggestOutput {
             task: task.to_string(),
             suggestions,
-            semantic: if semantic { Some(false) } else { None },
+            semantic: semantic.then_some(true),
+            source: sync.source.as_str().to_string(),
+            offline: sync.offline().then_some(true),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -5,11 +5,13 @@
 use std::process::ExitCode;
 use std::time::Duration;
 
-use reqwest::blocking::Client;
 use reqwest::header::{ACCEPT, USER_AGENT};
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::config;
+use crate::http::HttpClient;
+
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const GITHUB_OWNER: &str = "Dicklesworthstone";
 const GITHUB_REPO: &str = "jeffreysprompts.com";
@@ -77,16 +79,15 @@ fn compare_versions(current: &str, latest: &str) -> i8 {
 }
 
 fn fetch_latest_release() -> Result<Option<GithubRelease>, String> {
-    let client = Client::builder()
-        .timeout(Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("Failed to initialize HTTP client: {e}"))?;
+    let network = config::load_config().network;
+    let client = HttpClient::new(&network, Duration::from_secs(15), &Default::default())
+        .map_err(|e| format!("Failed to initialize HTTP client: {e:#}"))?;
 
     let response = client
-        .get(RELEASE_API)
-        .header(ACCEPT, "application/vnd.github+json")
-        .header(USER_AGENT, format!("jfp-rust/{VERSION}"))
-        .send()
+        .get(RELEASE_API, |req| {
+            req.header(ACCEPT, "application/vnd.github+json")
+                .header(USER_AGENT, format!("jfp-rust/{VERSION}"))
+        })
         .map_err(|e| format!("Failed to reach GitHub API: {e}"))?;
 
     let status = response.status();
@@ -0,0 +1,200 @@
+//! Whatsnew command implementation
+//!
+//! Lists prompts added or changed by registry refreshes:
+//! - `--since <date>` shows changes after a date (YYYY-MM-DD or RFC 3339)
+//! - Without `--since`, shows changes since whatsnew was last viewed
+//! - Reads the `prompt_changes` log written by refresh and auto-sync
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::registry;
+use crate::storage::{ChangeKind, RecordedChange};
+
+/// Meta key: when `jfp whatsnew` was last viewed
+const META_WHATSNEW_VIEWED: &str = "whatsnew_last_viewed";
+
+#[derive(Serialize)]
+struct WhatsnewOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<String>,
+    changes: Vec<RecordedChange>,
+    total: usize,
+}
+
+pub fn run(since: Option<&str>, use_json: bool) -> ExitCode {
+    let explicit_since = match since.map(parse_since).transpose() {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            if use_json {
+                println!(r#"{{"error": "invalid_since", "message": "{}"}}"#, message);
+            } else {
+                eprintln!("Error: {}", message);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Open database, syncing from the registry when empty or stale
+    let (db, sync) = match registry::open_database() {
+        Ok(opened) => opened,
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    sync.report(use_json);
+
+    let advance_marker = explicit_since.is_none();
+    let since = explicit_since.or_else(|| db.get_meta(META_WHATSNEW_VIEWED).ok());
+
+    let changes = match db.changes_since(since.as_deref()) {
+        Ok(changes) => collapse_changes(changes),
+        Err(e) => {
+            if use_json {
+                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error reading changes: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Only the implicit "since last viewed" window advances the marker
+    if advance_marker {
+        let _ = db.set_meta(META_WHATSNEW_VIEWED, &Utc::now().to_rfc3339());
+    }
+
+    let total = changes.len();
+
+    if use_json {
+        let output = WhatsnewOutput {
+            since,
+            changes,
+            total,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        let window = since
+            .as_deref()
+            .map(|ts| format!(" since {}", ts))
+            .unwrap_or_default();
+
+        if changes.is_empty() {
+            println!("Nothing new{}.", window);
+        } else {
+            println!("What's new{} ({}):\n", window, total);
+            for change in &changes {
+                let label = match change.kind {
+                    ChangeKind::Added => "new",
+                    ChangeKind::ContentChanged => "updated",
+                    ChangeKind::MetadataChanged => "details",
+                    ChangeKind::Removed => "removed",
+                };
+                println!("  [{:<7}] {:<30} {}", label, change.id, change.title);
+            }
+            println!("\nRun 'jfp show <id>' to view a prompt");
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Normalize a `--since` value to an RFC 3339 UTC timestamp
+fn parse_since(value: &str) -> Result<String, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc).to_rfc3339());
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().to_rfc3339())
+        .ok_or_else(|| format!("Invalid --since '{}': expected YYYY-MM-DD or RFC 3339", value))
+}
+
+/// One entry per prompt, newest first, dropping prompts that were removed
+///
+/// A prompt added and then edited within the window is still reported as new.
+fn collapse_changes(changes: Vec<RecordedChange>) -> Vec<RecordedChange> {
+    let mut latest: Vec<RecordedChange> = Vec::new();
+    let mut index_by_id: HashMap<String, usize> = HashMap::new();
+
+    // `changes` is ordered newest first
+    for change in changes {
+        match index_by_id.get(&change.id) {
+            Some(&index) => {
+                if change.kind == ChangeKind::Added && latest[index].kind != ChangeKind::Removed {
+                    latest[index].kind = ChangeKind::Added;
+                }
+            }
+            None => {
+                index_by_id.insert(change.id.clone(), latest.len());
+                latest.push(change);
+            }
+        }
+    }
+
+    latest.retain(|change| change.kind != ChangeKind::Removed);
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(id: &str, kind: ChangeKind, changed_at: &str) -> RecordedChange {
+        RecordedChange {
+            id: id.to_string(),
+            title: id.to_string(),
+            kind,
+            changed_at: changed_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_since_accepts_dates_and_timestamps() {
+        assert_eq!(
+            parse_since("2026-01-02").as_deref(),
+            Ok("2026-01-02T00:00:00+00:00")
+        );
+        assert_eq!(
+            parse_since("2026-01-02T03:00:00+02:00").as_deref(),
+            Ok("2026-01-02T01:00:00+00:00")
+        );
+        assert!(parse_since("last week").is_err());
+    }
+
+    #[test]
+    fn collapse_keeps_one_entry_per_prompt() {
+        let changes = vec![
+            recorded("a", ChangeKind::ContentChanged, "2026-01-03"),
+            recorded("b", ChangeKind::Removed, "2026-01-03"),
+            recorded("a", ChangeKind::Added, "2026-01-02"),
+            recorded("b", ChangeKind::Added, "2026-01-01"),
+            recorded("c", ChangeKind::MetadataChanged, "2026-01-01"),
+        ];
+
+        let collapsed = collapse_changes(changes);
+
+        let kinds: Vec<_> = collapsed.iter().map(|c| (c.id.as_str(), c.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![("a", ChangeKind::Added), ("c", ChangeKind::MetadataChanged)]
+        );
+    }
+}
@@ -1,7 +1,10 @@
 //! Configuration management
 
 use directories::ProjectDirs;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::types::Config;
 
 /// Get the configuration directory path
 pub fn config_dir() -> Option<PathBuf> {
@@ -26,3 +29,202 @@ pub fn cache_dir() -> Option<PathBuf> {
     ProjectDirs::from("com", "jeffreysprompts", "jfp")
         .map(|dirs| dirs.cache_dir().to_path_buf())
 }
+
+/// Get the config file path
+///
+/// Earlier versions kept the file in `dirs::config_dir()/jfp`, which is a
+/// different directory on macOS and Windows. When only that file exists it
+/// is copied here; if the copy fails the legacy path is returned instead.
+pub fn config_path() -> Option<PathBuf> {
+    let path = config_dir().map(|d| d.join("config.toml"))?;
+    match legacy_config_path() {
+        Some(legacy) => Some(migrate_legacy_config(path, &legacy)),
+        None => Some(path),
+    }
+}
+
+/// Config file location before the move to `ProjectDirs`; `JFP_HOME` never
+/// applied to it
+fn legacy_config_path() -> Option<PathBuf> {
+    if std::env::var_os("JFP_HOME").is_some() {
+        return None;
+    }
+    dirs::config_dir().map(|d| d.join("jfp").join("config.toml"))
+}
+
+/// Copy `legacy` to `path` if only the legacy file exists, returning the
+/// path to use
+fn migrate_legacy_config(path: PathBuf, legacy: &Path) -> PathBuf {
+    if path == legacy || path.exists() || !legacy.exists() {
+        return path;
+    }
+
+    let copied = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::copy(legacy, &path));
+    match copied {
+        Ok(_) => {
+            eprintln!(
+                "Note: Moved config from {} to {}",
+                legacy.display(),
+                path.display()
+            );
+            path
+        }
+        Err(e) => {
+            eprintln!(
+                "Warning: Could not copy config from {} to {} ({}); using the old location",
+                legacy.display(),
+                path.display(),
+                e
+            );
+            legacy.to_path_buf()
+        }
+    }
+}
+
+/// Load the effective configuration
+///
+/// From EXISTING_JFP_STRUCTURE.md section 3:
+/// - Missing keys fall back to defaults
+/// - `JFP_REGISTRY_URL` overrides `registry.url` and `registry.remote` (and
+///   disables manifest checks, since the manifest belongs to the default URL)
+/// - `JFP_CACHE_TTL` overrides `registry.cacheTtl`
+///
+/// A missing or unreadable config file yields the defaults; the `config`
+/// command is responsible for surfacing parse errors to the user.
+pub fn load_config() -> Config {
+    let mut config = config_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| parse_config(&content).ok())
+        .unwrap_or_default();
+
+    apply_env_overrides(&mut config);
+    config
+}
+
+/// Parse config file contents into a `Config`
+///
+/// `jfp config set` writes dotted keys (`"registry.cacheTtl" = 60`) at the top
+/// level, so those are expanded into nested tables before deserializing.
+pub fn parse_config(content: &str) -> Result<Config, toml::de::Error> {
+    let table: toml::Table = content.parse()?;
+    let expanded = expand_dotted_keys(table);
+    toml::Value::Table(expanded).try_into()
+}
+
+fn expand_dotted_keys(table: toml::Table) -> toml::Table {
+    let mut expanded = toml::Table::new();
+
+    for (key, value) in table {
+        let value = match value {
+            toml::Value::Table(inner) => toml::Value::Table(expand_dotted_keys(inner)),
+            other => other,
+        };
+
+        let mut segments: Vec<&str> = key.split('.').collect();
+        let Some(last) = segments.pop() else {
+            continue;
+        };
+
+        let mut current = &mut expanded;
+        for segment in segments {
+            let entry = current
+                .entry(segment.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::Table::new());
+            }
+            let toml::Value::Table(next) = entry else {
+                unreachable!("entry was just replaced with a table");
+            };
+            current = next;
+        }
+
+        match (current.get_mut(last), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) => {
+                existing.extend(incoming);
+            }
+            (_, value) => {
+                current.insert(last.to_string(), value);
+            }
+        }
+    }
+
+    expanded
+}
+
+fn apply_env_overrides(config: &mut Config) {
+    if let Ok(url) = std::env::var("JFP_REGISTRY_URL")
+        && !url.trim().is_empty()
+    {
+        config.registry.url = url.clone();
+        config.registry.remote = url;
+        // The default manifest describes the default registry, not this one
+        config.registry.manifest_url = String::new();
+    }
+
+    if let Some(ttl) = std::env::var("JFP_CACHE_TTL")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+    {
+        config.registry.cache_ttl = ttl;
+    }
+
+    if std::env::var_os("JFP_NO_COLOR").is_some() {
+        config.output.color = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{migrate_legacy_config, parse_config};
+
+    #[test]
+    fn parse_config_uses_defaults_for_missing_keys() {
+        let config = parse_config("").expect("empty config should parse");
+        assert_eq!(config.registry.cache_ttl, 3600);
+        assert!(config.registry.auto_refresh);
+    }
+
+    #[test]
+    fn parse_config_reads_nested_tables() {
+        let config = parse_config("[registry]\ncacheTtl = 60\nautoRefresh = false\n")
+            .expect("nested config should parse");
+        assert_eq!(config.registry.cache_ttl, 60);
+        assert!(!config.registry.auto_refresh);
+    }
+
+    #[test]
+    fn parse_config_expands_dotted_keys_from_config_set() {
+        let config = parse_config("\"registry.cacheTtl\" = 120\n\"registry.timeoutMs\" = 500\n")
+            .expect("dotted config should parse");
+        assert_eq!(config.registry.cache_ttl, 120);
+        assert_eq!(config.registry.timeout_ms, 500);
+    }
+
+    #[test]
+    fn legacy_config_is_migrated_when_missing() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let legacy = dir.path().join("old/jfp/config.toml");
+        let path = dir.path().join("new/jfp/config.toml");
+
+        // Nothing to migrate
+        assert_eq!(migrate_legacy_config(path.clone(), &legacy), path);
+        assert!(!path.exists());
+
+        fs::create_dir_all(dir.path().join("old/jfp"))?;
+        fs::write(&legacy, "[registry]\ncacheTtl = 60\n")?;
+        assert_eq!(migrate_legacy_config(path.clone(), &legacy), path);
+        assert_eq!(fs::read_to_string(&path)?, "[registry]\ncacheTtl = 60\n");
+
+        // An existing new file wins over the legacy one
+        fs::write(&legacy, "[registry]\ncacheTtl = 5\n")?;
+        migrate_legacy_config(path.clone(), &legacy);
+        assert_eq!(fs::read_to_string(&path)?, "[registry]\ncacheTtl = 60\n");
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@ use std::process::ExitCode;
 
 mod commands;
 mod config;
+mod http;
 mod registry;
 mod storage;
 mod types;
@@ -51,16 +52,36 @@ enum Commands {
         /// Show only featured prompts
         #[arg(long)]
         featured: bool,
+
+        /// Show only starred prompts
+        #[arg(long)]
+        starred: bool,
+
+        /// Only prompts from this registry (`default`, a named registry, or `local`)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Sort by local usage: recent, frequent
+        #[arg(long)]
+        sort: Option<String>,
     },
 
     /// Search prompts by keyword
     Search {
-        /// Search query
+        /// Search query: words, "phrases", -exclude, OR, tag:, category:, featured:true
         query: String,
 
         /// Maximum number of results
         #[arg(long, short, default_value = "10")]
         limit: usize,
+
+        /// Only prompts from this registry (`default`, a named registry, or `local`)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Match words as typed, without synonyms
+        #[arg(long)]
+        no_expand: bool,
     },
 
     /// Show details for a specific prompt
@@ -128,9 +149,13 @@ enum Commands {
         #[arg(long, short, default_value = "5")]
         limit: usize,
 
-        /// Use semantic search
+        /// Also rank by similarity of meaning (offline, no model download)
         #[arg(long)]
         semantic: bool,
+
+        /// Match words as typed, without synonyms
+        #[arg(long)]
+        no_expand: bool,
     },
 
     /// List available categories
@@ -180,7 +205,148 @@ enum Commands {
     Status,
 
     /// Refresh local registry cache
-    Refresh,
+    Refresh {
+        /// Show what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Run as the detached stale-while-revalidate refresh (internal)
+        #[arg(long, hide = true)]
+        background: bool,
+    },
+
+    /// Pack or unpack the registry for offline machines
+    Registry {
+        /// Action: pack, unpack
+        action: String,
+
+        /// Pack file to write or read
+        file: std::path::PathBuf,
+    },
+
+    /// Check, repair and inspect the local database
+    Db {
+        /// Action: check, vacuum, analyze, rebuild-fts, reset, path, size
+        action: String,
+
+        /// Confirm `reset`
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Back up every prompt to a JSONL file
+    Backup {
+        /// Output file (default: an automatic, rotated backup in the cache dir)
+        path: Option<std::path::PathBuf>,
+
+        /// Number of automatic backups to keep
+        #[arg(long, default_value_t = commands::backup::DEFAULT_KEEP)]
+        keep: usize,
+    },
+
+    /// Restore prompts from a JSONL backup
+    Restore {
+        /// Backup file written by `jfp backup`
+        path: std::path::PathBuf,
+
+        /// replace: make the database match the backup; merge: only add and update
+        #[arg(long, default_value = "replace")]
+        mode: String,
+
+        /// Only check the file, don't restore
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// List the recorded revisions of a prompt
+    History {
+        /// Prompt ID
+        id: String,
+    },
+
+    /// Show a unified diff of a prompt's content between revisions
+    Diff {
+        /// Prompt ID
+        id: String,
+
+        /// Older revision (default: the one before the latest)
+        rev_a: Option<i64>,
+
+        /// Newer revision (default: the latest)
+        rev_b: Option<i64>,
+    },
+
+    /// Star a prompt (stored locally, kept across refreshes)
+    Star {
+        /// Prompt ID
+        id: String,
+    },
+
+    /// Remove a star
+    Unstar {
+        /// Prompt ID
+        id: String,
+    },
+
+    /// Curate local, ordered collections of prompts
+    Collection {
+        /// Action: create, add, rm, move, show, list, delete, export
+        #[arg(default_value = "list")]
+        action: String,
+
+        /// Collection name
+        name: Option<String>,
+
+        /// Prompt IDs (add, rm), or a prompt ID and its new position (move)
+        args: Vec<String>,
+
+        /// Description (for create)
+        #[arg(long, short)]
+        description: Option<String>,
+
+        /// Export format (md, skill)
+        #[arg(long, short, default_value = "md")]
+        format: String,
+
+        /// Export directory (default: ./<name>)
+        #[arg(long, short)]
+        output_dir: Option<String>,
+
+        /// Export to stdout instead of files
+        #[arg(long)]
+        stdout: bool,
+    },
+
+    /// Add, list, edit or remove personal notes on prompts
+    Note {
+        /// Action: add, list, edit, rm
+        action: String,
+
+        /// Prompt ID (optional for list)
+        id: Option<String>,
+
+        /// Note text (default: stdin, or $EDITOR in a terminal)
+        text: Vec<String>,
+
+        /// Note number to edit or remove, when a prompt has several
+        #[arg(long)]
+        note: Option<i64>,
+    },
+
+    /// Show your most-used prompts (local usage, see `analytics.enabled`)
+    Stats {
+        /// Prompts per time window
+        #[arg(long, short, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Show prompts added or changed by recent refreshes
+    Whatsnew {
+        /// Only changes after this date (YYYY-MM-DD or RFC 3339); defaults
+        /// to the last time whatsnew was viewed
+        #[arg(long)]
+        since: Option<String>,
+    },
 
     /// Check for CLI updates
     #[command(name = "update-cli")]
@@ -280,11 +446,19 @@ fn main() -> ExitCode {
 
     // Dispatch to command handlers
     match command {
-        Commands::List { category, tag, featured } => {
-            commands::list::run(category, tag, featured, use_json)
+        Commands::List { category, tag, featured, starred, registry, sort } => {
+            commands::list::run(
+                category,
+                tag,
+                featured,
+                starred,
+                registry,
+                sort.as_deref(),
+                use_json,
+            )
         }
-        Commands::Search { query, limit } => {
-            commands::search::run(&query, limit, use_json)
+        Commands::Search { query, limit, registry, no_expand } => {
+            commands::search::run(&query, limit, registry.as_deref(), no_expand, no_color, use_json)
         }
         Commands::Show { id, raw } => {
             commands::show::run(&id, raw, use_json)
@@ -322,14 +496,54 @@ fn main() -> ExitCode {
         Commands::Export { ids, format, output_dir, stdout } => {
             commands::export::run(ids, &format, output_dir, stdout, use_json)
         }
-        Commands::Refresh => {
-            commands::refresh::run(use_json)
+        Commands::Refresh { dry_run, background } => {
+            commands::refresh::run(dry_run, background, use_json)
+        }
+        Commands::Registry { action, file } => {
+            commands::registry::run(&action, &file, use_json)
+        }
+        Commands::Db { action, yes } => {
+            commands::db::run(&action, yes, use_json)
+        }
+        Commands::Backup { path, keep } => {
+            commands::backup::run(path, keep, use_json)
+        }
+        Commands::Restore { path, mode, verify } => {
+            commands::restore::run(&path, &mode, verify, use_json)
+        }
+        Commands::History { id } => {
+            commands::history::run(&id, use_json)
+        }
+        Commands::Diff { id, rev_a, rev_b } => {
+            commands::diff::run(&id, rev_a, rev_b, use_json)
+        }
+        Commands::Star { id } => {
+            commands::star::star(&id, use_json)
+        }
+        Commands::Unstar { id } => {
+            commands::star::unstar(&id, use_json)
+        }
+        Commands::Collection { action, name, args, description, format, output_dir, stdout } => {
+            if action == "export" {
+                commands::collection::export(name.as_deref(), &format, output_dir, stdout, use_json)
+            } else {
+                commands::collection::run(&action, name.as_deref(), &args, description.as_deref(), use_json)
+            }
+        }
+        Commands::Note { action, id, text, note } => {
+            commands::note::run(&action, id.as_deref(), &text, note, use_json)
+        }
+        Commands::Stats { limit } => {
+            commands::stats::run(limit, use_json)
+        }
+        Commands::Whatsnew { since } => {
+            commands::whatsnew::run(since.as_deref(), use_json)
         }
         Commands::Render { id, fill, context } => {
             commands::render::run(&id, fill, context, use_json)
         }
-        Commands::Suggest { task, limit, semantic } => {
-            commands::suggest::run(&task, limit, semantic, use_json)
+        Commands::Suggest { task, limit, semantic, no_expand } => {
+            commands::suggest::run(&task, limit, semantic, no_expand, use_json)
         }
         Commands::Bundles => {
             commands::bundles::list_bundles(use_json)
@@ -32,6 +32,7 @@ Provide specific, actionable feedback."#
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
         Prompt {
             id: "explain-code".to_string(),
@@ -55,6 +56,7 @@ Include:
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
         Prompt {
             id: "write-tests".to_string(),
@@ -78,6 +80,7 @@ Requirements:
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
         Prompt {
             id: "refactor".to_string(),
@@ -102,6 +105,7 @@ Explain each change you make."#
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
         Prompt {
             id: "debug".to_string(),
@@ -132,6 +136,7 @@ Please:
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
         Prompt {
             id: "documentation".to_string(),
@@ -156,6 +161,7 @@ Include:
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
         Prompt {
             id: "optimize".to_string(),
@@ -182,6 +188,7 @@ Explain the performance impact of each change."#
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
         Prompt {
             id: "api-design".to_string(),
@@ -209,6 +216,7 @@ Suggest improvements for each area."#
             author: Some("JeffreysPrompts".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         },
     ]
 }
@@ -0,0 +1,395 @@
+//! File and directory registry sources
+//!
+//! `registry.url` may name a local file or directory instead of an HTTP
+//! endpoint, either as `file:///path` or as a plain path; relative paths
+//! are taken from the directory holding `config.toml`. This lets a team
+//! point jfp at a git checkout of their prompts:
+//! - A file holds a registry payload (`{ "version"?, "prompts": [...], "bundles"? }`),
+//!   an array of prompts, or a single prompt
+//! - A directory is walked recursively (skipping dot-entries such as `.git`)
+//!   for `*.json` files in the same shapes and `*.md` prompt files; `*.md`
+//!   files without `---` frontmatter (a README) are skipped with a warning
+//! - A content hash of everything read stands in for the HTTP ETag, so a
+//!   checkout that touches mtimes without changing content is "not modified"
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::types::{Bundle, Prompt};
+
+/// Prompts read from a file or directory source
+#[derive(Debug)]
+pub struct FileRegistry {
+    pub prompts: Vec<Prompt>,
+    pub version: Option<String>,
+    /// Bundles from registry payload files
+    pub bundles: Vec<Bundle>,
+    /// `sha256:<hex>` over every file read, used as the ETag
+    pub fingerprint: String,
+    /// Raw bytes of a single-file source (empty for directories)
+    pub body: Vec<u8>,
+    /// Files that were skipped, naming each one
+    pub warnings: Vec<String>,
+}
+
+/// Local path named by a registry URL, or `None` for network URLs
+///
+/// Relative paths are resolved against the config directory, not the
+/// current directory, so the source does not depend on where jfp runs.
+pub fn local_path(url: &str) -> Option<PathBuf> {
+    let base = config::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    local_path_in(url, &base)
+}
+
+fn local_path_in(url: &str, base: &Path) -> Option<PathBuf> {
+    let url = url.trim();
+    let path = match url.strip_prefix("file://") {
+        Some(rest) => rest.strip_prefix("localhost").unwrap_or(rest),
+        None if url.is_empty() || url.contains("://") => return None,
+        None => url,
+    };
+
+    // `join` keeps absolute paths as they are
+    Some(base.join(path))
+}
+
+/// Read a registry file or directory
+pub fn read_source(path: &Path) -> Result<FileRegistry> {
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("Registry source {} not found", path.display()))?;
+
+    if metadata.is_dir() {
+        return read_directory(path);
+    }
+
+    let body = fs::read(path)
+        .with_context(|| format!("Failed to read registry file {}", path.display()))?;
+    let (prompts, version, bundles) = parse_json_prompts(&body)
+        .with_context(|| format!("Failed to parse registry file {}", path.display()))?;
+
+    Ok(FileRegistry {
+        prompts,
+        version,
+        bundles,
+        fingerprint: format!("sha256:{:x}", Sha256::digest(&body)),
+        body,
+        warnings: Vec::new(),
+    })
+}
+
+fn read_directory(dir: &Path) -> Result<FileRegistry> {
+    let mut files = Vec::new();
+    collect_prompt_files(dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    let mut prompts: Vec<Prompt> = Vec::new();
+    let mut origin: HashMap<String, String> = HashMap::new();
+    let mut version = None;
+    let mut bundles = Vec::new();
+    let mut warnings = Vec::new();
+
+    for file in files {
+        let relative = file
+            .strip_prefix(dir)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let bytes =
+            fs::read(&file).with_context(|| format!("Failed to read registry file {}", relative))?;
+
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update(&bytes);
+        hasher.update([0]);
+
+        let markdown = file.extension().is_some_and(|ext| ext == "md");
+        if markdown && !has_frontmatter(&bytes) {
+            warnings.push(format!(
+                "Skipping registry file {}: no '---' frontmatter",
+                relative
+            ));
+            continue;
+        }
+
+        let parsed = if markdown {
+            parse_markdown_prompt(&file, &bytes).map(|prompt| (vec![prompt], None, Vec::new()))
+        } else {
+            parse_json_prompts(&bytes)
+        };
+        let (file_prompts, file_version, file_bundles) =
+            parsed.with_context(|| format!("Failed to parse registry file {}", relative))?;
+
+        version = version.or(file_version);
+        bundles.extend(file_bundles);
+        for prompt in file_prompts {
+            if let Some(previous) = origin.insert(prompt.id.clone(), relative.clone()) {
+                bail!(
+                    "Duplicate prompt id '{}' in {} and {}",
+                    prompt.id,
+                    previous,
+                    relative
+                );
+            }
+            prompts.push(prompt);
+        }
+    }
+
+    Ok(FileRegistry {
+        prompts,
+        version,
+        bundles,
+        fingerprint: format!("sha256:{:x}", hasher.finalize()),
+        body: Vec::new(),
+        warnings,
+    })
+}
+
+/// Recursively gather `*.json` and `*.md` files, skipping dot-entries
+fn collect_prompt_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read registry directory {}", dir.display()))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_prompt_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "json" || ext == "md")
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// A registry payload, an array of prompts, or a single prompt
+fn parse_json_prompts(bytes: &[u8]) -> Result<(Vec<Prompt>, Option<String>, Vec<Bundle>)> {
+    let value: Value = serde_json::from_slice(bytes)?;
+
+    match value {
+        Value::Array(_) => Ok((serde_json::from_value(value)?, None, Vec::new())),
+        Value::Object(ref map) if map.contains_key("prompts") => {
+            let version = map.get("version").and_then(Value::as_str).map(str::to_string);
+            let prompts = serde_json::from_value(map["prompts"].clone())?;
+            let bundles = match map.get("bundles") {
+                Some(bundles) => serde_json::from_value(bundles.clone())?,
+                None => Vec::new(),
+            };
+            Ok((prompts, version, bundles))
+        }
+        Value::Object(_) => Ok((vec![serde_json::from_value(value)?], None, Vec::new())),
+        _ => bail!("expected a prompt, an array of prompts or a registry object"),
+    }
+}
+
+/// Whether a markdown file opens with `---` (after an optional BOM)
+fn has_frontmatter(bytes: &[u8]) -> bool {
+    let bytes = bytes.strip_prefix("\u{feff}".as_bytes()).unwrap_or(bytes);
+    bytes.starts_with(b"---")
+}
+
+/// Parse a markdown prompt with `---` frontmatter
+///
+/// Frontmatter is `key: value` lines (`id`, `title`, `description`,
+/// `category`, `tags`, `featured`, `author`, `version`); the body after it
+/// is the prompt content. `id` defaults to the file stem, `tags` may be
+/// `[a, b]` or `a, b`.
+fn parse_markdown_prompt(path: &Path, bytes: &[u8]) -> Result<Prompt> {
+    let text = std::str::from_utf8(bytes).context("file is not valid UTF-8")?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    let rest = text
+        .strip_prefix("---")
+        .and_then(|rest| rest.strip_prefix('\n').or_else(|| rest.strip_prefix("\r\n")))
+        .ok_or_else(|| anyhow!("missing '---' frontmatter"))?;
+    let (frontmatter, body) = rest
+        .split_once("\n---")
+        .ok_or_else(|| anyhow!("unterminated frontmatter"))?;
+    let body = body.split_once('\n').map(|(_, body)| body).unwrap_or("");
+
+    let mut fields: HashMap<String, String> = HashMap::new();
+    for line in frontmatter.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow!("invalid frontmatter line '{}'", line))?;
+        fields.insert(key.trim().to_string(), unquote(value.trim()).to_string());
+    }
+
+    let id = match fields.remove("id") {
+        Some(id) => id,
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("missing id"))?,
+    };
+    let title = fields
+        .remove("title")
+        .filter(|title| !title.is_empty())
+        .ok_or_else(|| anyhow!("missing title"))?;
+
+    let mut prompt = Prompt::new(id, title, body.trim());
+    prompt.description = fields.remove("description");
+    prompt.category = fields.remove("category");
+    prompt.author = fields.remove("author");
+    prompt.version = fields.remove("version");
+    prompt.featured = fields.remove("featured").is_some_and(|v| v == "true");
+    if let Some(tags) = fields.remove("tags") {
+        prompt.tags = tags
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(',')
+            .map(|tag| unquote(tag.trim()).to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+    }
+
+    if prompt.content.is_empty() {
+        bail!("prompt '{}' has no content", prompt.id);
+    }
+
+    Ok(prompt)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn local_path_accepts_file_urls_and_plain_paths() {
+        let base = Path::new("/home/me/.config/jfp");
+        assert_eq!(
+            local_path_in("file:///srv/prompts/registry.json", base),
+            Some(PathBuf::from("/srv/prompts/registry.json"))
+        );
+        assert_eq!(
+            local_path_in("file://localhost/srv/prompts", base),
+            Some(PathBuf::from("/srv/prompts"))
+        );
+        assert_eq!(local_path_in("/srv/prompts", base), Some(PathBuf::from("/srv/prompts")));
+        assert_eq!(local_path_in("prompts", base), Some(base.join("prompts")));
+        assert_eq!(local_path_in("./prompts", base), Some(base.join("./prompts")));
+        assert_eq!(local_path_in("https://jeffreysprompts.com/api/prompts", base), None);
+        assert_eq!(local_path_in("", base), None);
+    }
+
+    #[test]
+    fn reads_registry_payload_file() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("registry.json");
+        fs::write(
+            &path,
+            r#"{"version": "2.0.0", "prompts": [{"id": "a", "title": "A", "content": "alpha"}],
+                "bundles": [{"id": "starter", "title": "Starter", "featured": true, "promptIds": ["a"]}]}"#,
+        )?;
+
+        let registry = read_source(&path)?;
+
+        assert_eq!(registry.version.as_deref(), Some("2.0.0"));
+        assert_eq!(registry.prompts.len(), 1);
+        assert_eq!(registry.bundles.len(), 1);
+        assert!(registry.bundles[0].featured);
+        assert_eq!(registry.bundles[0].prompts, vec!["a".to_string()]);
+        assert!(!registry.body.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn reads_directory_of_json_and_markdown() -> Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir_all(dir.path().join("review"))?;
+        fs::create_dir_all(dir.path().join(".git"))?;
+        fs::write(dir.path().join(".git/config.json"), "not json")?;
+        fs::write(
+            dir.path().join("a.json"),
+            r#"[{"id": "a", "title": "A", "content": "alpha"}]"#,
+        )?;
+        fs::write(
+            dir.path().join("review/code-review.md"),
+            "---\ntitle: \"Code Review\"\ncategory: review\ntags: [rust, review]\nfeatured: true\n---\n\nReview this code.\n",
+        )?;
+
+        let registry = read_source(dir.path())?;
+        let review = registry
+            .prompts
+            .iter()
+            .find(|p| p.id == "code-review")
+            .expect("markdown prompt read");
+
+        assert_eq!(registry.prompts.len(), 2);
+        assert_eq!(review.title, "Code Review");
+        assert_eq!(review.tags, vec!["rust", "review"]);
+        assert!(review.featured);
+        assert_eq!(review.content, "Review this code.");
+
+        // Only content changes move the fingerprint
+        let before = registry.fingerprint;
+        assert_eq!(read_source(dir.path())?.fingerprint, before);
+        fs::write(dir.path().join("a.json"), r#"{"id": "a", "title": "A", "content": "beta"}"#)?;
+        assert_ne!(read_source(dir.path())?.fingerprint, before);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_and_bad_markdown() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("a.json"), r#"{"id": "a", "title": "A", "content": "x"}"#)?;
+        fs::write(dir.path().join("a.md"), "---\ntitle: A\n---\nbody\n")?;
+        let err = read_source(dir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("Duplicate prompt id 'a'"));
+
+        fs::remove_file(dir.path().join("a.json"))?;
+        fs::write(dir.path().join("a.md"), "---\ntitle: A\n---\n\n")?;
+        let err = read_source(dir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("a.md"), "{:#}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_markdown_without_frontmatter() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("README.md"), "# Team prompts\n\nSee below.\n")?;
+        fs::write(dir.path().join("a.md"), "---\ntitle: A\n---\nbody\n")?;
+
+        let registry = read_source(dir.path())?;
+
+        assert_eq!(registry.prompts.len(), 1);
+        assert_eq!(registry.warnings.len(), 1);
+        assert!(registry.warnings[0].contains("README.md"), "{:?}", registry.warnings);
+        Ok(())
+    }
+}
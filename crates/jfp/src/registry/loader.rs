@@ -4,7 +4,17 @@
 //! - Uses ETag with If-None-Match
 //! - Cache TTL from config
 //! - SWR: if stale and autoRefresh, triggers background refresh
-
+//! - Checks `registry.manifest.json` first; the payload is only downloaded
+//!   when the manifest differs from `CacheMeta`, and is verified before caching.
+//!   If the manifest cannot be fetched the update is refused like a failed
+//!   verification, unless `registry.allowUnverified` is set; then the payload
+//!   is cached with a warning kept in `CacheMeta` until a manifest matches it
+//! - `file://` URLs and plain paths are read from disk (see `file_source`),
+//!   with a content hash in place of the ETag
+//! - The payload's bundles are cached next to the prompts, in
+//!   `<cache>.bundles.json`
+
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{BufReader, BufWriter};
 use std::path::PathBuf;
@@ -12,14 +22,18 @@ use std::time::Duration;
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use reqwest::blocking::Client;
 use reqwest::header::{ACCEPT, ETAG, IF_NONE_MATCH, USER_AGENT};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
 use super::embedded::bundled_prompts;
+use super::file_source;
+use super::manifest::{prompt_hashes, RegistryManifest};
 use crate::config;
-use crate::types::{Prompt, Registry, RegistryLoadResult, RegistrySource};
+use crate::http::HttpClient;
+use crate::types::{
+    Bundle, NetworkConfig, Prompt, Registry, RegistryConfig, RegistryLoadResult, RegistrySource,
+};
 
 /// Default cache TTL in seconds
 const DEFAULT_CACHE_TTL: u64 = 3600;
@@ -30,6 +44,9 @@ const DEFAULT_TIMEOUT_MS: u64 = 2000;
 /// Registry API URL
 const REGISTRY_URL: &str = "https://jeffreysprompts.com/api/prompts";
 
+/// Registry manifest URL
+const MANIFEST_URL: &str = "https://jeffreysprompts.com/registry.manifest.json";
+
 /// Cached registry metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CacheMeta {
@@ -38,6 +55,12 @@ struct CacheMeta {
     etag: Option<String>,
     fetched_at: String,
     prompt_count: usize,
+    /// Per-prompt content hashes, compared against the manifest
+    #[serde(default)]
+    prompt_hashes: BTreeMap<String, String>,
+    /// Load warnings for the cached data, reported again on every cached load
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
 }
 
 /// Payload returned by `GET /api/prompts` (we only rely on a few fields).
@@ -46,21 +69,37 @@ struct RegistryApiPayload {
     #[serde(default)]
     version: Option<String>,
     prompts: Vec<Prompt>,
+    #[serde(default)]
+    bundles: Vec<Bundle>,
 }
 
 struct RemoteFetchResult {
-    /// `None` indicates a 304 Not Modified response.
-    prompts: Option<Vec<Prompt>>,
+    prompts: Vec<Prompt>,
+    bundles: Vec<Bundle>,
     etag: Option<String>,
     version: Option<String>,
+    warnings: Vec<String>,
+}
+
+/// What `fetch_update` found
+enum Update {
+    /// The cached registry is current; `verified` when the manifest matched it
+    Current { verified: bool },
+    /// A newer payload
+    Fetched(RemoteFetchResult),
 }
 
 /// Registry loader with caching
 pub struct RegistryLoader {
+    url: String,
+    manifest_url: Option<String>,
     cache_path: PathBuf,
     meta_path: PathBuf,
     cache_ttl: Duration,
     timeout: Duration,
+    network: NetworkConfig,
+    headers: BTreeMap<String, String>,
+    allow_unverified: bool,
 }
 
 impl RegistryLoader {
@@ -68,33 +107,59 @@ impl RegistryLoader {
     pub fn new() -> Self {
         let config_dir = config::config_dir().unwrap_or_else(|| PathBuf::from("."));
         Self {
+            url: REGISTRY_URL.to_string(),
+            manifest_url: Some(MANIFEST_URL.to_string()),
             cache_path: config_dir.join("registry.json"),
             meta_path: config_dir.join("registry.meta.json"),
             cache_ttl: Duration::from_secs(DEFAULT_CACHE_TTL),
             timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            network: NetworkConfig::default(),
+            headers: BTreeMap::new(),
+            allow_unverified: false,
+        }
+    }
+
+    /// Create from the `registry` section of the config file
+    pub fn from_config(config: &RegistryConfig) -> Self {
+        Self {
+            url: config.url.clone(),
+            manifest_url: Some(config.manifest_url.clone()).filter(|url| !url.trim().is_empty()),
+            cache_path: config.cache_path.clone(),
+            meta_path: config.meta_path.clone(),
+            cache_ttl: Duration::from_secs(config.cache_ttl),
+            timeout: Duration::from_millis(config.timeout_ms),
+            network: NetworkConfig::default(),
+            headers: config.headers.clone(),
+            allow_unverified: config.allow_unverified,
         }
     }
 
     /// Create with custom paths (for testing)
+    #[cfg(test)]
     pub fn with_paths(cache_path: PathBuf, meta_path: PathBuf) -> Self {
         Self {
+            url: REGISTRY_URL.to_string(),
+            manifest_url: None,
             cache_path,
             meta_path,
             cache_ttl: Duration::from_secs(DEFAULT_CACHE_TTL),
             timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            network: NetworkConfig::default(),
+            headers: BTreeMap::new(),
+            allow_unverified: false,
         }
     }
 
     /// Set cache TTL
+    #[cfg(test)]
     pub fn with_ttl(mut self, ttl: Duration) -> Self {
         self.cache_ttl = ttl;
         self
     }
 
-    /// Set request timeout
-    #[allow(dead_code)]
-    pub fn with_timeout(mut self, timeout: Duration) -> Self {
-        self.timeout = timeout;
+    /// Use the `[network]` config (proxy, CA bundle, retries)
+    pub fn with_network(mut self, network: NetworkConfig) -> Self {
+        self.network = network;
         self
     }
 
@@ -112,18 +177,22 @@ impl RegistryLoader {
             if !stale {
                 // Cache is fresh, use it
                 return Ok(RegistryLoadResult {
-                    registry: Registry::new(prompts),
+                    registry: self.cached_registry(prompts, &meta),
                     source: RegistrySource::Cache,
                     stale: false,
+                    fallback_reason: None,
+                    warnings: meta.warnings,
                 });
             }
 
-            // Cache is stale but exists - return stale data
-            // In async context, we'd trigger background refresh here
+            // Cache is stale but exists - return stale data; `open_database`
+            // revalidates in a detached `jfp refresh --background`
             return Ok(RegistryLoadResult {
-                registry: Registry::new(prompts),
+                registry: self.cached_registry(prompts, &meta),
                 source: RegistrySource::Cache,
                 stale: true,
+                fallback_reason: None,
+                warnings: meta.warnings,
             });
         }
 
@@ -132,120 +201,168 @@ impl RegistryLoader {
             registry: Registry::new(bundled_prompts()),
             source: RegistrySource::Bundled,
             stale: false,
+            fallback_reason: None,
+            warnings: Vec::new(),
         })
     }
 
     /// Load registry synchronously, attempting remote fetch
-    #[allow(dead_code)]
     pub fn load_sync(&self) -> Result<RegistryLoadResult> {
         // Try to load from cache first
         let cached = self.load_cache()?;
 
         // Check if cache is fresh
-        if let Some((prompts, meta)) = &cached {
-            if !self.is_stale(meta) {
-                return Ok(RegistryLoadResult {
-                    registry: Registry::new(prompts.clone()),
-                    source: RegistrySource::Cache,
-                    stale: false,
-                });
-            }
+        if let Some((prompts, meta)) = &cached
+            && !self.is_stale(meta)
+        {
+            return Ok(RegistryLoadResult {
+                registry: self.cached_registry(prompts.clone(), meta),
+                source: RegistrySource::Cache,
+                stale: false,
+                fallback_reason: None,
+                warnings: meta.warnings.clone(),
+            });
         }
 
-        // Cache is stale or missing - try remote
-        let etag = cached.as_ref().and_then(|(_, m)| m.etag.as_deref());
-
-        match self.fetch_remote(etag) {
-            Ok(remote) => {
-                if let Some(prompts) = remote.prompts {
-                    // Got new data - save to cache
-                    self.save_cache(&prompts, remote.etag, remote.version)?;
-                    Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
-                        source: RegistrySource::Remote,
-                        stale: false,
-                    })
-                } else {
-                    // 304 Not Modified - cache is still valid
-                    if let Some((prompts, _)) = cached {
-                        // Update cache timestamp
-                        self.touch_cache()?;
-                        Ok(RegistryLoadResult {
-                            registry: Registry::new(prompts),
-                            source: RegistrySource::Cache,
-                            stale: false,
-                        })
-                    } else {
-                        // This shouldn't happen (304 without cache)
-                        Ok(RegistryLoadResult {
-                            registry: Registry::new(bundled_prompts()),
-                            source: RegistrySource::Bundled,
-                            stale: false,
-                        })
-                    }
-                }
-            }
-            Err(_) => {
-                // Network error - use cache if available, else bundled
-                if let Some((prompts, _)) = cached {
-                    Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
-                        source: RegistrySource::Cache,
-                        stale: true,
-                    })
-                } else {
-                    Ok(RegistryLoadResult {
-                        registry: Registry::new(bundled_prompts()),
-                        source: RegistrySource::Bundled,
-                        stale: false,
-                    })
-                }
-            }
-        }
+        // Cache is stale or missing - try remote, else bundled
+        self.fetch_latest(cached, true).or_else(|e| {
+            Ok(RegistryLoadResult {
+                registry: Registry::new(bundled_prompts()),
+                source: RegistrySource::Bundled,
+                stale: false,
+                fallback_reason: Some(format!("{:#}", e)),
+                warnings: Vec::new(),
+            })
+        })
     }
 
     /// Force refresh from remote
     pub fn refresh(&self) -> Result<RegistryLoadResult> {
         let cached = self.load_cache()?;
-        let etag = cached.as_ref().and_then(|(_, m)| m.etag.as_deref());
+        self.fetch_latest(cached, true)
+    }
 
-        match self.fetch_remote(etag) {
-            Ok(remote) => {
-                if let Some(prompts) = remote.prompts {
-                    self.save_cache(&prompts, remote.etag, remote.version)?;
-                    Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
-                        source: RegistrySource::Remote,
-                        stale: false,
-                    })
-                } else if let Some((prompts, _)) = cached {
-                    // 304 Not Modified - refresh still succeeds using cached data.
-                    self.touch_cache()?;
-                    Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
-                        source: RegistrySource::Cache,
-                        stale: false,
-                    })
-                } else {
-                    // Unexpected 304 without cache
-                    self.load()
+    /// Fetch from remote like `refresh`, but leave the cache files untouched
+    /// (for `refresh --dry-run`)
+    pub fn preview(&self) -> Result<RegistryLoadResult> {
+        let cached = self.load_cache()?;
+        self.fetch_latest(cached, false)
+    }
+
+    /// Fetch updates, falling back to `cached` (marked stale) on failure
+    ///
+    /// Errors only when the fetch fails and there is no cache to fall back to.
+    fn fetch_latest(
+        &self,
+        cached: Option<(Vec<Prompt>, CacheMeta)>,
+        persist: bool,
+    ) -> Result<RegistryLoadResult> {
+        match self.fetch_update(cached.as_ref().map(|(_, meta)| meta)) {
+            Ok(Update::Fetched(remote)) => {
+                if persist {
+                    let now = Utc::now().to_rfc3339();
+                    self.save_cache(
+                        &remote.prompts,
+                        &remote.bundles,
+                        remote.etag,
+                        remote.version.clone(),
+                        &now,
+                        &remote.warnings,
+                    )?;
                 }
+                Ok(RegistryLoadResult {
+                    registry: Registry::new(remote.prompts)
+                        .with_bundles(remote.bundles)
+                        .with_version(remote.version),
+                    source: RegistrySource::Remote,
+                    stale: false,
+                    fallback_reason: None,
+                    warnings: remote.warnings,
+                })
             }
-            Err(e) => {
-                // Network error - fall back to cache if available.
-                if let Some((prompts, _)) = cached {
+            Ok(Update::Current { verified }) => match cached {
+                // Not modified - the cached copy is current, and no longer
+                // unverified once the manifest matched it
+                Some((prompts, meta)) => {
+                    if persist {
+                        self.touch_cache(verified)?;
+                    }
                     Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
+                        registry: self.cached_registry(prompts, &meta),
                         source: RegistrySource::Cache,
-                        stale: true,
+                        stale: false,
+                        fallback_reason: None,
+                        warnings: if verified { Vec::new() } else { meta.warnings },
                     })
-                } else {
-                    Err(e)
                 }
-            }
+                // Unexpected "not modified" without a cache
+                None => self.load(),
+            },
+            Err(e) => match cached {
+                Some((prompts, meta)) => Ok(RegistryLoadResult {
+                    registry: self.cached_registry(prompts, &meta),
+                    source: RegistrySource::Cache,
+                    stale: true,
+                    fallback_reason: Some(format!("{:#}", e)),
+                    warnings: meta.warnings,
+                }),
+                None => Err(e),
+            },
         }
     }
 
+    /// Check the manifest, then download and verify the payload if needed
+    ///
+    /// Returns `Update::Current` when the cached registry is still current,
+    /// either because the manifest matches `CacheMeta` or the server
+    /// answered 304.
+    /// Without a manifest URL this degrades to a plain ETag request without
+    /// verification. If the manifest cannot be fetched the update fails, so
+    /// the caller keeps its cached or bundled data; with `allow_unverified`
+    /// it is accepted instead, with a warning. File sources never use the
+    /// manifest.
+    fn fetch_update(&self, cached: Option<&CacheMeta>) -> Result<Update> {
+        let mut unverified = None;
+        let manifest = match self
+            .manifest_url
+            .as_deref()
+            .filter(|_| file_source::local_path(&self.url).is_none())
+        {
+            Some(url) => match self.fetch_manifest(url) {
+                Ok(manifest) => Some(manifest),
+                Err(e) if self.allow_unverified => {
+                    unverified = Some(format!(
+                        "Registry update not verified (manifest unavailable: {:#})",
+                        e
+                    ));
+                    None
+                }
+                Err(e) => return Err(e.context("Registry update not verified")),
+            },
+            None => None,
+        };
+
+        if let (Some(manifest), Some(meta)) = (&manifest, cached)
+            && manifest.matches(meta.version.as_deref(), &meta.prompt_hashes)
+        {
+            return Ok(Update::Current { verified: true });
+        }
+
+        let etag = cached.and_then(|meta| meta.etag.as_deref());
+        let Some((mut remote, body)) = self.fetch_remote(etag)? else {
+            return Ok(Update::Current { verified: false });
+        };
+        remote.warnings.extend(unverified);
+
+        if let Some(manifest) = &manifest {
+            manifest
+                .verify(&body, &remote.prompts)
+                .map_err(|reason| anyhow::anyhow!("Registry integrity check failed: {}", reason))?;
+        }
+
+        Ok(Update::Fetched(remote))
+    }
+
     /// Load prompts from cache
     fn load_cache(&self) -> Result<Option<(Vec<Prompt>, CacheMeta)>> {
         if !self.cache_path.exists() {
@@ -266,6 +383,8 @@ impl RegistryLoader {
                 etag: None,
                 fetched_at: Utc::now().to_rfc3339(),
                 prompt_count: prompts.len(),
+                prompt_hashes: BTreeMap::new(),
+                warnings: Vec::new(),
             })
         } else {
             CacheMeta {
@@ -273,14 +392,44 @@ impl RegistryLoader {
                 etag: None,
                 fetched_at: Utc::now().to_rfc3339(),
                 prompt_count: prompts.len(),
+                prompt_hashes: BTreeMap::new(),
+                warnings: Vec::new(),
             }
         };
 
         Ok(Some((prompts, meta)))
     }
 
+    /// Cached prompts with the bundles and payload version cached alongside them
+    fn cached_registry(&self, prompts: Vec<Prompt>, meta: &CacheMeta) -> Registry {
+        Registry::new(prompts)
+            .with_bundles(self.load_cached_bundles())
+            .with_version(meta.version.clone())
+    }
+
+    /// Bundles from the cache sidecar; none if it is missing or unreadable
+    /// (caches written before bundles were kept)
+    fn load_cached_bundles(&self) -> Vec<Bundle> {
+        fs::File::open(self.bundles_path())
+            .ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+            .unwrap_or_default()
+    }
+
+    fn bundles_path(&self) -> PathBuf {
+        self.cache_path.with_extension("bundles.json")
+    }
+
     /// Save prompts to cache
-    fn save_cache(&self, prompts: &[Prompt], etag: Option<String>, version: Option<String>) -> Result<()> {
+    fn save_cache(
+        &self,
+        prompts: &[Prompt],
+        bundles: &[Bundle],
+        etag: Option<String>,
+        version: Option<String>,
+        fetched_at: &str,
+        warnings: &[String],
+    ) -> Result<()> {
         // Ensure directory exists
         if let Some(parent) = self.cache_path.parent() {
             fs::create_dir_all(parent)?;
@@ -295,12 +444,22 @@ impl RegistryLoader {
         }
         fs::rename(&temp_path, &self.cache_path)?;
 
+        let bundles_path = self.bundles_path();
+        let temp_bundles = bundles_path.with_extension("tmp");
+        {
+            let file = fs::File::create(&temp_bundles)?;
+            serde_json::to_writer(BufWriter::new(file), bundles)?;
+        }
+        fs::rename(&temp_bundles, &bundles_path)?;
+
         // Save metadata
         let meta = CacheMeta {
             version,
             etag,
-            fetched_at: Utc::now().to_rfc3339(),
+            fetched_at: fetched_at.to_string(),
             prompt_count: prompts.len(),
+            prompt_hashes: prompt_hashes(prompts),
+            warnings: warnings.to_vec(),
         };
 
         let temp_meta = self.meta_path.with_extension("tmp");
@@ -314,20 +473,38 @@ impl RegistryLoader {
         Ok(())
     }
 
-    /// Update cache timestamp without re-fetching
-    fn touch_cache(&self) -> Result<()> {
-        if let Ok(file) = fs::File::open(&self.meta_path) {
-            if let Ok(mut meta) = serde_json::from_reader::<_, CacheMeta>(BufReader::new(file)) {
-                meta.fetched_at = Utc::now().to_rfc3339();
+    /// Replace the cache with prompts fetched elsewhere at `fetched_at`
+    ///
+    /// Used by `jfp registry unpack`; keeping the original fetch time means
+    /// the cache goes stale when the packed data would have.
+    pub fn import_cache(
+        &self,
+        prompts: &[Prompt],
+        bundles: &[Bundle],
+        version: Option<String>,
+        fetched_at: &str,
+    ) -> Result<()> {
+        self.save_cache(prompts, bundles, None, version, fetched_at, &[])
+    }
 
-                let temp_meta = self.meta_path.with_extension("tmp");
-                {
-                    let file = fs::File::create(&temp_meta)?;
-                    let writer = BufWriter::new(file);
-                    serde_json::to_writer(writer, &meta)?;
-                }
-                fs::rename(&temp_meta, &self.meta_path)?;
+    /// Update cache timestamp without re-fetching, dropping the cached
+    /// warnings once the data has been `verified`
+    fn touch_cache(&self, verified: bool) -> Result<()> {
+        if let Ok(file) = fs::File::open(&self.meta_path)
+            && let Ok(mut meta) = serde_json::from_reader::<_, CacheMeta>(BufReader::new(file))
+        {
+            meta.fetched_at = Utc::now().to_rfc3339();
+            if verified {
+                meta.warnings.clear();
+            }
+
+            let temp_meta = self.meta_path.with_extension("tmp");
+            {
+                let file = fs::File::create(&temp_meta)?;
+                let writer = BufWriter::new(file);
+                serde_json::to_writer(writer, &meta)?;
             }
+            fs::rename(&temp_meta, &self.meta_path)?;
         }
         Ok(())
     }
@@ -342,30 +519,84 @@ impl RegistryLoader {
         elapsed.num_seconds() > self.cache_ttl.as_secs() as i64
     }
 
-    /// Fetch from remote API
-    fn fetch_remote(&self, etag: Option<&str>) -> Result<RemoteFetchResult> {
-        let client = Client::builder()
-            .timeout(self.timeout)
-            .build()
-            .context("Failed to build registry HTTP client")?;
+    fn client(&self) -> Result<HttpClient> {
+        HttpClient::new(&self.network, self.timeout, &self.headers)
+            .context("Failed to build registry HTTP client")
+    }
 
-        let mut req = client
-            .get(REGISTRY_URL)
-            .header(ACCEPT, "application/json")
-            .header(USER_AGENT, format!("jfp/{}", env!("CARGO_PKG_VERSION")));
+    /// Fetch the registry manifest
+    fn fetch_manifest(&self, url: &str) -> Result<RegistryManifest> {
+        let resp = self
+            .client()?
+            .get(url, |req| {
+                req.header(ACCEPT, "application/json")
+                    .header(USER_AGENT, format!("jfp/{}", env!("CARGO_PKG_VERSION")))
+            })
+            .context("Failed to fetch registry manifest")?;
 
-        if let Some(etag) = etag {
-            req = req.header(IF_NONE_MATCH, etag);
+        if !resp.status().is_success() {
+            anyhow::bail!("Manifest request failed with status {}", resp.status());
         }
 
-        let resp = req.send().context("Failed to fetch registry")?;
+        resp.json().context("Failed to parse registry manifest")
+    }
+
+    /// Fetch the registry from its source, dispatching on the URL scheme
+    ///
+    /// Returns `None` when `etag` is still current (304 Not Modified, or an
+    /// unchanged content hash for file sources), otherwise the parsed
+    /// payload and the raw body (for checksum verification).
+    fn fetch_remote(&self, etag: Option<&str>) -> Result<Option<(RemoteFetchResult, Vec<u8>)>> {
+        match file_source::local_path(&self.url) {
+            Some(path) => self.fetch_file(&path, etag),
+            None => self.fetch_http(&self.client()?, etag),
+        }
+    }
+
+    /// Read a `file://` or plain-path registry source
+    fn fetch_file(
+        &self,
+        path: &std::path::Path,
+        etag: Option<&str>,
+    ) -> Result<Option<(RemoteFetchResult, Vec<u8>)>> {
+        let registry = file_source::read_source(path)?;
+
+        if etag == Some(registry.fingerprint.as_str()) {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            RemoteFetchResult {
+                prompts: registry.prompts,
+                bundles: registry.bundles,
+                etag: Some(registry.fingerprint),
+                version: registry.version,
+                warnings: registry.warnings,
+            },
+            registry.body,
+        )))
+    }
+
+    /// Fetch from the HTTP API with If-None-Match
+    fn fetch_http(
+        &self,
+        client: &HttpClient,
+        etag: Option<&str>,
+    ) -> Result<Option<(RemoteFetchResult, Vec<u8>)>> {
+        let resp = client
+            .get(&self.url, |req| {
+                let req = req
+                    .header(ACCEPT, "application/json")
+                    .header(USER_AGENT, format!("jfp/{}", env!("CARGO_PKG_VERSION")));
+                match etag {
+                    Some(etag) => req.header(IF_NONE_MATCH, etag),
+                    None => req,
+                }
+            })
+            .context("Failed to fetch registry")?;
 
         if resp.status() == StatusCode::NOT_MODIFIED {
-            return Ok(RemoteFetchResult {
-                prompts: None,
-                etag: None,
-                version: None,
-            });
+            return Ok(None);
         }
 
         if !resp.status().is_success() {
@@ -378,13 +609,20 @@ impl RegistryLoader {
             .and_then(|value| value.to_str().ok())
             .map(|value| value.to_string());
 
-        let payload: RegistryApiPayload = resp.json().context("Failed to parse registry JSON")?;
-
-        Ok(RemoteFetchResult {
-            prompts: Some(payload.prompts),
-            etag: response_etag,
-            version: payload.version,
-        })
+        let body = resp.bytes().context("Failed to read registry response")?.to_vec();
+        let payload: RegistryApiPayload =
+            serde_json::from_slice(&body).context("Failed to parse registry JSON")?;
+
+        Ok(Some((
+            RemoteFetchResult {
+                prompts: payload.prompts,
+                bundles: payload.bundles,
+                etag: response_etag,
+                version: payload.version,
+                warnings: Vec::new(),
+            },
+            body,
+        )))
     }
 }
 
@@ -394,22 +632,6 @@ impl Default for RegistryLoader {
     }
 }
 
-/// Get the cache file path
-#[allow(dead_code)]
-pub fn cache_path() -> PathBuf {
-    config::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("registry.json")
-}
-
-/// Get the metadata file path
-#[allow(dead_code)]
-pub fn meta_path() -> PathBuf {
-    config::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("registry.meta.json")
-}
-
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -446,6 +668,8 @@ mod tests {
             etag: None,
             fetched_at: Utc::now().to_rfc3339(),
             prompt_count: 1,
+            prompt_hashes: BTreeMap::new(),
+            warnings: Vec::new(),
         };
         fs::write(&meta, serde_json::to_string(&cache_meta)?)?;
 
@@ -476,6 +700,8 @@ mod tests {
             etag: None,
             fetched_at: old_time.to_rfc3339(),
             prompt_count: 1,
+            prompt_hashes: BTreeMap::new(),
+            warnings: Vec::new(),
         };
         fs::write(&meta, serde_json::to_string(&cache_meta)?)?;
 
@@ -486,4 +712,220 @@ mod tests {
         assert!(result.stale);
         Ok(())
     }
+
+    /// Serve fixed bodies by path on a local port; returns the base URL
+    fn serve(routes: Vec<(&'static str, String)>) -> Result<String> {
+        serve_with_status(routes.into_iter().map(|(path, body)| (path, 200, body)).collect())
+    }
+
+    /// Like `serve`, with a status code per route
+    fn serve_with_status(routes: Vec<(&'static str, u16, String)>) -> Result<String> {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let base = format!("http://{}", listener.local_addr()?);
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 4096];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let response = match routes.iter().find(|(route, _, _)| *route == path) {
+                    Some((_, status, body)) => format!(
+                        "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string(),
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        Ok(base)
+    }
+
+    fn served_loader(dir: &std::path::Path, base: &str) -> RegistryLoader {
+        let mut loader = RegistryLoader::with_paths(
+            dir.join("registry.json"),
+            dir.join("registry.meta.json"),
+        );
+        loader.url = format!("{}/api/prompts", base);
+        loader.manifest_url = Some(format!("{}/registry.manifest.json", base));
+        loader
+    }
+
+    fn manifest_json(version: &str, prompts: &[Prompt]) -> String {
+        serde_json::json!({
+            "version": version,
+            "checksums": { "prompts": prompt_hashes(prompts) },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_refresh_verifies_payload_against_manifest() -> Result<()> {
+        let dir = tempdir()?;
+        let prompts = vec![Prompt::new("a", "A", "alpha")];
+        let payload = serde_json::json!({ "version": "2", "prompts": prompts }).to_string();
+        let base = serve(vec![
+            ("/registry.manifest.json", manifest_json("2", &prompts)),
+            ("/api/prompts", payload),
+        ])?;
+        let loader = served_loader(dir.path(), &base);
+
+        let result = loader.refresh()?;
+        assert_eq!(result.source, RegistrySource::Remote);
+        assert!(result.fallback_reason.is_none());
+        assert_eq!(result.registry.meta.version, "2");
+
+        // A second refresh is answered by the manifest alone
+        let result = loader.refresh()?;
+        assert_eq!(result.source, RegistrySource::Cache);
+        assert!(!result.stale);
+        assert_eq!(result.registry.meta.version, "2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_failure_keeps_cache() -> Result<()> {
+        let dir = tempdir()?;
+        let cached = vec![Prompt::new("a", "A", "alpha")];
+        fs::write(dir.path().join("registry.json"), serde_json::to_string(&cached)?)?;
+
+        let payload = serde_json::json!({
+            "version": "2",
+            "prompts": [Prompt::new("a", "A", "unverified")],
+        })
+        .to_string();
+        let base = serve_with_status(vec![
+            ("/registry.manifest.json", 500, String::new()),
+            ("/api/prompts", 200, payload),
+        ])?;
+        let loader = served_loader(dir.path(), &base).with_network(NetworkConfig {
+            retries: 0,
+            ..NetworkConfig::default()
+        });
+
+        let result = loader.refresh()?;
+
+        assert_eq!(result.source, RegistrySource::Cache);
+        assert!(result.stale);
+        assert_eq!(result.registry.prompts[0].content, "alpha");
+        let reason = result.fallback_reason.unwrap_or_default();
+        assert!(reason.contains("not verified"), "{}", reason);
+        let on_disk: Vec<Prompt> =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("registry.json"))?)?;
+        assert_eq!(on_disk[0].content, "alpha");
+
+        // Without a cache, loading falls back to the bundled prompts
+        fs::remove_file(dir.path().join("registry.json"))?;
+        let result = loader.load()?;
+        assert_eq!(result.source, RegistrySource::Bundled);
+        Ok(())
+    }
+
+    #[test]
+    fn test_allow_unverified_accepts_update_without_manifest() -> Result<()> {
+        let dir = tempdir()?;
+        let prompts = vec![Prompt::new("a", "A", "alpha")];
+        let payload = serde_json::json!({ "version": "2", "prompts": prompts }).to_string();
+        let base = serve_with_status(vec![
+            ("/registry.manifest.json", 500, String::new()),
+            ("/api/prompts", 200, payload),
+        ])?;
+        let mut loader = served_loader(dir.path(), &base).with_network(NetworkConfig {
+            retries: 0,
+            ..NetworkConfig::default()
+        });
+        loader.allow_unverified = true;
+
+        let result = loader.refresh()?;
+
+        assert_eq!(result.source, RegistrySource::Remote);
+        assert!(result.fallback_reason.is_none());
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("not verified"), "{:?}", result.warnings);
+        assert!(result.warnings[0].contains("500"), "{:?}", result.warnings);
+        // The warning stays with the cached data until a manifest matches it
+        assert_eq!(loader.load()?.warnings, result.warnings);
+        let base = serve(vec![("/registry.manifest.json", manifest_json("2", &prompts))])?;
+        let loader = served_loader(dir.path(), &base);
+        let result = loader.refresh()?;
+        assert_eq!(result.source, RegistrySource::Cache);
+        assert!(result.warnings.is_empty());
+        assert!(loader.load()?.warnings.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_mismatch_falls_back_to_cache() -> Result<()> {
+        let dir = tempdir()?;
+        let cached = vec![Prompt::new("a", "A", "alpha")];
+        fs::write(dir.path().join("registry.json"), serde_json::to_string(&cached)?)?;
+
+        let tampered = vec![Prompt::new("a", "A", "tampered")];
+        let payload = serde_json::json!({ "version": "2", "prompts": tampered }).to_string();
+        let base = serve(vec![
+            ("/registry.manifest.json", manifest_json("2", &[Prompt::new("a", "A", "beta")])),
+            ("/api/prompts", payload),
+        ])?;
+        let loader = served_loader(dir.path(), &base);
+
+        let result = loader.refresh()?;
+
+        assert_eq!(result.source, RegistrySource::Cache);
+        assert!(result.stale);
+        assert_eq!(result.registry.prompts[0].content, "alpha");
+        let reason = result.fallback_reason.unwrap_or_default();
+        assert!(reason.contains("integrity check failed"), "{}", reason);
+        // The cache was not overwritten
+        let on_disk: Vec<Prompt> =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("registry.json"))?)?;
+        assert_eq!(on_disk[0].content, "alpha");
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_from_fixture_directory() -> Result<()> {
+        let dir = tempdir()?;
+        let source = dir.path().join("team-prompts");
+        fs::create_dir_all(&source)?;
+        fs::write(
+            source.join("review.md"),
+            "---\ntitle: Review\n---\nReview the diff.\n",
+        )?;
+        fs::write(source.join("README.md"), "# Team prompts\n")?;
+
+        let mut loader = RegistryLoader::with_paths(
+            dir.path().join("registry.json"),
+            dir.path().join("registry.meta.json"),
+        );
+        loader.url = format!("file://{}", source.display());
+        loader.manifest_url = Some("http://127.0.0.1:9/registry.manifest.json".to_string());
+
+        let result = loader.refresh()?;
+        assert_eq!(result.source, RegistrySource::Remote);
+        assert_eq!(result.registry.prompts[0].id, "review");
+        assert_eq!(
+            result.warnings,
+            vec!["Skipping registry file README.md: no '---' frontmatter".to_string()]
+        );
+
+        // Unchanged content hash behaves like 304 Not Modified; the cached
+        // data keeps its warnings
+        let result = loader.refresh()?;
+        assert_eq!(result.source, RegistrySource::Cache);
+        assert!(!result.stale);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(loader.load()?.warnings.len(), 1);
+
+        fs::write(source.join("extra.json"), r#"{"id": "extra", "title": "Extra", "content": "x"}"#)?;
+        let result = loader.refresh()?;
+        assert_eq!(result.source, RegistrySource::Remote);
+        assert_eq!(result.registry.prompts.len(), 2);
+        assert_eq!(loader.load()?.registry.prompts.len(), 2);
+        Ok(())
+    }
 }
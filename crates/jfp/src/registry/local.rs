@@ -0,0 +1,259 @@
+//! Local prompts loader
+//!
+//! From EXISTING_JFP_STRUCTURE.md section 6 (Local Prompts Validation):
+//! - Reads `~/.config/jfp/local/*.json`
+//! - Each file holds a single prompt or an array of prompts
+//! - Invalid prompts produce warnings instead of aborting
+//! - Merge order: cached/remote/bundled -> local (later wins by id)
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::manifest::sha256_hex;
+use crate::storage::LOCAL_SOURCE;
+use crate::types::{LocalPromptsConfig, Prompt};
+
+/// Prompts read from the local prompts directory
+#[derive(Debug, Default)]
+pub struct LocalPrompts {
+    pub prompts: Vec<Prompt>,
+    /// Human-readable problems, each naming the file (and prompt id if known)
+    pub warnings: Vec<String>,
+}
+
+impl LocalPrompts {
+    /// Stable digest of the loaded prompts, used to detect edits between runs
+    ///
+    /// SHA-256 of the serialized prompts, so it stays the same across
+    /// Rust releases and jfp builds.
+    pub fn fingerprint(&self) -> String {
+        sha256_hex(&serde_json::to_vec(&self.prompts).unwrap_or_default())
+    }
+}
+
+/// Load local prompts according to config (empty when disabled)
+pub fn load_configured_local_prompts(config: &LocalPromptsConfig) -> LocalPrompts {
+    if !config.enabled {
+        return LocalPrompts::default();
+    }
+    load_local_prompts(&config.dir)
+}
+
+/// Load and validate every `*.json` file in `dir`
+///
+/// Files are read in name order so that duplicate ids resolve predictably
+/// (the later file wins, with a warning).
+pub fn load_local_prompts(dir: &Path) -> LocalPrompts {
+    let mut result = LocalPrompts::default();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return result,
+        Err(e) => {
+            result
+                .warnings
+                .push(format!("Cannot read local prompts dir {}: {}", dir.display(), e));
+            return result;
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut seen: HashMap<String, String> = HashMap::new();
+
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+
+        let parsed: Value = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        {
+            Ok(value) => value,
+            Err(e) => {
+                result
+                    .warnings
+                    .push(format!("Skipping local prompts file {}: {}", file_name, e));
+                continue;
+            }
+        };
+
+        let items = match parsed {
+            Value::Array(items) => items,
+            other => vec![other],
+        };
+
+        for (index, item) in items.into_iter().enumerate() {
+            let raw_id = item
+                .get("id")
+                .and_then(Value::as_str)
+                .map(|id| id.to_string());
+
+            let prompt = serde_json::from_value::<Prompt>(item)
+                .map_err(|e| e.to_string())
+                .and_then(|prompt| prompt.validate().map(|()| prompt));
+
+            match prompt {
+                Ok(mut prompt) => {
+                    prompt.is_local = true;
+                    prompt.source = Some(LOCAL_SOURCE.to_string());
+                    if let Some(previous) = seen.insert(prompt.id.clone(), file_name.clone()) {
+                        result.warnings.push(format!(
+                            "Local prompt '{}' in {} overrides the one in {}",
+                            prompt.id, file_name, previous
+                        ));
+                        result.prompts.retain(|p| p.id != prompt.id);
+                    }
+                    result.prompts.push(prompt);
+                }
+                Err(reason) => {
+                    let warning = match raw_id {
+                        Some(id) => format!(
+                            "Invalid local prompt '{}' in {}: {}",
+                            id, file_name, reason
+                        ),
+                        None => format!(
+                            "Invalid local prompt #{} in {}: {}",
+                            index + 1,
+                            file_name,
+                            reason
+                        ),
+                    };
+                    result.warnings.push(warning);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Merge `local` over `base`; local prompts replace base prompts by id
+pub fn merge_local_prompts(base: Vec<Prompt>, local: &[Prompt]) -> Vec<Prompt> {
+    if local.is_empty() {
+        return base;
+    }
+
+    let mut merged = base;
+    let mut index_by_id: HashMap<String, usize> = merged
+        .iter()
+        .enumerate()
+        .map(|(index, prompt)| (prompt.id.clone(), index))
+        .collect();
+
+    for prompt in local {
+        match index_by_id.get(&prompt.id) {
+            Some(&index) => merged[index] = prompt.clone(),
+            None => {
+                index_by_id.insert(prompt.id.clone(), merged.len());
+                merged.push(prompt.clone());
+            }
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn loads_single_object_and_array_files() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("one.json"),
+            r#"{"id": "team-review", "title": "Team Review", "content": "Review this"}"#,
+        )?;
+        fs::write(
+            dir.path().join("many.json"),
+            r#"[{"id": "a-prompt", "title": "A", "content": "a"},
+                {"id": "b-prompt", "title": "B", "content": "b"}]"#,
+        )?;
+        fs::write(dir.path().join("notes.txt"), "ignored")?;
+
+        let local = load_local_prompts(dir.path());
+
+        assert!(local.warnings.is_empty(), "{:?}", local.warnings);
+        assert_eq!(local.prompts.len(), 3);
+        assert!(local.prompts.iter().all(|p| p.is_local));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_entries_warn_with_file_and_id() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("team.json"),
+            r#"[{"id": "Bad_Id", "title": "Bad", "content": "x"},
+                {"id": "good-one", "title": "Good", "content": "y"},
+                {"title": "No id"}]"#,
+        )?;
+        fs::write(dir.path().join("broken.json"), "{not json")?;
+
+        let local = load_local_prompts(dir.path());
+
+        assert_eq!(local.prompts.len(), 1);
+        assert_eq!(local.prompts[0].id, "good-one");
+        assert_eq!(local.warnings.len(), 3);
+        assert!(local.warnings.iter().any(|w| w.contains("broken.json")));
+        assert!(local
+            .warnings
+            .iter()
+            .any(|w| w.contains("'Bad_Id'") && w.contains("team.json")));
+        assert!(local.warnings.iter().any(|w| w.contains("#3 in team.json")));
+        Ok(())
+    }
+
+    #[test]
+    fn missing_dir_is_empty_without_warnings() {
+        let local = load_local_prompts(Path::new("/nonexistent/jfp/local"));
+        assert!(local.prompts.is_empty());
+        assert!(local.warnings.is_empty());
+    }
+
+    #[test]
+    fn fingerprint_is_a_content_sha256() {
+        let local = LocalPrompts {
+            prompts: vec![Prompt::new("team-review", "Team Review", "Review this")],
+            warnings: Vec::new(),
+        };
+        let fingerprint = local.fingerprint();
+
+        assert_eq!(fingerprint.len(), "sha256:".len() + 64);
+        assert_eq!(LocalPrompts::default().fingerprint(), sha256_hex(b"[]"));
+
+        let edited = LocalPrompts {
+            prompts: vec![Prompt::new("team-review", "Team Review", "Review that")],
+            warnings: Vec::new(),
+        };
+        assert_ne!(edited.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn local_prompts_win_by_id() {
+        let base = vec![
+            Prompt::new("code-review", "Registry Review", "registry"),
+            Prompt::new("debug", "Debug", "registry"),
+        ];
+        let mut local = Prompt::new("code-review", "Team Review", "ours");
+        local.is_local = true;
+
+        let merged = merge_local_prompts(base, &[local, Prompt::new("extra", "Extra", "x")]);
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].title, "Team Review");
+        assert!(merged[0].is_local);
+        assert_eq!(merged[2].id, "extra");
+    }
+}
@@ -0,0 +1,511 @@
+//! Cross-process refresh lock
+//!
+//! Registry refreshes (foreground `jfp refresh`, `jfp registry unpack` and
+//! the detached stale-while-revalidate child) hold `refresh.lock` in
+//! `config::cache_dir()`, so concurrent shells and agents never run two
+//! refreshes or write `registry.json` and `jfp.db` at the same time.
+//!
+//! The lock file is created exclusively and records the holder's pid and
+//! start time. A lock whose holder is gone (or that is older than
+//! `LOCK_STALE_AFTER_SECS`) is treated as stale and taken over: under an
+//! advisory lock on `refresh.takeover`, a new lock file is renamed over the
+//! stale one, so the path never goes missing and two processes cannot both
+//! recover the same stale lock. Releasing removes the file under the same
+//! advisory lock, and only while it still holds the releasing holder's
+//! token, so a release never deletes a lock another process took over. The
+//! last contention and the last stale-lock recovery are kept in
+//! `registry_meta` for `jfp status`.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::is_older_than;
+use crate::config;
+use crate::storage::Database;
+
+/// Lock file name inside the cache dir
+pub const LOCK_FILE: &str = "refresh.lock";
+
+/// A lock held longer than this is assumed abandoned
+pub const LOCK_STALE_AFTER_SECS: u64 = 300;
+
+/// Whether `LockInfo::is_stale` can tell that the holder has exited; where
+/// it cannot, only `LOCK_STALE_AFTER_SECS` makes a lock stale
+pub const HOLDER_LIVENESS_CHECKED: bool = cfg!(unix);
+
+/// How long a foreground refresh waits for a running one to finish
+pub const REFRESH_LOCK_WAIT: Duration = Duration::from_secs(10);
+
+/// How often `lock_refresh` re-checks a busy lock
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Meta key: last time a refresh found the lock held by another process
+pub const META_LOCK_CONTENTION: &str = "refresh_lock_contention";
+
+/// Meta key: last time a stale lock was taken over
+pub const META_LOCK_RECOVERED: &str = "refresh_lock_recovered";
+
+/// Contents of the lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub acquired_at: String,
+}
+
+impl LockInfo {
+    /// Whether the holder has exited or held the lock implausibly long
+    ///
+    /// Off Unix the holder's pid is not probed (see
+    /// `HOLDER_LIVENESS_CHECKED`), so a crashed holder's lock is only
+    /// recovered once it is `LOCK_STALE_AFTER_SECS` old.
+    pub fn is_stale(&self) -> bool {
+        !pid_alive(self.pid) || is_older_than(Some(&self.acquired_at), LOCK_STALE_AFTER_SECS)
+    }
+}
+
+/// A contention or recovery event, stored as JSON in `registry_meta`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockEvent {
+    /// When it happened
+    pub at: String,
+    /// Pid recorded in the lock at the time
+    pub pid: u32,
+    /// When that process took the lock
+    pub locked_since: String,
+}
+
+/// A held refresh lock; the file is removed on drop
+#[derive(Debug)]
+pub struct RefreshLock {
+    path: PathBuf,
+    /// What we wrote to the lock file, compared before removing it
+    token: LockInfo,
+}
+
+impl Drop for RefreshLock {
+    fn drop(&mut self) {
+        // Under the takeover guard, so the file cannot be replaced between
+        // the check and the removal
+        let Ok(guard) = open_takeover_guard(&self.path) else {
+            return;
+        };
+        if guard.lock().is_err() {
+            return;
+        }
+
+        if read_refresh_lock(&self.path).is_some_and(|info| info == self.token) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Result of trying to take the lock
+#[derive(Debug)]
+pub struct LockAttempt {
+    /// The lock, if it was acquired
+    pub lock: Option<RefreshLock>,
+    /// Who holds the lock when it could not be acquired
+    pub holder: Option<LockInfo>,
+    /// A stale lock that was removed on the way to acquiring
+    pub recovered: Option<LockInfo>,
+}
+
+/// Default lock path: `<cache_dir>/refresh.lock`
+pub fn refresh_lock_path() -> PathBuf {
+    config::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(LOCK_FILE)
+}
+
+/// Read the lock file, if present and parseable
+pub fn read_refresh_lock(path: &Path) -> Option<LockInfo> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Try once to take the lock, recovering it if stale
+pub fn try_lock_refresh(path: &Path) -> Result<LockAttempt> {
+    if let Some(lock) = create_lock(path)? {
+        return Ok(LockAttempt {
+            lock: Some(lock),
+            holder: None,
+            recovered: None,
+        });
+    }
+
+    let holder = current_holder(path);
+    if !is_abandoned(&holder) {
+        return Ok(LockAttempt {
+            lock: None,
+            holder: Some(holder),
+            recovered: None,
+        });
+    }
+
+    match take_over_lock(path)? {
+        Some(lock) => Ok(LockAttempt {
+            lock: Some(lock),
+            holder: None,
+            recovered: Some(holder),
+        }),
+        // Another process recovered it first
+        None => Ok(LockAttempt {
+            lock: None,
+            holder: read_refresh_lock(path),
+            recovered: None,
+        }),
+    }
+}
+
+/// Take the lock, waiting up to `wait` for another holder to finish
+pub fn lock_refresh(path: &Path, wait: Duration) -> Result<LockAttempt> {
+    let started = Instant::now();
+    let mut recovered = None;
+
+    loop {
+        let mut attempt = try_lock_refresh(path)?;
+        recovered = recovered.or(attempt.recovered.take());
+
+        if attempt.lock.is_some() || started.elapsed() >= wait {
+            attempt.recovered = recovered;
+            return Ok(attempt);
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Take the lock for a foreground refresh, waiting up to
+/// `REFRESH_LOCK_WAIT` and recording contention or recovery in `db`
+pub fn hold_refresh_lock(db: &Database) -> Result<RefreshLock> {
+    let attempt = lock_refresh(&refresh_lock_path(), REFRESH_LOCK_WAIT)?;
+    record_lock_attempt(db, &attempt)?;
+
+    attempt.lock.ok_or_else(|| match attempt.holder {
+        Some(holder) => anyhow!(
+            "Another refresh is running (pid {} since {})",
+            holder.pid,
+            holder.acquired_at
+        ),
+        None => anyhow!("Another refresh is running"),
+    })
+}
+
+/// Record contention and stale-lock recovery from a lock attempt
+pub fn record_lock_attempt(db: &Database, attempt: &LockAttempt) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    let event = |info: &LockInfo| LockEvent {
+        at: now.clone(),
+        pid: info.pid,
+        locked_since: info.acquired_at.clone(),
+    };
+
+    if let Some(stale) = &attempt.recovered {
+        db.set_meta(META_LOCK_RECOVERED, &serde_json::to_string(&event(stale))?)?;
+    }
+    if attempt.lock.is_none()
+        && let Some(holder) = &attempt.holder
+    {
+        db.set_meta(META_LOCK_CONTENTION, &serde_json::to_string(&event(holder))?)?;
+    }
+
+    Ok(())
+}
+
+/// Read a recorded lock event (`META_LOCK_CONTENTION` or `META_LOCK_RECOVERED`)
+pub fn read_lock_event(db: &Database, key: &str) -> Option<LockEvent> {
+    let value = db.get_meta(key).ok()?;
+    serde_json::from_str(&value).ok()
+}
+
+/// Create the lock file exclusively; `None` if it already exists
+fn create_lock(path: &Path) -> Result<Option<RefreshLock>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to create lock {}", path.display()))
+        }
+    };
+
+    let info = LockInfo {
+        pid: std::process::id(),
+        acquired_at: Utc::now().to_rfc3339(),
+    };
+    file.write_all(serde_json::to_string(&info)?.as_bytes())?;
+
+    Ok(Some(RefreshLock {
+        path: path.to_path_buf(),
+        token: info,
+    }))
+}
+
+/// Replace an abandoned lock with our own; `None` if another process is
+/// taking it over or already has
+///
+/// Takeovers are serialized by an advisory lock on a sidecar file (not on
+/// the lock itself, which is replaced), and the abandonment is re-checked
+/// under it. The new lock is written to a temp file and renamed into place,
+/// then read back to confirm it is ours.
+fn take_over_lock(path: &Path) -> Result<Option<RefreshLock>> {
+    let guard = open_takeover_guard(path)?;
+    match guard.try_lock() {
+        Ok(()) => {}
+        Err(fs::TryLockError::WouldBlock) => return Ok(None),
+        Err(fs::TryLockError::Error(e)) => {
+            return Err(e).with_context(|| {
+                format!("Failed to lock {}", path.with_extension("takeover").display())
+            })
+        }
+    }
+
+    if !is_abandoned(&current_holder(path)) {
+        return Ok(None);
+    }
+
+    let info = LockInfo {
+        pid: std::process::id(),
+        acquired_at: Utc::now().to_rfc3339(),
+    };
+    let temp_path = path.with_extension(format!("{}.tmp", info.pid));
+    fs::write(&temp_path, serde_json::to_string(&info)?)?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e).with_context(|| format!("Failed to replace stale lock {}", path.display()));
+    }
+
+    let ours = read_refresh_lock(path).is_some_and(|holder| holder == info);
+    Ok(ours.then(|| RefreshLock {
+        path: path.to_path_buf(),
+        token: info,
+    }))
+}
+
+/// Open `refresh.takeover`, the sidecar file whose advisory lock serializes
+/// takeovers and releases of `path`
+fn open_takeover_guard(path: &Path) -> Result<fs::File> {
+    let guard_path = path.with_extension("takeover");
+    fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&guard_path)
+        .with_context(|| format!("Failed to open {}", guard_path.display()))
+}
+
+/// The recorded holder; an unreadable lock (e.g. mid-write) is attributed
+/// to pid 0 as of the file's mtime
+fn current_holder(path: &Path) -> LockInfo {
+    read_refresh_lock(path).unwrap_or_else(|| LockInfo {
+        pid: 0,
+        acquired_at: modified_at(path).unwrap_or_else(|| Utc::now().to_rfc3339()),
+    })
+}
+
+/// Whether a lock may be taken over: its holder is gone or took too long,
+/// or it has been unreadable for too long
+fn is_abandoned(holder: &LockInfo) -> bool {
+    if holder.pid == 0 {
+        return is_older_than(Some(&holder.acquired_at), LOCK_STALE_AFTER_SECS);
+    }
+    holder.is_stale()
+}
+
+fn modified_at(path: &Path) -> Option<String> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(chrono::DateTime::<Utc>::from(modified).to_rfc3339())
+}
+
+/// Whether a process with `pid` exists
+///
+/// `kill(pid, 0)` sends no signal; `EPERM` means the process exists but
+/// belongs to another user.
+#[cfg(unix)]
+fn pid_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        // 0 and negative pids address process groups, not a holder
+        return false;
+    }
+
+    // SAFETY: signal 0 only checks that the target exists
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Liveness cannot be probed here, so the holder is assumed alive
+#[cfg(not(unix))]
+fn pid_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn write_lock(path: &Path, pid: u32, acquired_at: &str) -> Result<()> {
+        let info = LockInfo {
+            pid,
+            acquired_at: acquired_at.to_string(),
+        };
+        fs::write(path, serde_json::to_string(&info)?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn lock_is_exclusive_and_released_on_drop() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LOCK_FILE);
+
+        let first = try_lock_refresh(&path)?;
+        assert!(first.lock.is_some());
+
+        let second = try_lock_refresh(&path)?;
+        assert!(second.lock.is_none());
+        assert_eq!(second.holder.map(|h| h.pid), Some(std::process::id()));
+
+        drop(first);
+        assert!(!path.exists());
+        assert!(try_lock_refresh(&path)?.lock.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn release_keeps_a_lock_taken_over_meanwhile() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LOCK_FILE);
+
+        let first = try_lock_refresh(&path)?;
+        assert!(first.lock.is_some());
+
+        // Another holder in the same process replaced it, e.g. after the
+        // first one was judged stale
+        let successor = "2020-01-01T00:00:00+00:00";
+        write_lock(&path, std::process::id(), successor)?;
+
+        drop(first);
+        let holder = read_refresh_lock(&path);
+        assert_eq!(holder.map(|h| h.acquired_at), Some(successor.to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn old_lock_is_recovered() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LOCK_FILE);
+        let old = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        write_lock(&path, std::process::id(), &old)?;
+
+        let attempt = try_lock_refresh(&path)?;
+
+        assert!(attempt.lock.is_some());
+        assert_eq!(attempt.recovered.map(|info| info.acquired_at), Some(old));
+        Ok(())
+    }
+
+    /// Pid of a child that has exited and been reaped
+    #[cfg(unix)]
+    fn exited_pid() -> Result<u32> {
+        let mut child = std::process::Command::new("true").spawn()?;
+        let pid = child.id();
+        child.wait()?;
+        Ok(pid)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pid_alive_probes_the_process() -> Result<()> {
+        assert!(pid_alive(std::process::id()));
+        assert!(!pid_alive(exited_pid()?));
+        assert!(!pid_alive(0));
+        assert!(!pid_alive(u32::MAX));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lock_of_exited_process_is_recovered() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LOCK_FILE);
+        write_lock(&path, exited_pid()?, &Utc::now().to_rfc3339())?;
+
+        let attempt = try_lock_refresh(&path)?;
+
+        assert!(attempt.lock.is_some());
+        assert!(attempt.recovered.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn stale_lock_is_recovered_only_once() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LOCK_FILE);
+        let old = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        write_lock(&path, 4242, &old)?;
+
+        let attempts: Vec<LockAttempt> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| try_lock_refresh(&path)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("thread panicked"))
+                .collect::<Result<_>>()
+        })?;
+
+        assert_eq!(attempts.iter().filter(|a| a.lock.is_some()).count(), 1);
+        assert_eq!(attempts.iter().filter(|a| a.recovered.is_some()).count(), 1);
+        assert_eq!(read_refresh_lock(&path).map(|h| h.pid), Some(std::process::id()));
+        Ok(())
+    }
+
+    #[test]
+    fn acquire_gives_up_after_waiting() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LOCK_FILE);
+        let _held = try_lock_refresh(&path)?;
+
+        let attempt = lock_refresh(&path, Duration::from_millis(150))?;
+
+        assert!(attempt.lock.is_none());
+        assert!(attempt.holder.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn contention_and_recovery_are_recorded() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LOCK_FILE);
+        let db = Database::in_memory()?;
+        let old = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        write_lock(&path, 4242, &old)?;
+
+        let held = try_lock_refresh(&path)?;
+        record_lock_attempt(&db, &held)?;
+        let recovered = read_lock_event(&db, META_LOCK_RECOVERED).expect("recovery recorded");
+        assert_eq!((recovered.pid, recovered.locked_since), (4242, old));
+        assert!(read_lock_event(&db, META_LOCK_CONTENTION).is_none());
+
+        record_lock_attempt(&db, &try_lock_refresh(&path)?)?;
+        let contention = read_lock_event(&db, META_LOCK_CONTENTION).expect("contention recorded");
+        assert_eq!(contention.pid, std::process::id());
+        Ok(())
+    }
+}
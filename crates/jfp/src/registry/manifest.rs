@@ -0,0 +1,138 @@
+//! Registry manifest
+//!
+//! From scripts/build-data.ts, `registry.manifest.json` carries the registry
+//! version plus sha256 checksums of the whole payload and of each prompt's
+//! content. The loader fetches this small file before the full payload to
+//! skip unchanged downloads, and checks the payload against it before caching.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::types::Prompt;
+
+/// Parsed `registry.manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryManifest {
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub checksums: ManifestChecksums,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManifestChecksums {
+    /// `sha256:<hex>` of the registry payload
+    #[serde(default)]
+    pub registry: Option<String>,
+    /// Prompt id -> `sha256:<hex>` of the prompt content
+    #[serde(default)]
+    pub prompts: BTreeMap<String, String>,
+}
+
+impl RegistryManifest {
+    /// Whether the manifest describes exactly the cached registry
+    pub fn matches(&self, version: Option<&str>, hashes: &BTreeMap<String, String>) -> bool {
+        !hashes.is_empty() && self.version.as_deref() == version && self.checksums.prompts == *hashes
+    }
+
+    /// Check a downloaded payload against the manifest
+    ///
+    /// The registry checksum is computed over the build output, which the
+    /// API does not always serve byte-for-byte, so a mismatch there falls
+    /// back to checking every prompt's content hash and the set of ids.
+    pub fn verify(&self, body: &[u8], prompts: &[Prompt]) -> Result<(), String> {
+        if self.checksums.registry.as_deref() == Some(sha256_hex(body).as_str()) {
+            return Ok(());
+        }
+
+        if self.checksums.prompts.is_empty() {
+            return Err("registry checksum does not match manifest".to_string());
+        }
+
+        let actual = prompt_hashes(prompts);
+        for (id, expected) in &self.checksums.prompts {
+            match actual.get(id) {
+                Some(hash) if hash == expected => {}
+                Some(_) => return Err(format!("checksum mismatch for prompt '{}'", id)),
+                None => return Err(format!("prompt '{}' listed in manifest is missing", id)),
+            }
+        }
+
+        if let Some(extra) = actual.keys().find(|id| !self.checksums.prompts.contains_key(*id)) {
+            return Err(format!("prompt '{}' is not listed in manifest", extra));
+        }
+
+        Ok(())
+    }
+}
+
+/// `sha256:<hex>` digest, in the manifest's format
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(bytes))
+}
+
+/// Content hash of every prompt, keyed by id
+pub fn prompt_hashes(prompts: &[Prompt]) -> BTreeMap<String, String> {
+    prompts
+        .iter()
+        .map(|p| (p.id.clone(), sha256_hex(p.content.as_bytes())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest_for(prompts: &[Prompt], registry: Option<String>) -> RegistryManifest {
+        RegistryManifest {
+            version: Some("1.0.0".to_string()),
+            checksums: ManifestChecksums {
+                registry,
+                prompts: prompt_hashes(prompts),
+            },
+        }
+    }
+
+    #[test]
+    fn sha256_hex_matches_known_digest() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn verify_accepts_matching_registry_checksum() {
+        let body = br#"{"prompts":[]}"#;
+        let manifest = manifest_for(&[], Some(sha256_hex(body)));
+        assert!(manifest.verify(body, &[]).is_ok());
+    }
+
+    #[test]
+    fn verify_falls_back_to_prompt_checksums() {
+        let prompts = vec![Prompt::new("a", "A", "alpha"), Prompt::new("b", "B", "beta")];
+        let manifest = manifest_for(&prompts, Some("sha256:other".to_string()));
+        assert!(manifest.verify(b"reformatted body", &prompts).is_ok());
+
+        let mut tampered = prompts.clone();
+        tampered[1].content = "evil".to_string();
+        let err = manifest.verify(b"", &tampered).unwrap_err();
+        assert!(err.contains("'b'"), "{}", err);
+
+        let err = manifest.verify(b"", &prompts[..1]).unwrap_err();
+        assert!(err.contains("missing"), "{}", err);
+    }
+
+    #[test]
+    fn matches_requires_same_version_and_hashes() {
+        let prompts = vec![Prompt::new("a", "A", "alpha")];
+        let manifest = manifest_for(&prompts, None);
+        let hashes = prompt_hashes(&prompts);
+
+        assert!(manifest.matches(Some("1.0.0"), &hashes));
+        assert!(!manifest.matches(Some("0.9.0"), &hashes));
+        assert!(!manifest.matches(Some("1.0.0"), &BTreeMap::new()));
+    }
+}
@@ -5,6 +5,18 @@
 
 mod loader;
 mod embedded;
+mod file_source;
+mod local;
+mod lock;
+mod manifest;
+mod named;
+mod pack;
+mod sync;
 
 pub use loader::*;
 pub use embedded::*;
+pub use local::*;
+pub use lock::*;
+pub use named::*;
+pub use pack::*;
+pub use sync::*;
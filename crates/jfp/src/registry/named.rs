@@ -0,0 +1,450 @@
+//! Named registries
+//!
+//! Besides the default `[registry]`, the config may list `[[registries]]`
+//! with a name, URL, priority and enabled flag. Each one has its own cache
+//! and meta files (and so its own ETag) under `registries/` in the config
+//! dir. Their prompts are tagged with the registry name in the `source`
+//! column; when two registries ship the same id, the higher-priority one
+//! keeps the bare id and the other copy is stored as `<name>/<id>`.
+//! Bundles merge the same way by bundle id, and their prompt lists follow
+//! any renamed prompt ids.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::embedded::bundled_prompts;
+use super::RegistryLoader;
+use crate::config;
+use crate::storage::{DEFAULT_SOURCE, LOCAL_SOURCE};
+use crate::types::{
+    is_kebab_case, Bundle, Config, Prompt, Registry, RegistryLoadResult, RegistrySource,
+};
+
+/// One registry and the loader for its cache
+pub struct NamedRegistry {
+    pub name: String,
+    pub priority: i64,
+    pub loader: RegistryLoader,
+}
+
+impl NamedRegistry {
+    fn is_default(&self) -> bool {
+        self.name == DEFAULT_SOURCE
+    }
+}
+
+/// How each registry in a set is loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Cache only (`RegistryLoader::load`)
+    Cached,
+    /// Cache if fresh, else remote (`RegistryLoader::load_sync`)
+    Sync,
+    /// Always check the remote (`RegistryLoader::refresh`)
+    Refresh,
+    /// Like `Refresh`, without writing the cache files
+    Preview,
+}
+
+/// Prompts from every enabled registry, ready to reconcile into the database
+pub struct MergedLoad {
+    /// Combined prompts; `source`, `stale` and the registry version describe
+    /// the default registry, `fallback_reason` collects the reasons of every
+    /// registry that fell back
+    pub result: RegistryLoadResult,
+    /// Registries whose stored prompts must not be pruned because this load
+    /// is not a complete snapshot of them
+    pub preserve_sources: Vec<String>,
+    /// When the data was originally fetched, if not just now (packed imports)
+    pub fetched_at: Option<String>,
+}
+
+/// The default registry plus any enabled named registries, highest priority first
+pub struct RegistrySet {
+    registries: Vec<NamedRegistry>,
+    /// Problems with the `[[registries]]` config
+    pub warnings: Vec<String>,
+}
+
+impl RegistrySet {
+    /// Build the set from the config file
+    ///
+    /// Entries with an invalid or duplicate name are skipped with a warning.
+    pub fn from_config(config: &Config) -> Self {
+        let dir = config::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("registries");
+
+        let mut registries = vec![NamedRegistry {
+            name: DEFAULT_SOURCE.to_string(),
+            priority: config.registry.priority,
+            loader: RegistryLoader::from_config(&config.registry)
+                .with_network(config.network.clone()),
+        }];
+        let mut warnings = Vec::new();
+        let mut seen: HashSet<&str> = HashSet::new();
+
+        for named in &config.registries {
+            if !named.enabled {
+                continue;
+            }
+            if !is_kebab_case(&named.name)
+                || named.name == DEFAULT_SOURCE
+                || named.name == LOCAL_SOURCE
+            {
+                warnings.push(format!(
+                    "Skipping registry '{}': name must be kebab-case and not '{}' or '{}'",
+                    named.name, DEFAULT_SOURCE, LOCAL_SOURCE
+                ));
+                continue;
+            }
+            if !seen.insert(named.name.as_str()) {
+                warnings.push(format!("Skipping duplicate registry '{}'", named.name));
+                continue;
+            }
+
+            let mut registry = config.registry.clone();
+            registry.url = named.url.clone();
+            registry.remote = named.url.clone();
+            registry.manifest_url = named.manifest_url.clone().unwrap_or_default();
+            registry.cache_path = dir.join(format!("{}.json", named.name));
+            registry.meta_path = dir.join(format!("{}.meta.json", named.name));
+            registry.headers = named.headers.clone();
+
+            registries.push(NamedRegistry {
+                name: named.name.clone(),
+                priority: named.priority,
+                loader: RegistryLoader::from_config(&registry)
+                    .with_network(config.network.clone()),
+            });
+        }
+
+        let mut set = Self::new(registries);
+        set.warnings = warnings;
+        set
+    }
+
+    /// Build a set from explicit registries, ordering them by priority
+    ///
+    /// Ties go to the default registry, then to the name.
+    pub fn new(mut registries: Vec<NamedRegistry>) -> Self {
+        registries.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| b.is_default().cmp(&a.is_default()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Self {
+            registries,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// A set containing only the default registry
+    #[cfg(test)]
+    pub fn single(loader: RegistryLoader) -> Self {
+        Self::new(vec![NamedRegistry {
+            name: DEFAULT_SOURCE.to_string(),
+            priority: 0,
+            loader,
+        }])
+    }
+
+    /// Registry names, highest priority first
+    pub fn names(&self) -> Vec<&str> {
+        self.registries.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    /// Seed each registry's cache from packed prompts (`jfp registry unpack`)
+    ///
+    /// Prompts are grouped by `source`, and ids namespaced as `<name>/<id>`
+    /// get their bare id back so the usual merge re-resolves collisions.
+    /// Bundles already refer to the merged ids, so they are all cached with
+    /// the default registry. Returns the packed registry names that are not
+    /// configured here; their prompts are skipped.
+    pub fn import_packed(
+        &self,
+        prompts: &[Prompt],
+        bundles: &[Bundle],
+        version: Option<&str>,
+        fetched_at: &str,
+    ) -> Result<Vec<String>> {
+        let mut by_source: BTreeMap<String, Vec<Prompt>> = BTreeMap::new();
+        for prompt in prompts {
+            let source = prompt.source.as_deref().unwrap_or(DEFAULT_SOURCE).to_string();
+            let mut prompt = prompt.clone();
+            if let Some(bare) = prompt.id.strip_prefix(&format!("{}/", source)) {
+                prompt.id = bare.to_string();
+            }
+            prompt.source = None;
+            by_source.entry(source).or_default().push(prompt);
+        }
+
+        let mut skipped = Vec::new();
+        for (source, prompts) in by_source {
+            match self.registries.iter().find(|r| r.name == source) {
+                Some(registry) => {
+                    let version = version.filter(|_| registry.is_default()).map(str::to_string);
+                    let bundles = if registry.is_default() { bundles } else { &[] };
+                    registry
+                        .loader
+                        .import_cache(&prompts, bundles, version, fetched_at)?;
+                }
+                None => skipped.push(source),
+            }
+        }
+
+        Ok(skipped)
+    }
+
+    /// Load every registry and merge the results
+    ///
+    /// The default registry falls back to bundled prompts when it has
+    /// nothing else; a named registry with no data contributes nothing and
+    /// keeps whatever is already stored for it.
+    pub fn load(&self, mode: LoadMode) -> MergedLoad {
+        let mut prompts: Vec<Prompt> = Vec::new();
+        let mut bundles: Vec<Bundle> = Vec::new();
+        let mut taken: HashSet<String> = HashSet::new();
+        let mut preserve_sources = Vec::new();
+        let mut reasons = Vec::new();
+        let mut warnings = Vec::new();
+        let mut source = RegistrySource::Bundled;
+        let mut version = None;
+        let mut stale = false;
+        let label_reasons = self.registries.len() > 1;
+
+        for registry in &self.registries {
+            let loaded = match mode {
+                LoadMode::Cached => registry.loader.load(),
+                LoadMode::Sync => registry.loader.load_sync(),
+                LoadMode::Refresh => registry.loader.refresh(),
+                LoadMode::Preview => registry.loader.preview(),
+            };
+
+            let result = match loaded {
+                Ok(result) if registry.is_default() || result.source != RegistrySource::Bundled => {
+                    result
+                }
+                // Named registries never fall back to the bundled prompts
+                Ok(result) => {
+                    if let Some(reason) = result.fallback_reason {
+                        reasons.push(format!("{}: {}", registry.name, reason));
+                    }
+                    preserve_sources.push(registry.name.clone());
+                    continue;
+                }
+                Err(e) if registry.is_default() => RegistryLoadResult {
+                    registry: Registry::new(bundled_prompts()),
+                    source: RegistrySource::Bundled,
+                    stale: false,
+                    fallback_reason: Some(format!("{:#}", e)),
+                    warnings: Vec::new(),
+                },
+                Err(e) => {
+                    reasons.push(format!("{}: {:#}", registry.name, e));
+                    preserve_sources.push(registry.name.clone());
+                    continue;
+                }
+            };
+
+            if registry.is_default() {
+                source = result.source;
+                version = Some(result.registry.meta.version.clone());
+                // The bundled set is a subset, not a registry snapshot
+                if result.source == RegistrySource::Bundled {
+                    preserve_sources.push(registry.name.clone());
+                }
+            }
+            stale |= result.stale;
+
+            if let Some(reason) = result.fallback_reason {
+                if label_reasons {
+                    reasons.push(format!("{}: {}", registry.name, reason));
+                } else {
+                    reasons.push(reason);
+                }
+            }
+            for warning in result.warnings {
+                if label_reasons {
+                    warnings.push(format!("{}: {}", registry.name, warning));
+                } else {
+                    warnings.push(warning);
+                }
+            }
+
+            let mut renamed: HashMap<String, String> = HashMap::new();
+            for mut prompt in result.registry.prompts {
+                if !taken.insert(prompt.id.clone()) {
+                    let id = format!("{}/{}", registry.name, prompt.id);
+                    renamed.insert(std::mem::replace(&mut prompt.id, id), prompt.id.clone());
+                    taken.insert(prompt.id.clone());
+                }
+                prompt.source = Some(registry.name.clone());
+                prompts.push(prompt);
+            }
+
+            // Higher-priority registries keep their definition of a bundle
+            for mut bundle in result.registry.bundles {
+                if bundles.iter().any(|b| b.id == bundle.id) {
+                    continue;
+                }
+                for id in &mut bundle.prompts {
+                    if let Some(new_id) = renamed.get(id) {
+                        *id = new_id.clone();
+                    }
+                }
+                bundles.push(bundle);
+            }
+        }
+
+        MergedLoad {
+            result: RegistryLoadResult {
+                registry: Registry::new(prompts).with_bundles(bundles).with_version(version),
+                source,
+                stale,
+                fallback_reason: Some(reasons.join("; ")).filter(|r| !r.is_empty()),
+                warnings,
+            },
+            preserve_sources,
+            fetched_at: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+    use chrono::Utc;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn cached_registry(
+        dir: &std::path::Path,
+        name: &str,
+        priority: i64,
+        prompts: Option<&[Prompt]>,
+    ) -> Result<NamedRegistry> {
+        let cache = dir.join(format!("{}.json", name));
+        let meta = dir.join(format!("{}.meta.json", name));
+        if let Some(prompts) = prompts {
+            fs::write(&cache, serde_json::to_string(prompts)?)?;
+            fs::write(
+                &meta,
+                serde_json::json!({
+                    "etag": null,
+                    "fetched_at": Utc::now().to_rfc3339(),
+                    "prompt_count": prompts.len(),
+                })
+                .to_string(),
+            )?;
+        }
+        Ok(NamedRegistry {
+            name: name.to_string(),
+            priority,
+            loader: RegistryLoader::with_paths(cache, meta),
+        })
+    }
+
+    #[test]
+    fn colliding_ids_are_namespaced_by_priority() -> Result<()> {
+        let dir = tempdir()?;
+        let set = RegistrySet::new(vec![
+            cached_registry(dir.path(), "default", 0, Some(&[Prompt::new("code-review", "Default", "a")]))?,
+            cached_registry(dir.path(), "team", 10, Some(&[Prompt::new("code-review", "Team", "b")]))?,
+        ]);
+
+        assert_eq!(set.names(), vec!["team", "default"]);
+
+        let merged = set.load(LoadMode::Cached);
+        let prompts = &merged.result.registry.prompts;
+        let ids: Vec<_> = prompts
+            .iter()
+            .map(|p| (p.id.as_str(), p.source.as_deref()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("code-review", Some("team")),
+                ("default/code-review", Some("default"))
+            ]
+        );
+        assert_eq!(merged.result.source, RegistrySource::Cache);
+        assert!(merged.preserve_sources.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn bundles_merge_by_priority_and_follow_renamed_ids() -> Result<()> {
+        let dir = tempdir()?;
+        let default = cached_registry(dir.path(), "default", 0, Some(&[Prompt::new("code-review", "Default", "a")]))?;
+        let team = cached_registry(dir.path(), "team", 10, Some(&[Prompt::new("code-review", "Team", "b")]))?;
+        let bundle = |title: &str| Bundle {
+            prompts: vec!["code-review".to_string()],
+            ..Bundle::new("starter", title)
+        };
+        fs::write(
+            dir.path().join("default.bundles.json"),
+            serde_json::to_string(&[bundle("Default"), Bundle::new("extra", "Extra")])?,
+        )?;
+        fs::write(dir.path().join("team.bundles.json"), serde_json::to_string(&[bundle("Team")])?)?;
+
+        let merged = RegistrySet::new(vec![default, team]).load(LoadMode::Cached);
+        let bundles = &merged.result.registry.bundles;
+
+        assert_eq!(bundles.len(), 2);
+        assert_eq!(bundles[0].title, "Team");
+        assert_eq!(bundles[0].prompts, vec!["code-review".to_string()]);
+        assert_eq!(bundles[1].id, "extra");
+
+        // The default registry's copy of the bundle points at its renamed prompt
+        fs::remove_file(dir.path().join("team.bundles.json"))?;
+        let set = RegistrySet::new(vec![
+            cached_registry(dir.path(), "default", 0, None)?,
+            cached_registry(dir.path(), "team", 10, None)?,
+        ]);
+        let bundles = set.load(LoadMode::Cached).result.registry.bundles;
+        assert_eq!(bundles[0].prompts, vec!["default/code-review".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn named_registry_without_cache_is_preserved_not_bundled() -> Result<()> {
+        let dir = tempdir()?;
+        let set = RegistrySet::new(vec![
+            cached_registry(dir.path(), "default", 0, Some(&[Prompt::new("a", "A", "a")]))?,
+            cached_registry(dir.path(), "team", 0, None)?,
+        ]);
+
+        let merged = set.load(LoadMode::Cached);
+
+        assert_eq!(merged.result.registry.prompts.len(), 1);
+        assert_eq!(merged.preserve_sources, vec!["team".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_registry_names_are_skipped() {
+        let mut config = Config::default();
+        for name in ["Team Prompts", "local", "team", "team"] {
+            config.registries.push(crate::types::NamedRegistryConfig {
+                name: name.to_string(),
+                url: "https://example.invalid/prompts".to_string(),
+                manifest_url: None,
+                priority: 0,
+                enabled: true,
+                headers: Default::default(),
+            });
+        }
+
+        let set = RegistrySet::from_config(&config);
+
+        assert_eq!(set.names(), vec!["default", "team"]);
+        assert_eq!(set.warnings.len(), 3);
+    }
+}
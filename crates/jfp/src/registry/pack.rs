@@ -0,0 +1,321 @@
+//! Offline registry packs
+//!
+//! `jfp registry pack` writes the registry prompts in the database to a
+//! single self-describing JSON archive for machines with no network, and
+//! `jfp registry unpack` verifies one and imports it through the same
+//! cache + reconcile path `refresh` uses. The archive carries:
+//! - the registry payload (`{ version, prompts }`, prompts tagged by source)
+//! - a manifest in the `registry.manifest.json` format with its checksums
+//! - bundle definitions and their checksum
+//! - metadata, including when the data was originally fetched
+
+use std::fs;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::manifest::{prompt_hashes, sha256_hex, ManifestChecksums, RegistryManifest};
+use super::{is_older_than, LoadMode, MergedLoad, RegistrySet};
+use crate::storage::DEFAULT_SOURCE;
+use crate::types::{Bundle, Prompt, RegistrySource};
+
+/// Value of `format` in every pack
+pub const PACK_FORMAT: &str = "jfp-registry-pack";
+
+/// Newest pack layout this binary understands
+pub const PACK_FORMAT_VERSION: u32 = 1;
+
+/// A `jfp registry pack` archive
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegistryPack {
+    pub format: String,
+    pub format_version: u32,
+    pub metadata: PackMetadata,
+    pub registry: PackPayload,
+    pub manifest: RegistryManifest,
+    #[serde(default)]
+    pub bundles: Vec<PackBundle>,
+    /// `sha256:<hex>` of the serialized `bundles`
+    pub bundles_checksum: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackMetadata {
+    pub created_at: String,
+    /// When the packed data was last fetched from its registry
+    pub fetched_at: String,
+    /// `RegistrySource` of the data when it was packed
+    pub source: String,
+    pub jfp_version: String,
+    pub prompt_count: usize,
+    /// Registry names present in the payload
+    pub registries: Vec<String>,
+}
+
+/// Registry payload, in the shape served by `/api/prompts`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackPayload {
+    #[serde(default)]
+    pub version: Option<String>,
+    pub prompts: Vec<Prompt>,
+}
+
+/// A bundle as carried in a pack
+///
+/// Fields added after the first pack layout are skipped when empty, so
+/// older packs still match their bundles checksum.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackBundle {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub prompts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub featured: bool,
+}
+
+impl From<&Bundle> for PackBundle {
+    fn from(bundle: &Bundle) -> Self {
+        Self {
+            id: bundle.id.clone(),
+            title: bundle.title.clone(),
+            description: bundle.description.clone(),
+            prompts: bundle.prompts.clone(),
+            version: bundle.version.clone(),
+            author: bundle.author.clone(),
+            featured: bundle.featured,
+        }
+    }
+}
+
+impl From<&PackBundle> for Bundle {
+    fn from(bundle: &PackBundle) -> Self {
+        Self {
+            description: bundle.description.clone(),
+            version: bundle.version.clone(),
+            prompt_count: bundle.prompts.len(),
+            featured: bundle.featured,
+            author: bundle.author.clone(),
+            prompts: bundle.prompts.clone(),
+            ..Bundle::new(&bundle.id, &bundle.title)
+        }
+    }
+}
+
+impl RegistryPack {
+    /// Build a pack from registry prompts (local prompts are left out) and
+    /// the registry payload version they were synced from
+    pub fn new(
+        prompts: Vec<Prompt>,
+        bundles: Vec<PackBundle>,
+        version: Option<String>,
+        fetched_at: &str,
+        source: RegistrySource,
+    ) -> Result<Self> {
+        let prompts: Vec<Prompt> = prompts.into_iter().filter(|p| !p.is_local).collect();
+
+        let mut registries: Vec<String> = prompts
+            .iter()
+            .map(|p| p.source.clone().unwrap_or_else(|| DEFAULT_SOURCE.to_string()))
+            .collect();
+        registries.sort();
+        registries.dedup();
+
+        let registry = PackPayload { version, prompts };
+        let manifest = RegistryManifest {
+            version: registry.version.clone(),
+            checksums: ManifestChecksums {
+                registry: Some(sha256_hex(&serde_json::to_vec(&registry)?)),
+                prompts: prompt_hashes(&registry.prompts),
+            },
+        };
+
+        Ok(Self {
+            format: PACK_FORMAT.to_string(),
+            format_version: PACK_FORMAT_VERSION,
+            metadata: PackMetadata {
+                created_at: Utc::now().to_rfc3339(),
+                fetched_at: fetched_at.to_string(),
+                source: source.as_str().to_string(),
+                jfp_version: env!("CARGO_PKG_VERSION").to_string(),
+                prompt_count: registry.prompts.len(),
+                registries,
+            },
+            bundles_checksum: sha256_hex(&serde_json::to_vec(&bundles)?),
+            registry,
+            manifest,
+            bundles,
+        })
+    }
+
+    /// Read a pack file without verifying it
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open pack {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to parse pack {}", path.display()))
+    }
+
+    /// Write the pack atomically (temp file + rename)
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let temp_path = path.with_extension("tmp");
+        {
+            let file = fs::File::create(&temp_path)
+                .with_context(|| format!("Failed to create {}", temp_path.display()))?;
+            serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        }
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Check the format, the counts and every checksum
+    pub fn verify(&self) -> Result<()> {
+        if self.format != PACK_FORMAT {
+            bail!("not a jfp registry pack (format '{}')", self.format);
+        }
+        if self.format_version > PACK_FORMAT_VERSION {
+            bail!(
+                "pack format version {} is newer than this jfp supports ({})",
+                self.format_version,
+                PACK_FORMAT_VERSION
+            );
+        }
+        if self.metadata.prompt_count != self.registry.prompts.len() {
+            bail!(
+                "pack lists {} prompts but contains {}",
+                self.metadata.prompt_count,
+                self.registry.prompts.len()
+            );
+        }
+
+        let body = serde_json::to_vec(&self.registry)?;
+        self.manifest
+            .verify(&body, &self.registry.prompts)
+            .map_err(|reason| anyhow::anyhow!("Pack integrity check failed: {}", reason))?;
+
+        if sha256_hex(&serde_json::to_vec(&self.bundles)?) != self.bundles_checksum {
+            bail!("Pack integrity check failed: bundles checksum does not match");
+        }
+
+        Ok(())
+    }
+
+    /// Seed the registry caches from the pack and load them back as a
+    /// packed import, ready for `apply_load_result`
+    ///
+    /// Returns the load plus the packed registries not configured here.
+    pub fn import(&self, registries: &RegistrySet, cache_ttl: u64) -> Result<(MergedLoad, Vec<String>)> {
+        let fetched_at = &self.metadata.fetched_at;
+        let bundles: Vec<Bundle> = self.bundles.iter().map(Bundle::from).collect();
+        let skipped = registries.import_packed(
+            &self.registry.prompts,
+            &bundles,
+            self.registry.version.as_deref(),
+            fetched_at,
+        )?;
+
+        let mut merged = registries.load(LoadMode::Cached);
+        merged.result.source = RegistrySource::Packed;
+        merged.result.stale = is_older_than(Some(fetched_at), cache_ttl);
+        merged.fetched_at = Some(fetched_at.clone());
+
+        Ok((merged, skipped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::registry::{apply_load_result, LocalPrompts, RegistryLoader, META_LAST_SYNC};
+    use crate::storage::{Database, META_REGISTRY_VERSION};
+
+    fn sample_pack(fetched_at: &str) -> Result<RegistryPack> {
+        let mut local = Prompt::new("mine", "Mine", "local");
+        local.is_local = true;
+        let bundles = vec![PackBundle {
+            id: "starter".to_string(),
+            title: "Starter".to_string(),
+            description: None,
+            prompts: vec!["a".to_string()],
+            version: Some("1.0.0".to_string()),
+            author: None,
+            featured: true,
+        }];
+        RegistryPack::new(
+            vec![Prompt::new("a", "A", "alpha"), Prompt::new("b", "B", "beta"), local],
+            bundles,
+            Some("2.1.0".to_string()),
+            fetched_at,
+            RegistrySource::Remote,
+        )
+    }
+
+    #[test]
+    fn pack_round_trips_and_detects_tampering() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("registry.jfppack");
+        let pack = sample_pack("2026-01-01T00:00:00+00:00")?;
+        assert_eq!(pack.metadata.prompt_count, 2);
+        pack.write(&path)?;
+
+        let read = RegistryPack::read(&path)?;
+        read.verify()?;
+        assert_eq!(read.registry.version.as_deref(), Some("2.1.0"));
+        assert_eq!(read.manifest.version.as_deref(), Some("2.1.0"));
+
+        let mut tampered = read;
+        tampered.registry.prompts[0].content = "evil".to_string();
+        let err = tampered.verify().unwrap_err();
+        assert!(err.to_string().contains("integrity check failed"), "{}", err);
+
+        let mut bundles_tampered = RegistryPack::read(&path)?;
+        bundles_tampered.bundles.clear();
+        assert!(bundles_tampered.verify().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn import_records_packed_source_and_original_fetch_time() -> Result<()> {
+        let dir = tempdir()?;
+        let fetched_at = "2026-01-01T00:00:00+00:00";
+        let pack = sample_pack(fetched_at)?;
+        let registries = RegistrySet::single(RegistryLoader::with_paths(
+            dir.path().join("registry.json"),
+            dir.path().join("registry.meta.json"),
+        ));
+
+        let (merged, skipped) = pack.import(&registries, 3600)?;
+        assert!(skipped.is_empty());
+
+        let mut db = Database::in_memory()?;
+        let (status, _) = apply_load_result(&mut db, merged, LocalPrompts::default())?;
+
+        assert_eq!(status.source, RegistrySource::Packed);
+        assert!(status.stale);
+        assert_eq!(db.get_meta(META_LAST_SYNC)?, fetched_at);
+        assert_eq!(db.get_meta(META_REGISTRY_VERSION)?, "2.1.0");
+        assert!(db.get_prompt("b")?.is_some());
+        assert!(db.get_prompt("mine")?.is_none());
+        let starter = db.bundle("starter")?.expect("packed bundle stored");
+        assert!(starter.featured);
+        assert_eq!(starter.prompts, vec!["a".to_string()]);
+        // The loader cache now holds the packed data with its original age
+        assert!(registries.load(LoadMode::Cached).result.stale);
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+//! Prompt source layer
+//!
+//! Read commands open the database through here instead of seeding it by
+//! hand. On first use, and whenever the last sync attempt is older than
+//! `registry.cacheTtl`, prompts are pulled through `RegistryLoader` into
+//! SQLite. Where the data came from is recorded in `registry_meta` so every
+//! command can report the source and staleness without touching the network.
+//!
+//! Every enabled registry (see `named`) is loaded and merged, with prompts
+//! tagged by registry name in the `source` column.
+//!
+//! When the stored prompts are due for a refresh, reads are answered from
+//! the database immediately and a detached `jfp refresh --background` child
+//! revalidates them (stale-while-revalidate). Only an empty database, or
+//! one with `autoRefresh` off, is synced in the foreground, and then under
+//! the refresh lock (see `lock`). Nothing is written without that lock: if
+//! another process keeps it, the stored prompts are served unchanged.
+//!
+//! Local prompts are read on every open and merged over the registry data
+//! (local wins by id). A fingerprint of the merged local set is stored so
+//! edits are picked up without waiting for the TTL.
+
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::{
+    load_configured_local_prompts, lock_refresh, merge_local_prompts, read_refresh_lock,
+    record_lock_attempt, refresh_lock_path, try_lock_refresh, LoadMode, LocalPrompts,
+    LockAttempt, MergedLoad, RegistrySet, REFRESH_LOCK_WAIT,
+};
+use crate::config;
+use crate::storage::{ChangeSet, Database, META_REGISTRY_VERSION};
+use crate::types::{RegistryConfig, RegistrySource};
+
+/// Meta key: when the database last received fresh registry data
+pub const META_LAST_SYNC: &str = "last_sync";
+
+/// Meta key: when a sync was last attempted (successful or not)
+pub const META_LAST_SYNC_ATTEMPT: &str = "last_sync_attempt";
+
+/// Meta key: `RegistrySource` of the prompts currently stored
+pub const META_REGISTRY_SOURCE: &str = "registry_source";
+
+/// Meta key: whether the stored prompts came from a stale fallback
+pub const META_REGISTRY_STALE: &str = "registry_stale";
+
+/// Meta key: why the last sync fell back to cached or bundled data
+pub const META_LAST_SYNC_ERROR: &str = "last_sync_error";
+
+/// Meta key: fingerprint of the local prompts last merged into the database
+pub const META_LOCAL_FINGERPRINT: &str = "local_fingerprint";
+
+/// Meta key: JSON array of the load warnings for the stored registry data
+pub const META_REGISTRY_WARNINGS: &str = "registry_warnings";
+
+/// Provenance of the prompts currently in the database
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncStatus {
+    pub source: RegistrySource,
+    pub stale: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_sync: Option<String>,
+    /// Why the last sync could not use the remote registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Load warnings for the stored registry data, then problems found
+    /// while loading local prompts
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl SyncStatus {
+    /// True when the stored prompts are not a fresh copy of the registry
+    pub fn offline(&self) -> bool {
+        self.stale || self.source == RegistrySource::Bundled
+    }
+
+    /// Short notice for human output when the data may be out of date
+    pub fn notice(&self) -> Option<String> {
+        if self.source == RegistrySource::Bundled {
+            return Some(
+                "Using bundled prompts (registry unavailable). Run 'jfp refresh' to retry."
+                    .to_string(),
+            );
+        }
+
+        if self.stale {
+            let since = self
+                .last_sync
+                .as_deref()
+                .map(|ts| format!(" (last synced {})", ts))
+                .unwrap_or_default();
+            return Some(format!(
+                "Using stale cached registry{}. Run 'jfp refresh' to update.",
+                since
+            ));
+        }
+
+        None
+    }
+
+    /// Print warnings and the staleness notice to stderr
+    ///
+    /// Warnings are printed in every mode since stdout carries the JSON;
+    /// the notice is human-mode only.
+    pub fn report(&self, use_json: bool) {
+        for warning in &self.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+
+        if !use_json && let Some(notice) = self.notice() {
+            eprintln!("Note: {}", notice);
+        }
+    }
+}
+
+/// Open the default database, syncing it from the configured registries
+/// when it is empty or due for a refresh
+///
+/// Nothing is written to the database without the refresh lock. If another
+/// process keeps it, the stored prompts are reported as they are.
+pub fn open_database() -> Result<(Database, SyncStatus)> {
+    let config = config::load_config();
+    let mut db = Database::open()?;
+    let registries = RegistrySet::from_config(&config);
+    let mut local = load_configured_local_prompts(&config.local_prompts);
+    local.warnings.splice(0..0, registries.warnings.iter().cloned());
+
+    let has_data = db.prompt_count()? > 0;
+    let due = sync_due(&db, config.registry.cache_ttl);
+    if has_data && (!due || config.registry.auto_refresh) {
+        // Merge local edits before a background child can take the lock
+        let mut status = if local_prompts_changed(&db, &local) {
+            let attempt = try_lock_refresh(&refresh_lock_path())?;
+            record_lock_attempt(&db, &attempt)?;
+            match attempt.lock {
+                Some(_lock) => merge_local_changes(&mut db, &registries, local)?,
+                None => stored_status(&db, &attempt, local),
+            }
+        } else {
+            merge_local_changes(&mut db, &registries, local)?
+        };
+
+        // Stale-while-revalidate: answer from the stored prompts now and
+        // let a detached child fetch the update
+        if due && let Err(e) = revalidate_in_background(&db) {
+            status
+                .warnings
+                .push(format!("Could not start background refresh: {}", e));
+        }
+        return Ok((db, status));
+    }
+
+    // Nothing usable stored yet: sync in the foreground, one refresh at a time
+    let attempt = lock_refresh(&refresh_lock_path(), REFRESH_LOCK_WAIT)?;
+    record_lock_attempt(&db, &attempt)?;
+    let status = match attempt.lock {
+        Some(_lock) => sync_database(&mut db, &registries, &config.registry, local)?,
+        None => stored_status(&db, &attempt, local),
+    };
+    Ok((db, status))
+}
+
+/// Sync `db` from `registries` if it is empty or its last attempt is older
+/// than the configured TTL; otherwise report the stored status
+///
+/// If only the local prompts changed, the registry data already on disk is
+/// re-merged without counting as a sync attempt. The caller holds the
+/// refresh lock.
+pub fn sync_database(
+    db: &mut Database,
+    registries: &RegistrySet,
+    registry: &RegistryConfig,
+    local: LocalPrompts,
+) -> Result<SyncStatus> {
+    if db.prompt_count()? > 0 && !sync_due(db, registry.cache_ttl) {
+        return merge_local_changes(db, registries, local);
+    }
+
+    let mode = if registry.auto_refresh {
+        LoadMode::Sync
+    } else {
+        LoadMode::Cached
+    };
+
+    apply_load_result(db, registries.load(mode), local).map(|(status, _)| status)
+}
+
+/// Whether the local prompts differ from the set last merged into `db`
+fn local_prompts_changed(db: &Database, local: &LocalPrompts) -> bool {
+    db.get_meta(META_LOCAL_FINGERPRINT).ok().as_deref() != Some(local.fingerprint().as_str())
+}
+
+/// Re-merge the cached registry data if the local prompts changed, and
+/// report the stored status
+///
+/// The caller holds the refresh lock.
+fn merge_local_changes(
+    db: &mut Database,
+    registries: &RegistrySet,
+    local: LocalPrompts,
+) -> Result<SyncStatus> {
+    if local_prompts_changed(db, &local) {
+        let merged = registries.load(LoadMode::Cached);
+        write_prompts(db, &merged, &local)?;
+    }
+
+    let mut status = read_status(db);
+    status.warnings.extend(local.warnings);
+    Ok(status)
+}
+
+/// Report the stored status, unchanged, while another process holds the
+/// refresh lock
+fn stored_status(db: &Database, attempt: &LockAttempt, local: LocalPrompts) -> SyncStatus {
+    let mut status = read_status(db);
+    status.warnings.extend(local.warnings);
+    status.warnings.push(match &attempt.holder {
+        Some(holder) => format!(
+            "Another refresh is running (pid {}); showing the stored prompts",
+            holder.pid
+        ),
+        None => "Another refresh is running; showing the stored prompts".to_string(),
+    });
+    status
+}
+
+/// Spawn a detached `jfp refresh --background` unless a refresh is running
+///
+/// The spawn counts as the sync attempt, so commands run before the child
+/// finishes do not spawn more of them.
+fn revalidate_in_background(db: &Database) -> Result<()> {
+    if read_refresh_lock(&refresh_lock_path()).is_some_and(|holder| !holder.is_stale()) {
+        return Ok(());
+    }
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(["refresh", "--background"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // Own process group, so Ctrl-C in the shell does not take the child down
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    command.spawn()?;
+    db.set_meta(META_LAST_SYNC_ATTEMPT, &Utc::now().to_rfc3339())
+}
+
+/// Write a loader result merged with local prompts into the database and
+/// record its provenance
+///
+/// Returns the new status along with how many prompts were added, updated
+/// and removed.
+pub fn apply_load_result(
+    db: &mut Database,
+    merged: MergedLoad,
+    local: LocalPrompts,
+) -> Result<(SyncStatus, ChangeSet)> {
+    let changes = write_prompts(db, &merged, &local)?;
+    let result = merged.result;
+
+    let now = Utc::now().to_rfc3339();
+    db.set_meta(META_LAST_SYNC_ATTEMPT, &now)?;
+    db.set_meta(META_REGISTRY_SOURCE, result.source.as_str())?;
+    db.set_meta(META_REGISTRY_STALE, if result.stale { "true" } else { "false" })?;
+
+    match &merged.fetched_at {
+        Some(fetched_at) => db.set_meta(META_LAST_SYNC, fetched_at)?,
+        None if !result.stale && result.source != RegistrySource::Bundled => {
+            db.set_meta(META_LAST_SYNC, &now)?
+        }
+        None => {}
+    }
+
+    match &result.fallback_reason {
+        Some(reason) => db.set_meta(META_LAST_SYNC_ERROR, reason)?,
+        None => db.delete_meta(META_LAST_SYNC_ERROR)?,
+    }
+
+    if result.warnings.is_empty() {
+        db.delete_meta(META_REGISTRY_WARNINGS)?;
+    } else {
+        db.set_meta(META_REGISTRY_WARNINGS, &serde_json::to_string(&result.warnings)?)?;
+    }
+
+    let mut status = read_status(db);
+    status.warnings.extend(local.warnings);
+    Ok((status, changes))
+}
+
+/// Compute what `apply_load_result` would change, without writing
+pub fn preview_load_result(
+    db: &Database,
+    merged: &MergedLoad,
+    local: &LocalPrompts,
+) -> Result<ChangeSet> {
+    let prompts = merge_local_prompts(merged.result.registry.prompts.clone(), &local.prompts);
+    db.diff_prompts(&prompts, &merged.preserve_sources)
+}
+
+/// Reconcile the database against registry prompts with local prompts
+/// merged over them, logging the changes for `jfp whatsnew`
+///
+/// Local prompts that no longer exist on disk are always dropped, as are
+/// registry prompts missing from a registry that loaded a full snapshot.
+/// The initial seed of an empty database is not logged as "new". The
+/// registry's bundles replace the stored ones unless this is the bundled
+/// fallback.
+fn write_prompts(
+    db: &mut Database,
+    merged: &MergedLoad,
+    local: &LocalPrompts,
+) -> Result<ChangeSet> {
+    let initial_seed = db.prompt_count()? == 0;
+    let prompts = merge_local_prompts(merged.result.registry.prompts.clone(), &local.prompts);
+    // New revisions are tagged with the registry version they came from
+    db.set_meta(META_REGISTRY_VERSION, &merged.result.registry.meta.version)?;
+    let changes = db.reconcile_prompts(&prompts, &merged.preserve_sources)?;
+    db.set_meta(META_LOCAL_FINGERPRINT, &local.fingerprint())?;
+    // The bundled fallback carries no bundles; keep the last fetched ones
+    if merged.result.source != RegistrySource::Bundled {
+        db.replace_bundles(&merged.result.registry.bundles)?;
+    }
+
+    if !initial_seed && !changes.changes.is_empty() {
+        db.record_changes(&changes.changes, &Utc::now().to_rfc3339())?;
+    }
+
+    Ok(changes)
+}
+
+/// Read the recorded provenance without syncing
+pub fn read_status(db: &Database) -> SyncStatus {
+    let source = db
+        .get_meta(META_REGISTRY_SOURCE)
+        .ok()
+        .and_then(|value| RegistrySource::parse(&value))
+        .unwrap_or(RegistrySource::Bundled);
+    let stale = db
+        .get_meta(META_REGISTRY_STALE)
+        .map(|value| value == "true")
+        .unwrap_or(false);
+    let warnings = db
+        .get_meta(META_REGISTRY_WARNINGS)
+        .ok()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default();
+
+    SyncStatus {
+        source,
+        stale,
+        last_sync: db.get_meta(META_LAST_SYNC).ok(),
+        last_error: db.get_meta(META_LAST_SYNC_ERROR).ok(),
+        warnings,
+    }
+}
+
+/// Whether the last sync attempt is older than `ttl_secs`
+fn sync_due(db: &Database, ttl_secs: u64) -> bool {
+    let last_attempt = db
+        .get_meta(META_LAST_SYNC_ATTEMPT)
+        .or_else(|_| db.get_meta(META_LAST_SYNC))
+        .ok();
+
+    is_older_than(last_attempt.as_deref(), ttl_secs)
+}
+
+/// Whether an RFC 3339 timestamp is missing, unparseable, or older than `ttl_secs`
+pub fn is_older_than(timestamp: Option<&str>, ttl_secs: u64) -> bool {
+    let Some(parsed) = timestamp.and_then(|ts| DateTime::parse_from_rfc3339(ts).ok()) else {
+        return true;
+    };
+
+    let elapsed = Utc::now().signed_duration_since(parsed);
+    elapsed.num_seconds() > ttl_secs as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::registry::RegistryLoader;
+    use crate::types::Prompt;
+
+    fn write_fresh_cache(dir: &std::path::Path, prompts: &[Prompt]) -> Result<RegistrySet> {
+        let cache = dir.join("registry.json");
+        let meta = dir.join("registry.meta.json");
+        fs::write(&cache, serde_json::to_string(prompts)?)?;
+        fs::write(
+            &meta,
+            serde_json::json!({
+                "etag": null,
+                "fetched_at": Utc::now().to_rfc3339(),
+                "prompt_count": prompts.len(),
+            })
+            .to_string(),
+        )?;
+        Ok(RegistrySet::single(RegistryLoader::with_paths(cache, meta)))
+    }
+
+    #[test]
+    fn empty_database_is_seeded_from_loader_cache() -> Result<()> {
+        let dir = tempdir()?;
+        let loader = write_fresh_cache(dir.path(), &[Prompt::new("cached-1", "Cached", "body")])?;
+        let mut db = Database::in_memory()?;
+
+        let status = sync_database(&mut db, &loader, &RegistryConfig::default(), LocalPrompts::default())?;
+
+        assert_eq!(status.source, RegistrySource::Cache);
+        assert!(!status.stale);
+        assert!(!status.offline());
+        assert!(db.get_prompt("cached-1")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn recent_sync_is_not_repeated() -> Result<()> {
+        let dir = tempdir()?;
+        let loader = write_fresh_cache(dir.path(), &[Prompt::new("cached-1", "Cached", "body")])?;
+        let mut db = Database::in_memory()?;
+        sync_database(&mut db, &loader, &RegistryConfig::default(), LocalPrompts::default())?;
+
+        // A newer cache should not be picked up until the TTL elapses
+        let loader = write_fresh_cache(dir.path(), &[Prompt::new("cached-2", "Newer", "body")])?;
+        let status = sync_database(&mut db, &loader, &RegistryConfig::default(), LocalPrompts::default())?;
+
+        assert_eq!(status.source, RegistrySource::Cache);
+        assert!(db.get_prompt("cached-2")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_cache_without_auto_refresh_falls_back_to_bundled() -> Result<()> {
+        let dir = tempdir()?;
+        let loader = RegistrySet::single(RegistryLoader::with_paths(
+            dir.path().join("registry.json"),
+            dir.path().join("registry.meta.json"),
+        ));
+        let registry = RegistryConfig {
+            auto_refresh: false,
+            ..RegistryConfig::default()
+        };
+        let mut db = Database::in_memory()?;
+
+        let status = sync_database(&mut db, &loader, &registry, LocalPrompts::default())?;
+
+        assert_eq!(status.source, RegistrySource::Bundled);
+        assert!(status.offline());
+        assert!(status.notice().is_some());
+        assert!(db.prompt_count()? > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn local_prompts_override_registry_and_follow_file_changes() -> Result<()> {
+        let dir = tempdir()?;
+        let loader = write_fresh_cache(dir.path(), &[Prompt::new("shared", "Registry", "body")])?;
+        let mut db = Database::in_memory()?;
+
+        let mut shared = Prompt::new("shared", "Local", "ours");
+        shared.is_local = true;
+        let mut extra = Prompt::new("team-only", "Team", "ours");
+        extra.is_local = true;
+        let local = LocalPrompts {
+            prompts: vec![shared, extra],
+            warnings: vec!["Invalid local prompt 'x' in bad.json: oops".to_string()],
+        };
+
+        let status = sync_database(&mut db, &loader, &RegistryConfig::default(), local)?;
+        assert_eq!(status.warnings.len(), 1);
+        let stored = db.get_prompt("shared")?.expect("shared prompt stored");
+        assert_eq!(stored.title, "Local");
+        assert!(stored.is_local);
+
+        // Local files removed: registry version returns, local-only prompt goes
+        sync_database(&mut db, &loader, &RegistryConfig::default(), LocalPrompts::default())?;
+        let stored = db.get_prompt("shared")?.expect("shared prompt stored");
+        assert_eq!(stored.title, "Registry");
+        assert!(!stored.is_local);
+        assert!(db.get_prompt("team-only")?.is_none());
+        assert!(db.search("team", 10, None, false)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn registry_warnings_are_stored_with_the_data() -> Result<()> {
+        let dir = tempdir()?;
+        let cache = dir.path().join("registry.json");
+        let meta = dir.path().join("registry.meta.json");
+        fs::write(&cache, serde_json::to_string(&[Prompt::new("a", "A", "body")])?)?;
+        fs::write(
+            &meta,
+            serde_json::json!({
+                "etag": null,
+                "fetched_at": Utc::now().to_rfc3339(),
+                "prompt_count": 1,
+                "warnings": ["Skipping registry file README.md: no '---' frontmatter"],
+            })
+            .to_string(),
+        )?;
+        let registries = RegistrySet::single(RegistryLoader::with_paths(cache, meta));
+        let mut db = Database::in_memory()?;
+        let local = LocalPrompts {
+            prompts: Vec::new(),
+            warnings: vec!["Invalid local prompt 'x' in bad.json: oops".to_string()],
+        };
+
+        let status = sync_database(&mut db, &registries, &RegistryConfig::default(), local)?;
+        assert_eq!(status.warnings.len(), 2);
+        assert!(status.warnings[0].contains("README.md"), "{:?}", status.warnings);
+
+        // `jfp status` reads them back without loading anything
+        assert_eq!(read_status(&db).warnings, status.warnings[..1]);
+        Ok(())
+    }
+
+    #[test]
+    fn is_older_than_handles_missing_and_old_timestamps() {
+        assert!(is_older_than(None, 60));
+        assert!(is_older_than(Some("not a date"), 60));
+
+        let old = (Utc::now() - chrono::Duration::hours(2)).to_rfc3339();
+        assert!(is_older_than(Some(&old), 3600));
+
+        let recent = Utc::now().to_rfc3339();
+        assert!(!is_older_than(Some(&recent), 3600));
+    }
+}
@@ -5,14 +5,263 @@
 //! - Busy timeout for lock handling
 //! - Transactions for multi-step writes
 
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use anyhow::Result;
-use rusqlite::{params, Connection, OptionalExtension};
+use anyhow::{bail, Context, Result};
+use rusqlite::types::Value;
+use rusqlite::{
+    params, params_from_iter, Connection, OpenFlags, OptionalExtension, Transaction,
+    TransactionBehavior,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::embedding::{
+    cosine_similarity, hash_embed, vector_from_blob, vector_to_blob, EMBEDDING_MODEL,
+};
+use super::fuzzy::{fuzzy_score, index_words};
+use super::highlight::{SearchHighlight, MATCH_END, MATCH_START};
+use super::query::{FilterField, SearchQuery};
+use super::schema::{Migration, MigrationStep, DROP_REGISTRY_TABLES, MIGRATIONS, SCHEMA_VERSION};
+use crate::types::{Bundle, Prompt, PromptVariable, SearchField, SearchMatch, VariableType};
+
+/// `source` of prompts from the primary `[registry]`
+pub const DEFAULT_SOURCE: &str = "default";
+
+/// `source` of prompts loaded from the local prompts directory
+pub const LOCAL_SOURCE: &str = "local";
+
+/// Meta key: version of the registry payload last written, recorded with
+/// each new prompt revision
+pub const META_REGISTRY_VERSION: &str = "registry_version";
+
+/// How long `prompt_changes` entries are kept
+const CHANGE_LOG_RETENTION_DAYS: i64 = 90;
+
+/// How long `prompt_usage` events are kept
+const USAGE_RETENTION_DAYS: i64 = 365;
+
+/// How much a search result's score can grow from frecency (at most +50%)
+const FRECENCY_WEIGHT: f64 = 0.5;
+
+/// Weight of a hit on synonyms added by `SearchQuery::expand`, relative to
+/// a hit on the query as typed
+const SYNONYM_WEIGHT: f64 = 0.5;
+
+/// Share of a semantic search score from embedding similarity; the rest
+/// comes from BM25 (as in the TypeScript `semanticRerank`)
+const SEMANTIC_WEIGHT: f64 = 0.6;
+
+/// Lowest similarity at which a prompt without a keyword hit is returned
+/// by `semantic_search`
+const SEMANTIC_MIN_SIMILARITY: f64 = 0.2;
+
+/// Tokens in a search result snippet
+const SNIPPET_TOKENS: i32 = 16;
+
+/// Frecency points per use by age in days: recent uses count more
+const FRECENCY_BUCKETS: &[(i64, f64)] = &[(4, 100.0), (14, 70.0), (31, 50.0), (90, 30.0)];
+
+/// Frecency points for a use older than every bucket
+const FRECENCY_FLOOR: f64 = 10.0;
+
+/// How a prompt differs between the database and an incoming registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    ContentChanged,
+    MetadataChanged,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::ContentChanged => "content_changed",
+            ChangeKind::MetadataChanged => "metadata_changed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "added" => Some(ChangeKind::Added),
+            "removed" => Some(ChangeKind::Removed),
+            "content_changed" => Some(ChangeKind::ContentChanged),
+            "metadata_changed" => Some(ChangeKind::MetadataChanged),
+            _ => None,
+        }
+    }
+}
+
+/// A single prompt-level difference
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PromptChange {
+    pub id: String,
+    pub title: String,
+    pub kind: ChangeKind,
+}
+
+/// Outcome of `Database::diff_prompts` / `Database::reconcile_prompts`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChangeSet {
+    pub changes: Vec<PromptChange>,
+    pub unchanged: usize,
+}
+
+impl ChangeSet {
+    pub fn added(&self) -> usize {
+        self.count(|kind| kind == ChangeKind::Added)
+    }
+
+    /// Content or metadata changes
+    pub fn updated(&self) -> usize {
+        self.count(|kind| {
+            matches!(kind, ChangeKind::ContentChanged | ChangeKind::MetadataChanged)
+        })
+    }
+
+    pub fn removed(&self) -> usize {
+        self.count(|kind| kind == ChangeKind::Removed)
+    }
+
+    fn count(&self, matches: impl Fn(ChangeKind) -> bool) -> usize {
+        self.changes.iter().filter(|c| matches(c.kind)).count()
+    }
+}
+
+/// What a prompt was used for (a `prompt_usage` event)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageAction {
+    Copy,
+    Render,
+    Export,
+    Show,
+}
 
-use super::schema::{CREATE_SCHEMA, SCHEMA_VERSION};
-use crate::types::{Prompt, PromptVariable, VariableType};
+impl UsageAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageAction::Copy => "copy",
+            UsageAction::Render => "render",
+            UsageAction::Export => "export",
+            UsageAction::Show => "show",
+        }
+    }
+}
+
+/// Use counts for one prompt over a window
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageStat {
+    pub id: String,
+    /// `None` once the prompt is no longer stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub uses: usize,
+    pub last_used: String,
+}
+
+/// A starred prompt (`prompt_stars`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Star {
+    pub id: String,
+    pub starred_at: String,
+}
+
+/// A local collection (`collections`) and its prompt ids in order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Collection {
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub prompt_ids: Vec<String>,
+}
+
+/// Differences between `prompts` and its hand-maintained `prompts_fts` index
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FtsDrift {
+    /// Prompts with no index row
+    pub missing: Vec<String>,
+    /// Index rows for prompts that no longer exist
+    pub orphaned: Vec<String>,
+    /// Index rows whose text no longer matches the prompt
+    pub stale: Vec<String>,
+    /// Extra index rows for the same prompt
+    pub duplicates: usize,
+}
+
+impl FtsDrift {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty()
+            && self.orphaned.is_empty()
+            && self.stale.is_empty()
+            && self.duplicates == 0
+    }
+
+    /// One-line description, e.g. `2 missing, 1 stale`
+    pub fn summary(&self) -> String {
+        [
+            (self.missing.len(), "missing"),
+            (self.orphaned.len(), "orphaned"),
+            (self.stale.len(), "stale"),
+            (self.duplicates, "duplicate"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, kind)| format!("{} {}", count, kind))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// A personal note on a prompt (`prompt_notes`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    pub id: i64,
+    pub prompt_id: String,
+    pub body: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A `prompt_changes` log entry
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedChange {
+    pub id: String,
+    pub title: String,
+    pub kind: ChangeKind,
+    pub changed_at: String,
+}
+
+/// A `prompt_revisions` entry: one distinct content/metadata version
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptRevision {
+    pub prompt_id: String,
+    pub revision: i64,
+    pub title: String,
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub featured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_version: Option<String>,
+    pub recorded_at: String,
+}
 
 /// Database wrapper with connection management
 pub struct Database {
@@ -26,6 +275,56 @@ pub fn db_path() -> PathBuf {
         .join("jfp.db")
 }
 
+/// Directory for automatic and pre-migration backups
+pub fn backups_dir() -> PathBuf {
+    crate::config::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("backups")
+}
+
+/// Where the pre-migration copy of `db` at schema `version` goes:
+/// `backups/jfp-v<version>-<timestamp>.db` next to it
+pub fn migration_backup_path(db: &Path, version: i32) -> PathBuf {
+    let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    db.parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("backups")
+        .join(format!("jfp-v{}-{}.db", version, timestamp))
+}
+
+/// Run one migration's steps
+fn apply_migration(conn: &Connection, migration: &Migration) -> Result<()> {
+    for step in migration.steps {
+        match step {
+            MigrationStep::Sql(sql) => conn.execute_batch(sql)?,
+            MigrationStep::AddColumn {
+                table,
+                column,
+                definition,
+            } => {
+                let table_exists: bool = conn.query_row(
+                    "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?",
+                    params![table],
+                    |row| row.get(0),
+                )?;
+                let column_exists: bool = conn.query_row(
+                    "SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?",
+                    params![table, column],
+                    |row| row.get(0),
+                )?;
+                if table_exists && !column_exists {
+                    conn.execute_batch(&format!(
+                        "ALTER TABLE {} ADD COLUMN {} {}",
+                        table, column, definition
+                    ))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 impl Database {
     /// Open or create the database at the default location
     pub fn open() -> Result<Self> {
@@ -35,6 +334,36 @@ impl Database {
 
     /// Open or create the database at a specific path
     pub fn open_at(path: &Path) -> Result<Self> {
+        let mut db = Self::connect(path)?;
+
+        // A crash or a manual edit can leave the search index without rows
+        // or with extra ones. Only ids and counts are compared here so every
+        // command stays fast; `jfp db check` also compares the indexed text.
+        // A failed check must not lock users out.
+        match db.fts_rows_out_of_step() {
+            Ok(false) => {}
+            Ok(true) => match db.repair_fts_drift() {
+                Ok(Some(drift)) => eprintln!(
+                    "Warning: search index was out of sync with prompts ({}); rebuilt it",
+                    drift.summary()
+                ),
+                Ok(None) => {}
+                Err(e) => eprintln!("Warning: could not rebuild the search index: {}", e),
+            },
+            Err(e) => eprintln!("Warning: could not check the search index: {}", e),
+        }
+
+        Ok(db)
+    }
+
+    /// Open the database at the default location without repairing the
+    /// search index, so `jfp db` can report drift instead of hiding it
+    pub fn open_for_maintenance() -> Result<Self> {
+        Self::connect(&db_path())
+    }
+
+    /// Open or create the database at `path` and bring its schema up to date
+    fn connect(path: &Path) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -54,13 +383,15 @@ impl Database {
             conn,
         };
 
-        // Initialize schema if needed
-        db.init_schema()?;
+        // Initialize or migrate the schema
+        db.init_schema(Some(path))
+            .with_context(|| format!("Failed to open database {}", path.display()))?;
 
         Ok(db)
     }
 
     /// Open an in-memory database (for testing)
+    #[cfg(test)]
     pub fn in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
         conn.pragma_update(None, "foreign_keys", "ON")?;
@@ -69,209 +400,625 @@ impl Database {
             conn,
         };
 
-        db.init_schema()?;
+        db.init_schema(None)?;
         Ok(db)
     }
 
-    /// Initialize the database schema
-    fn init_schema(&self) -> Result<()> {
-        // Check current version
-        let version: i32 = self
-            .conn
+    /// Bring the schema up to `SCHEMA_VERSION`
+    ///
+    /// Pending migrations run in a single immediate transaction, and the
+    /// version is re-read once it holds the write lock. An existing on-disk
+    /// database is first copied to `backups/` next to it. A database written
+    /// by a newer jfp is refused rather than opened with the wrong schema.
+    fn init_schema(&self, path: Option<&Path>) -> Result<()> {
+        // Most opens find the schema current without taking the write lock
+        if !self.needs_migration()? {
+            return Ok(());
+        }
+
+        // Another process may be migrating too: hold the write lock and look
+        // again, so only one of them backs up and migrates
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+        if !self.needs_migration()? {
+            return Ok(());
+        }
+        let version = self.schema_version();
+
+        if version > 0
+            && let Some(path) = path
+        {
+            self.backup_before_migration(path, version)?;
+        }
+
+        for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+            apply_migration(&tx, migration).with_context(|| {
+                format!(
+                    "Schema migration {} ({}) failed",
+                    migration.version, migration.description
+                )
+            })?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO registry_meta (key, value) VALUES ('schema_version', ?)",
+            params![SCHEMA_VERSION.to_string()],
+        )?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Whether the stored schema is older than `SCHEMA_VERSION`; errors if
+    /// it is newer
+    fn needs_migration(&self) -> Result<bool> {
+        let version = self.schema_version();
+        if version > SCHEMA_VERSION {
+            bail!(
+                "database schema version {} is newer than this jfp supports ({}); upgrade jfp",
+                version,
+                SCHEMA_VERSION
+            );
+        }
+        Ok(version < SCHEMA_VERSION)
+    }
+
+    /// Stored schema version (0 for a new database)
+    pub fn schema_version(&self) -> i32 {
+        self.conn
             .query_row(
                 "SELECT value FROM registry_meta WHERE key = 'schema_version'",
                 [],
                 |row| row.get::<_, String>(0).map(|s| s.parse().unwrap_or(0)),
             )
-            .unwrap_or(0);
+            .unwrap_or(0)
+    }
 
-        if version < SCHEMA_VERSION {
-            self.conn.execute_batch(CREATE_SCHEMA)?;
-            self.conn.execute(
-                "INSERT OR REPLACE INTO registry_meta (key, value) VALUES ('schema_version', ?)",
-                params![SCHEMA_VERSION.to_string()],
-            )?;
+    /// Copy the database aside before migrating it from `version`
+    ///
+    /// Called with the write lock held; VACUUM cannot run inside that
+    /// transaction, so the copy is taken through a read-only connection.
+    fn backup_before_migration(&self, path: &Path, version: i32) -> Result<PathBuf> {
+        let backup = migration_backup_path(path, version);
+        if let Some(parent) = backup.parent() {
+            std::fs::create_dir_all(parent)?;
         }
 
-        Ok(())
+        // VACUUM INTO takes a consistent copy, including WAL contents
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?
+            .execute("VACUUM INTO ?", params![backup.to_string_lossy()])
+            .with_context(|| format!("Failed to back up database to {}", backup.display()))?;
+        Ok(backup)
     }
 
     /// Insert or update a prompt
+    #[cfg(test)]
     pub fn upsert_prompt(&self, prompt: &Prompt) -> Result<()> {
-        let tags_text = prompt.tags.join(" ");
+        write_prompt(&self.conn, prompt)
+    }
 
-        self.conn.execute(
+    /// Bulk insert prompts (in a transaction)
+    #[cfg(test)]
+    pub fn bulk_upsert_prompts(&mut self, prompts: &[Prompt]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+
+        for prompt in prompts {
+            write_prompt(&tx, prompt)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Compare `prompts` with the stored prompts without writing anything
+    ///
+    /// Uses the same rules as `reconcile_prompts`, so a dry run reports
+    /// exactly what a real refresh would change.
+    pub fn diff_prompts(&self, prompts: &[Prompt], preserve_sources: &[String]) -> Result<ChangeSet> {
+        diff_stored_prompts(&self.conn, prompts, preserve_sources)
+    }
+
+    /// Make the stored prompts match `prompts` in one transaction
+    ///
+    /// New and changed prompts are written. Stored prompts not in the set
+    /// are deleted unless their `source` is in `preserve_sources` (registries
+    /// for which `prompts` is not a complete snapshot, e.g. a bundled
+    /// fallback or a registry that could not be loaded at all).
+    pub fn reconcile_prompts(
+        &mut self,
+        prompts: &[Prompt],
+        preserve_sources: &[String],
+    ) -> Result<ChangeSet> {
+        let tx = self.conn.transaction()?;
+        let change_set = reconcile_stored_prompts(&tx, prompts, preserve_sources)?;
+        tx.commit()?;
+        Ok(change_set)
+    }
+
+    /// Restore a backup in one transaction: reconcile the prompts as
+    /// `reconcile_prompts` does, then restore the stars and notes when the
+    /// backup has them, replacing the current ones or adding to them
+    ///
+    /// If any step fails nothing is written.
+    pub fn restore_backup(
+        &mut self,
+        prompts: &[Prompt],
+        preserve_sources: &[String],
+        stars: Option<&[Star]>,
+        notes: Option<&[Note]>,
+        replace: bool,
+    ) -> Result<ChangeSet> {
+        let tx = self.conn.transaction()?;
+        let change_set = reconcile_stored_prompts(&tx, prompts, preserve_sources)?;
+        if let Some(stars) = stars {
+            restore_stars(&tx, stars, replace)?;
+        }
+        if let Some(notes) = notes {
+            restore_notes(&tx, notes, replace)?;
+        }
+        tx.commit()?;
+        Ok(change_set)
+    }
+
+    /// Append a change set to the `prompt_changes` log
+    ///
+    /// Entries older than `CHANGE_LOG_RETENTION_DAYS` are dropped at the same
+    /// time so the log cannot grow without bound.
+    pub fn record_changes(&mut self, changes: &[PromptChange], changed_at: &str) -> Result<()> {
+        let tx = self.conn.transaction()?;
+
+        for change in changes {
+            tx.execute(
+                "INSERT INTO prompt_changes (prompt_id, title, kind, changed_at) VALUES (?, ?, ?, ?)",
+                params![&change.id, &change.title, change.kind.as_str(), changed_at],
+            )?;
+        }
+
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(CHANGE_LOG_RETENTION_DAYS))
+            .to_rfc3339();
+        tx.execute(
+            "DELETE FROM prompt_changes WHERE changed_at < ?",
+            params![cutoff],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Logged changes newer than `since` (all when `None`), newest first
+    pub fn changes_since(&self, since: Option<&str>) -> Result<Vec<RecordedChange>> {
+        let mut stmt = self.conn.prepare(
             r#"
-            INSERT INTO prompts (id, title, content, description, category, tags_text, featured, version, author, saved_at, is_local)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            ON CONFLICT(id) DO UPDATE SET
-                title = excluded.title,
-                content = excluded.content,
-                description = excluded.description,
-                category = excluded.category,
-                tags_text = excluded.tags_text,
-                featured = excluded.featured,
-                version = excluded.version,
-                author = excluded.author,
-                saved_at = excluded.saved_at,
-                is_local = excluded.is_local,
-                updated_at = datetime('now')
+            SELECT prompt_id, title, kind, changed_at
+            FROM prompt_changes
+            WHERE ?1 IS NULL OR changed_at > ?1
+            ORDER BY changed_at DESC, id DESC
             "#,
-            params![
-                &prompt.id,
-                &prompt.title,
-                &prompt.content,
-                &prompt.description,
-                &prompt.category,
-                &tags_text,
-                prompt.featured as i32,
-                &prompt.version,
-                &prompt.author,
-                &prompt.saved_at,
-                prompt.is_local as i32,
-            ],
         )?;
 
-        // Update tags (normalized)
+        let changes = stmt
+            .query_map(params![since], |row| {
+                Ok(RecordedChange {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    kind: ChangeKind::parse(&row.get::<_, String>(2)?)
+                        .unwrap_or(ChangeKind::MetadataChanged),
+                    changed_at: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(changes)
+    }
+
+    /// Record a local usage event
+    ///
+    /// Events older than `USAGE_RETENTION_DAYS` are dropped at the same time.
+    pub fn record_usage(&self, prompt_id: &str, action: UsageAction, used_at: &str) -> Result<()> {
         self.conn.execute(
-            "DELETE FROM prompt_tags WHERE prompt_id = ?",
-            params![&prompt.id],
+            "INSERT INTO prompt_usage (prompt_id, action, used_at) VALUES (?, ?, ?)",
+            params![prompt_id, action.as_str(), used_at],
         )?;
 
-        for tag in &prompt.tags {
-            self.conn.execute(
-                "INSERT INTO prompt_tags (prompt_id, tag) VALUES (?, ?)",
-                params![&prompt.id, tag],
-            )?;
-        }
-
-        // Update variables
+        let cutoff =
+            (chrono::Utc::now() - chrono::Duration::days(USAGE_RETENTION_DAYS)).to_rfc3339();
         self.conn.execute(
-            "DELETE FROM prompt_variables WHERE prompt_id = ?",
-            params![&prompt.id],
+            "DELETE FROM prompt_usage WHERE used_at < ?",
+            params![cutoff],
         )?;
+        Ok(())
+    }
 
-        for var in &prompt.variables {
-            self.conn.execute(
-                r#"
-                INSERT INTO prompt_variables (prompt_id, name, var_type, required, description, default_value)
-                VALUES (?, ?, ?, ?, ?, ?)
-                "#,
-                params![
-                    &prompt.id,
-                    &var.name,
-                    var_type_to_str(&var.var_type),
-                    var.required as i32,
-                    &var.description,
-                    &var.default,
-                ],
-            )?;
+    /// Most-used prompts since `since` (all recorded time when `None`)
+    ///
+    /// Ordered by use count, then most recent use.
+    pub fn usage_stats(&self, since: Option<&str>) -> Result<Vec<UsageStat>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT u.prompt_id, p.title, COUNT(*) AS uses, MAX(u.used_at) AS last_used
+            FROM prompt_usage u
+            LEFT JOIN prompts p ON p.id = u.prompt_id
+            WHERE ?1 IS NULL OR u.used_at > ?1
+            GROUP BY u.prompt_id
+            ORDER BY uses DESC, last_used DESC
+            "#,
+        )?;
+
+        let stats = stmt
+            .query_map(params![since], |row| {
+                Ok(UsageStat {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    uses: row.get::<_, i64>(2)? as usize,
+                    last_used: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(stats)
+    }
+
+    /// Frecency score per used prompt as of `now`
+    ///
+    /// Each use earns points by age (`FRECENCY_BUCKETS`), so a prompt used
+    /// often and lately outranks one used often long ago.
+    pub fn frecency_scores(&self, now: chrono::DateTime<chrono::Utc>) -> Result<HashMap<String, f64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT prompt_id, used_at FROM prompt_usage")?;
+        let events = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut scores: HashMap<String, f64> = HashMap::new();
+        for (prompt_id, used_at) in events {
+            let Ok(used_at) = chrono::DateTime::parse_from_rfc3339(&used_at) else {
+                continue;
+            };
+            let age_days = (now - used_at.with_timezone(&chrono::Utc)).num_days();
+            let points = FRECENCY_BUCKETS
+                .iter()
+                .find(|(max_age, _)| age_days < *max_age)
+                .map_or(FRECENCY_FLOOR, |(_, points)| *points);
+            *scores.entry(prompt_id).or_default() += points;
         }
 
-        // Update FTS index
-        self.conn.execute(
-            "DELETE FROM prompts_fts WHERE id = ?",
-            params![&prompt.id],
+        Ok(scores)
+    }
+
+    /// Star a prompt; returns false if it was already starred
+    pub fn star_prompt(&self, id: &str, starred_at: &str) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO prompt_stars (prompt_id, starred_at) VALUES (?, ?)",
+            params![id, starred_at],
         )?;
+        Ok(inserted > 0)
+    }
 
-        self.conn.execute(
+    /// Remove a star; returns false if the prompt was not starred
+    pub fn unstar_prompt(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM prompt_stars WHERE prompt_id = ?", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    /// All stars, most recent first (including stars of removed prompts)
+    pub fn stars(&self) -> Result<Vec<Star>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT prompt_id, starred_at FROM prompt_stars ORDER BY starred_at DESC, prompt_id",
+        )?;
+        let stars = stmt
+            .query_map([], |row| {
+                Ok(Star {
+                    id: row.get(0)?,
+                    starred_at: row.get(1)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(stars)
+    }
+
+    /// Add a note to a prompt
+    pub fn add_note(&mut self, prompt_id: &str, body: &str, at: &str) -> Result<Note> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO prompt_notes (prompt_id, body, created_at, updated_at) VALUES (?, ?, ?, ?)",
+            params![prompt_id, body, at, at],
+        )?;
+        let note = Note {
+            id: tx.last_insert_rowid(),
+            prompt_id: prompt_id.to_string(),
+            body: body.to_string(),
+            created_at: at.to_string(),
+            updated_at: at.to_string(),
+        };
+        index_note(&tx, &note)?;
+        tx.commit()?;
+        Ok(note)
+    }
+
+    /// Notes on one prompt, or on every prompt, oldest first
+    pub fn notes(&self, prompt_id: Option<&str>) -> Result<Vec<Note>> {
+        let mut stmt = self.conn.prepare(
             r#"
-            INSERT INTO prompts_fts (id, title, description, content, tags_text)
-            VALUES (?, ?, ?, ?, ?)
+            SELECT id, prompt_id, body, created_at, updated_at
+            FROM prompt_notes
+            WHERE ?1 IS NULL OR prompt_id = ?1
+            ORDER BY prompt_id, created_at, id
             "#,
-            params![
-                &prompt.id,
-                &prompt.title,
-                &prompt.description,
-                &prompt.content,
-                &tags_text,
-            ],
         )?;
+        let notes = stmt
+            .query_map(params![prompt_id], |row| {
+                Ok(Note {
+                    id: row.get(0)?,
+                    prompt_id: row.get(1)?,
+                    body: row.get(2)?,
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(notes)
+    }
 
-        Ok(())
+    /// Replace a note's text; returns false if there is no such note
+    pub fn update_note(&mut self, id: i64, body: &str, at: &str) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        let updated = tx.execute(
+            "UPDATE prompt_notes SET body = ?, updated_at = ? WHERE id = ?",
+            params![body, at, id],
+        )?;
+        if updated > 0 {
+            let prompt_id: String = tx.query_row(
+                "SELECT prompt_id FROM prompt_notes WHERE id = ?",
+                params![id],
+                |row| row.get(0),
+            )?;
+            tx.execute("DELETE FROM prompt_notes_fts WHERE note_id = ?", params![id])?;
+            tx.execute(
+                "INSERT INTO prompt_notes_fts (body, note_id, prompt_id) VALUES (?, ?, ?)",
+                params![body, id, prompt_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(updated > 0)
     }
 
-    /// Bulk insert prompts (in a transaction)
-    pub fn bulk_upsert_prompts(&mut self, prompts: &[Prompt]) -> Result<()> {
+    /// Delete a note; returns false if there is no such note
+    pub fn delete_note(&mut self, id: i64) -> Result<bool> {
         let tx = self.conn.transaction()?;
+        let deleted = tx.execute("DELETE FROM prompt_notes WHERE id = ?", params![id])?;
+        tx.execute("DELETE FROM prompt_notes_fts WHERE note_id = ?", params![id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
 
-        for prompt in prompts {
+    /// Create an empty collection; returns false if the name is taken
+    pub fn create_collection(&self, name: &str, description: Option<&str>, at: &str) -> Result<bool> {
+        let created = self.conn.execute(
+            "INSERT OR IGNORE INTO collections (name, description, created_at, updated_at) VALUES (?, ?, ?, ?)",
+            params![name, description, at, at],
+        )?;
+        Ok(created > 0)
+    }
+
+    /// Every collection, by name
+    pub fn collections(&self) -> Result<Vec<Collection>> {
+        let names = self
+            .conn
+            .prepare("SELECT name FROM collections ORDER BY name")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut collections = Vec::with_capacity(names.len());
+        for name in names {
+            if let Some(collection) = self.collection(&name)? {
+                collections.push(collection);
+            }
+        }
+        Ok(collections)
+    }
+
+    /// A collection with its prompt ids in order
+    pub fn collection(&self, name: &str) -> Result<Option<Collection>> {
+        let collection = self
+            .conn
+            .query_row(
+                "SELECT name, description, created_at, updated_at FROM collections WHERE name = ?",
+                params![name],
+                |row| {
+                    Ok(Collection {
+                        name: row.get(0)?,
+                        description: row.get(1)?,
+                        created_at: row.get(2)?,
+                        updated_at: row.get(3)?,
+                        prompt_ids: Vec::new(),
+                    })
+                },
+            )
+            .optional()?;
+        let Some(mut collection) = collection else {
+            return Ok(None);
+        };
+        collection.prompt_ids = collection_order(&self.conn, name)?;
+        Ok(Some(collection))
+    }
+
+    /// Append prompts to a collection, skipping ones already in it;
+    /// returns the ids added
+    pub fn add_to_collection(&mut self, name: &str, ids: &[String], at: &str) -> Result<Vec<String>> {
+        let tx = self.conn.transaction()?;
+        let mut order = collection_order(&tx, name)?;
+        let mut added = Vec::new();
+        for id in ids {
+            if order.contains(id) {
+                continue;
+            }
             tx.execute(
-                r#"
-                INSERT INTO prompts (id, title, content, description, category, featured, version, author, saved_at, is_local)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ON CONFLICT(id) DO UPDATE SET
-                    title = excluded.title,
-                    content = excluded.content,
-                    description = excluded.description,
-                    category = excluded.category,
-                    featured = excluded.featured,
-                    version = excluded.version,
-                    author = excluded.author,
-                    saved_at = excluded.saved_at,
-                    is_local = excluded.is_local,
-                    updated_at = datetime('now')
-                "#,
-                params![
-                    &prompt.id,
-                    &prompt.title,
-                    &prompt.content,
-                    &prompt.description,
-                    &prompt.category,
-                    prompt.featured as i32,
-                    &prompt.version,
-                    &prompt.author,
-                    &prompt.saved_at,
-                    prompt.is_local as i32,
-                ],
+                "INSERT INTO collection_prompts (collection, prompt_id, position, added_at) VALUES (?, ?, ?, ?)",
+                params![name, id, order.len() as i64, at],
             )?;
+            order.push(id.clone());
+            added.push(id.clone());
+        }
+        touch_collection(&tx, name, at)?;
+        tx.commit()?;
+        Ok(added)
+    }
 
-            // Tags
+    /// Remove prompts from a collection; returns the ids removed
+    pub fn remove_from_collection(&mut self, name: &str, ids: &[String], at: &str) -> Result<Vec<String>> {
+        let tx = self.conn.transaction()?;
+        let mut order = collection_order(&tx, name)?;
+        let removed: Vec<String> = ids.iter().filter(|id| order.contains(id)).cloned().collect();
+        for id in &removed {
             tx.execute(
-                "DELETE FROM prompt_tags WHERE prompt_id = ?",
-                params![&prompt.id],
+                "DELETE FROM collection_prompts WHERE collection = ? AND prompt_id = ?",
+                params![name, id],
             )?;
-            for tag in &prompt.tags {
-                tx.execute(
-                    "INSERT INTO prompt_tags (prompt_id, tag) VALUES (?, ?)",
-                    params![&prompt.id, tag],
-                )?;
-            }
         }
+        order.retain(|id| !removed.contains(id));
+        write_collection_order(&tx, name, &order)?;
+        touch_collection(&tx, name, at)?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Move a prompt to `position` (0-based, clamped to the end); returns
+    /// false if the prompt is not in the collection
+    pub fn move_in_collection(&mut self, name: &str, id: &str, position: usize, at: &str) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        let mut order = collection_order(&tx, name)?;
+        let Some(from) = order.iter().position(|existing| existing == id) else {
+            return Ok(false);
+        };
+        let moved = order.remove(from);
+        order.insert(position.min(order.len()), moved);
+        write_collection_order(&tx, name, &order)?;
+        touch_collection(&tx, name, at)?;
+        tx.commit()?;
+        Ok(true)
+    }
 
+    /// Delete a collection (not its prompts); returns false if it did not exist
+    pub fn delete_collection(&self, name: &str) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM collections WHERE name = ?", params![name])?;
+        Ok(deleted > 0)
+    }
+
+    /// Replace the stored bundles with the registry's definitions
+    ///
+    /// Membership keeps the registry's order; ids with no stored prompt are
+    /// left out, so `prompt_count` only counts prompts that can be shown.
+    pub fn replace_bundles(&mut self, bundles: &[Bundle]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM bundles", [])?;
+        for bundle in bundles {
+            tx.execute(
+                "INSERT OR REPLACE INTO bundles (id, title, description, version, featured, author) VALUES (?, ?, ?, ?, ?, ?)",
+                params![
+                    &bundle.id,
+                    &bundle.title,
+                    &bundle.description,
+                    &bundle.version,
+                    bundle.featured as i32,
+                    &bundle.author
+                ],
+            )?;
+            let mut position = 0i64;
+            for prompt_id in &bundle.prompts {
+                position += tx.execute(
+                    r#"
+                    INSERT OR IGNORE INTO bundle_prompts (bundle_id, prompt_id, position)
+                    SELECT ?1, id, ?3 FROM prompts WHERE id = ?2
+                    "#,
+                    params![&bundle.id, prompt_id, position],
+                )? as i64;
+            }
+        }
         tx.commit()?;
         Ok(())
     }
 
+    /// Stored bundles, featured first, then by title
+    pub fn bundles(&self) -> Result<Vec<Bundle>> {
+        let ids = self
+            .conn
+            .prepare("SELECT id FROM bundles ORDER BY featured DESC, title COLLATE NOCASE, id")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut bundles = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(bundle) = self.bundle(&id)? {
+                bundles.push(bundle);
+            }
+        }
+        Ok(bundles)
+    }
+
+    /// A stored bundle with its prompt ids in order
+    pub fn bundle(&self, id: &str) -> Result<Option<Bundle>> {
+        let bundle = self
+            .conn
+            .query_row(
+                "SELECT id, title, description, version, featured, author FROM bundles WHERE id = ?",
+                params![id],
+                |row| {
+                    Ok(Bundle {
+                        description: row.get(2)?,
+                        version: row.get(3)?,
+                        featured: row.get::<_, i32>(4)? != 0,
+                        author: row.get(5)?,
+                        ..Bundle::new(row.get::<_, String>(0)?, row.get::<_, String>(1)?)
+                    })
+                },
+            )
+            .optional()?;
+        let Some(mut bundle) = bundle else {
+            return Ok(None);
+        };
+        bundle.prompts = self
+            .conn
+            .prepare("SELECT prompt_id FROM bundle_prompts WHERE bundle_id = ? ORDER BY position, prompt_id")?
+            .query_map(params![id], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        bundle.prompt_count = bundle.prompts.len();
+        Ok(Some(bundle))
+    }
+
     /// Get a prompt by ID
+    ///
+    /// `<registry>/<id>` also resolves to the prompt `<id>` stored from that
+    /// registry, so `default/code-review` works whether or not it collided.
     pub fn get_prompt(&self, id: &str) -> Result<Option<Prompt>> {
-        let prompt = self
+        let mut prompt = self
             .conn
             .query_row(
                 r#"
-                SELECT id, title, content, description, category, featured, version, author, saved_at, is_local
+                SELECT id, title, content, description, category, featured, version, author, saved_at, is_local, source
                 FROM prompts WHERE id = ?
                 "#,
                 params![id],
-                |row| {
-                    Ok(Prompt {
-                        id: row.get(0)?,
-                        title: row.get(1)?,
-                        content: row.get(2)?,
-                        description: row.get(3)?,
-                        category: row.get(4)?,
-                        tags: Vec::new(), // Filled below
-                        variables: Vec::new(), // Filled below
-                        featured: row.get::<_, i32>(5)? != 0,
-                        version: row.get(6)?,
-                        author: row.get(7)?,
-                        saved_at: row.get(8)?,
-                        is_local: row.get::<_, i32>(9)? != 0,
-                    })
-                },
+                prompt_from_row,
             )
             .optional()?;
 
+        if prompt.is_none()
+            && let Some((registry, bare_id)) = id.split_once('/')
+        {
+            prompt = self
+                .conn
+                .query_row(
+                    r#"
+                    SELECT id, title, content, description, category, featured, version, author, saved_at, is_local, source
+                    FROM prompts WHERE id = ? AND source = ?
+                    "#,
+                    params![bare_id, registry],
+                    prompt_from_row,
+                )
+                .optional()?;
+        }
+
         let Some(mut prompt) = prompt else {
             return Ok(None);
         };
@@ -285,11 +1032,26 @@ impl Database {
         Ok(Some(prompt))
     }
 
+    /// Every revision of a prompt, oldest first
+    ///
+    /// Revisions are kept after a prompt is removed, so `id` is matched
+    /// as stored (resolve `<registry>/<id>` with `get_prompt` first).
+    pub fn prompt_revisions(&self, id: &str) -> Result<Vec<PromptRevision>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM prompt_revisions WHERE prompt_id = ? ORDER BY revision",
+            REVISION_COLUMNS
+        ))?;
+        let revisions = stmt
+            .query_map(params![id], revision_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(revisions)
+    }
+
     /// Get tags for a prompt
     fn get_prompt_tags(&self, prompt_id: &str) -> Result<Vec<String>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT tag FROM prompt_tags WHERE prompt_id = ?")?;
+            .prepare("SELECT tag FROM prompt_tags WHERE prompt_id = ? ORDER BY rowid")?;
         let tags = stmt
             .query_map(params![prompt_id], |row| row.get(0))?
             .collect::<std::result::Result<Vec<String>, _>>()?;
@@ -306,15 +1068,7 @@ impl Database {
         )?;
 
         let vars = stmt
-            .query_map(params![prompt_id], |row| {
-                Ok(PromptVariable {
-                    name: row.get(0)?,
-                    var_type: str_to_var_type(&row.get::<_, String>(1)?),
-                    required: row.get::<_, i32>(2)? != 0,
-                    description: row.get(3)?,
-                    default: row.get(4)?,
-                })
-            })?
+            .query_map(params![prompt_id], variable_from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(vars)
@@ -324,28 +1078,13 @@ impl Database {
     pub fn list_prompts(&self) -> Result<Vec<Prompt>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, title, content, description, category, featured, version, author, saved_at, is_local
+            SELECT id, title, content, description, category, featured, version, author, saved_at, is_local, source
             FROM prompts ORDER BY title
             "#,
         )?;
 
         let prompts = stmt
-            .query_map([], |row| {
-                Ok(Prompt {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                    description: row.get(3)?,
-                    category: row.get(4)?,
-                    tags: Vec::new(),
-                    variables: Vec::new(),
-                    featured: row.get::<_, i32>(5)? != 0,
-                    version: row.get(6)?,
-                    author: row.get(7)?,
-                    saved_at: row.get(8)?,
-                    is_local: row.get::<_, i32>(9)? != 0,
-                })
-            })?
+            .query_map([], prompt_from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         // Load tags for each prompt
@@ -358,12 +1097,40 @@ impl Database {
         Ok(result)
     }
 
+    /// Every prompt with its tags and variables, ordered by id
+    ///
+    /// Unlike `list_prompts`, this returns complete prompts for writing
+    /// archives such as registry packs.
+    pub fn export_prompts(&self) -> Result<Vec<Prompt>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT id, title, content, description, category, featured, version, author, saved_at, is_local, source
+            FROM prompts ORDER BY id
+            "#,
+        )?;
+
+        let prompts = stmt
+            .query_map([], prompt_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut result = Vec::with_capacity(prompts.len());
+        for mut prompt in prompts {
+            prompt.tags = self.get_prompt_tags(&prompt.id)?;
+            prompt.variables = self.get_prompt_variables(&prompt.id)?;
+            result.push(prompt);
+        }
+
+        Ok(result)
+    }
+
     /// List prompts with optional filters
     pub fn list_prompts_filtered(
         &self,
         category: Option<&str>,
         tag: Option<&str>,
         featured_only: bool,
+        starred_only: bool,
+        registry: Option<&str>,
     ) -> Result<Vec<Prompt>> {
         let mut conditions = Vec::new();
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
@@ -382,6 +1149,15 @@ impl Database {
             conditions.push("featured = 1");
         }
 
+        if starred_only {
+            conditions.push("id IN (SELECT prompt_id FROM prompt_stars)");
+        }
+
+        if let Some(name) = registry {
+            conditions.push("source = ?");
+            params_vec.push(Box::new(name.to_string()));
+        }
+
         let where_clause = if conditions.is_empty() {
             String::new()
         } else {
@@ -390,7 +1166,7 @@ impl Database {
 
         let sql = format!(
             r#"
-            SELECT id, title, content, description, category, featured, version, author, saved_at, is_local
+            SELECT id, title, content, description, category, featured, version, author, saved_at, is_local, source
             FROM prompts {} ORDER BY title
             "#,
             where_clause
@@ -400,22 +1176,7 @@ impl Database {
         let params: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
         let prompts = stmt
-            .query_map(params.as_slice(), |row| {
-                Ok(Prompt {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                    description: row.get(3)?,
-                    category: row.get(4)?,
-                    tags: Vec::new(),
-                    variables: Vec::new(),
-                    featured: row.get::<_, i32>(5)? != 0,
-                    version: row.get(6)?,
-                    author: row.get(7)?,
-                    saved_at: row.get(8)?,
-                    is_local: row.get::<_, i32>(9)? != 0,
-                })
-            })?
+            .query_map(params.as_slice(), prompt_from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         // Load tags for each prompt
@@ -477,54 +1238,403 @@ impl Database {
         Ok(count as usize)
     }
 
+    /// Number of stored prompts per `source`
+    pub fn source_counts(&self) -> Result<HashMap<String, usize>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT source, COUNT(*) FROM prompts GROUP BY source")?;
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<rusqlite::Result<HashMap<String, usize>>>()?;
+        Ok(counts)
+    }
+
     /// Full-text search using FTS5
-    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(Prompt, f64)>> {
+    ///
+    /// `query` is parsed with `SearchQuery::parse` and not expanded; see
+    /// `search_query`.
+    #[cfg(test)]
+    pub fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        registry: Option<&str>,
+        frecency: bool,
+    ) -> Result<Vec<(Prompt, f64)>> {
+        self.search_query(&SearchQuery::parse(query)?, limit, registry, frecency)
+    }
+
+    /// Run a parsed search query
+    ///
+    /// Prompts whose notes match are included too; a note match adds its
+    /// BM25 score to the prompt's. An expanded query (`SearchQuery::expand`)
+    /// also matches prompts on synonyms, at `SYNONYM_WEIGHT`. Excluded terms
+    /// drop prompts whose text or notes contain them. A query of filters
+    /// only scores every match 0 and lists featured prompts first.
+    ///
+    /// With `frecency`, more candidates are fetched and each BM25 score is
+    /// raised by up to `FRECENCY_WEIGHT` in proportion to the prompt's
+    /// frecency (relative to the most-used prompt), then re-sorted.
+    pub fn search_query(
+        &self,
+        query: &SearchQuery,
+        limit: usize,
+        registry: Option<&str>,
+        frecency: bool,
+    ) -> Result<Vec<(Prompt, f64)>> {
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let scores = if frecency {
+            self.frecency_scores(chrono::Utc::now())?
+        } else {
+            HashMap::new()
+        };
+        let candidates = if scores.is_empty() { limit } else { (limit * 4).max(50) };
+
+        // ?1 match, ?2 limit, ?3 registry; exclusions and filters follow
+        let mut values: Vec<Value> = vec![
+            query.fts.clone().map_or(Value::Null, Value::Text),
+            Value::Integer(candidates as i64),
+            registry.map_or(Value::Null, |r| Value::Text(r.to_string())),
+        ];
         // BM25 weights: id=5, title=3, description=2, content=1, tags=2
-        let mut stmt = self.conn.prepare(
+        let mut hits = if query.fts.is_some() {
+            r#"
+                SELECT id, bm25(prompts_fts, 5.0, 3.0, 2.0, 1.0, 2.0) AS score
+                FROM prompts_fts WHERE prompts_fts MATCH ?1
+                UNION ALL
+                SELECT prompt_id, bm25(prompt_notes_fts) AS score
+                FROM prompt_notes_fts WHERE prompt_notes_fts MATCH ?1
+            "#
+            .to_string()
+        } else {
+            "SELECT id, 0.0 AS score FROM prompts".to_string()
+        };
+        // Synonym matches score at `SYNONYM_WEIGHT`; literal matches also
+        // match the expanded query, so they keep ranking higher
+        if let Some(expanded) = query.expanded.as_ref().filter(|_| query.fts.is_some()) {
+            values.push(Value::Text(expanded.clone()));
+            hits.push_str(&format!(
+                r#"
+                UNION ALL
+                SELECT id, {SYNONYM_WEIGHT} * bm25(prompts_fts, 5.0, 3.0, 2.0, 1.0, 2.0) AS score
+                FROM prompts_fts WHERE prompts_fts MATCH ?{}
+                "#,
+                values.len()
+            ));
+        }
+
+        let conditions = search_conditions(query, &mut values);
+
+        let sql = format!(
             r#"
+            WITH hits AS ({hits})
             SELECT p.id, p.title, p.content, p.description, p.category,
-                   p.featured, p.version, p.author, p.saved_at, p.is_local,
-                   bm25(prompts_fts, 5.0, 3.0, 2.0, 1.0, 2.0) as score
-            FROM prompts_fts f
-            JOIN prompts p ON f.id = p.id
-            WHERE prompts_fts MATCH ?
-            ORDER BY score
-            LIMIT ?
+                   p.featured, p.version, p.author, p.saved_at, p.is_local, p.source,
+                   SUM(h.score) as score
+            FROM hits h
+            JOIN prompts p ON h.id = p.id
+            WHERE {conditions}
+            GROUP BY p.id
+            ORDER BY score, p.featured DESC, p.title
+            LIMIT ?2
             "#,
-        )?;
+            conditions = conditions.join(" AND ")
+        );
 
+        let mut stmt = self.conn.prepare(&sql)?;
         let results = stmt
-            .query_map(params![query, limit as i64], |row| {
-                Ok((
-                    Prompt {
-                        id: row.get(0)?,
-                        title: row.get(1)?,
-                        content: row.get(2)?,
-                        description: row.get(3)?,
-                        category: row.get(4)?,
-                        tags: Vec::new(),
-                        variables: Vec::new(),
-                        featured: row.get::<_, i32>(5)? != 0,
-                        version: row.get(6)?,
-                        author: row.get(7)?,
-                        saved_at: row.get(8)?,
-                        is_local: row.get::<_, i32>(9)? != 0,
-                    },
-                    row.get::<_, f64>(10)?,
-                ))
+            .query_map(params_from_iter(values), |row| {
+                Ok((prompt_from_row(row)?, row.get::<_, f64>(11)?))
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        // Load tags for each result
+        let max_frecency = scores.values().copied().fold(0.0, f64::max);
         let mut final_results = Vec::with_capacity(results.len());
-        for (mut prompt, score) in results {
+        for (prompt, score) in results {
+            // Negate because BM25 returns negative scores (subtracting keeps the
+            // 0 of a filter-only query from printing as -0)
+            let score = 0.0 - score;
+            let boost = match scores.get(&prompt.id) {
+                Some(frecency) if max_frecency > 0.0 => {
+                    1.0 + FRECENCY_WEIGHT * frecency / max_frecency
+                }
+                _ => 1.0,
+            };
+            final_results.push((prompt, score * boost));
+        }
+        final_results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        final_results.truncate(limit);
+
+        // Load tags for each result
+        for (prompt, _) in &mut final_results {
             prompt.tags = self.get_prompt_tags(&prompt.id)?;
-            final_results.push((prompt, -score)); // Negate because BM25 returns negative scores
         }
 
         Ok(final_results)
     }
 
+    /// Hybrid keyword and embedding search (see `storage::embedding`)
+    ///
+    /// `text`, plus the synonyms `query` was expanded with, is embedded and
+    /// compared with every stored prompt vector;
+    /// `query` is run through `search_query` for BM25 scores, scaled so the
+    /// best is 1. A prompt scores `SEMANTIC_WEIGHT` × similarity plus the
+    /// rest × its scaled BM25 score, so a prompt worded differently from
+    /// `text` can still rank. Prompts without a keyword hit need
+    /// `SEMANTIC_MIN_SIMILARITY`. Missing or outdated vectors are computed
+    /// first.
+    pub fn semantic_search(
+        &self,
+        text: &str,
+        query: &SearchQuery,
+        limit: usize,
+        frecency: bool,
+    ) -> Result<Vec<(Prompt, f64)>> {
+        self.ensure_embeddings()?;
+
+        let keyword = self.search_query(query, (limit * 4).max(50), None, frecency)?;
+        let best_keyword = keyword.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+        let mut scores: HashMap<String, f64> = keyword
+            .iter()
+            .map(|(prompt, score)| {
+                let scaled = if best_keyword > 0.0 { score / best_keyword } else { 0.0 };
+                (prompt.id.clone(), (1.0 - SEMANTIC_WEIGHT) * scaled)
+            })
+            .collect();
+
+        let target = hash_embed(&format!("{} {}", text, query.expansions.join(" ")));
+        let mut stmt = self.conn.prepare("SELECT prompt_id, vector FROM prompt_embeddings")?;
+        let vectors = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (id, blob) in vectors {
+            let similarity = cosine_similarity(&target, &vector_from_blob(&blob)).max(0.0);
+            match scores.get_mut(&id) {
+                Some(score) => *score += SEMANTIC_WEIGHT * similarity,
+                None if similarity >= SEMANTIC_MIN_SIMILARITY => {
+                    scores.insert(id, SEMANTIC_WEIGHT * similarity);
+                }
+                None => {}
+            }
+        }
+
+        let mut ranked: Vec<(String, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+
+        let mut prompts: HashMap<String, Prompt> =
+            keyword.into_iter().map(|(prompt, _)| (prompt.id.clone(), prompt)).collect();
+        let mut results = Vec::with_capacity(ranked.len());
+        for (id, score) in ranked {
+            let prompt = match prompts.remove(&id) {
+                Some(prompt) => Some(prompt),
+                None => self.get_prompt(&id)?,
+            };
+            if let Some(prompt) = prompt {
+                results.push((prompt, score));
+            }
+        }
+        Ok(results)
+    }
+
+    /// Compute embeddings for prompts that have none from the current
+    /// model (e.g. stored before embeddings existed); returns how many
+    pub fn ensure_embeddings(&self) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT p.id, p.title, p.description, p.tags_text
+            FROM prompts p
+            LEFT JOIN prompt_embeddings e ON e.prompt_id = p.id AND e.model = ?
+            WHERE e.prompt_id IS NULL
+            "#,
+        )?;
+        let stale = stmt
+            .query_map(params![EMBEDDING_MODEL], |row| {
+                let text = embedding_text(
+                    &row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?.as_deref(),
+                    &row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                );
+                Ok((row.get::<_, String>(0)?, text))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, text) in &stale {
+            write_embedding(&tx, id, text)?;
+        }
+        tx.commit()?;
+        Ok(stale.len())
+    }
+
+    /// Snippets and matched terms for search hits, by prompt id (see
+    /// `storage::highlight`)
+    ///
+    /// The expanded query is used when there is one, so synonym hits are
+    /// marked too. Positive `tag:` and `category:` filters count as matches
+    /// in those fields. A prompt the terms don't match (a fuzzy hit) gets
+    /// filter matches only.
+    pub fn search_highlights(
+        &self,
+        query: &SearchQuery,
+        ids: &[String],
+    ) -> Result<HashMap<String, SearchHighlight>> {
+        let fts = query.expanded.as_ref().or(query.fts.as_ref());
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT snippet(prompts_fts, 3, ?3, ?4, '…', ?5),
+                   snippet(prompts_fts, 2, ?3, ?4, '…', ?5),
+                   highlight(prompts_fts, 0, ?3, ?4),
+                   highlight(prompts_fts, 1, ?3, ?4),
+                   highlight(prompts_fts, 2, ?3, ?4),
+                   highlight(prompts_fts, 3, ?3, ?4),
+                   highlight(prompts_fts, 4, ?3, ?4)
+            FROM prompts_fts WHERE prompts_fts MATCH ?1 AND id = ?2
+            "#,
+        )?;
+        let fields = [
+            SearchField::Id,
+            SearchField::Title,
+            SearchField::Description,
+            SearchField::Content,
+            SearchField::Tag,
+        ];
+
+        let mut highlights = HashMap::with_capacity(ids.len());
+        for id in ids {
+            let mut highlight = SearchHighlight::default();
+            if let Some(fts) = fts {
+                let marked = stmt
+                    .query_row(
+                        params![
+                            fts,
+                            id,
+                            MATCH_START.to_string(),
+                            MATCH_END.to_string(),
+                            SNIPPET_TOKENS
+                        ],
+                        |row| {
+                            (0..7)
+                                .map(|column| row.get::<_, Option<String>>(column))
+                                .collect::<rusqlite::Result<Vec<_>>>()
+                        },
+                    )
+                    .optional()?;
+                if let Some(marked) = marked {
+                    // Content snippets can span lines
+                    highlight.snippet = marked[..2]
+                        .iter()
+                        .flatten()
+                        .find(|s| s.contains(MATCH_START))
+                        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "));
+                    highlight.title = marked[3].clone().filter(|t| t.contains(MATCH_START));
+                    for (field, text) in fields.iter().zip(&marked[2..]) {
+                        if let Some(text) = text {
+                            highlight.add_marked(*field, text);
+                        }
+                    }
+                }
+            }
+
+            for filter in query.filters.iter().filter(|f| !f.negated) {
+                let (field, found) = match filter.field {
+                    FilterField::Tag => (SearchField::Tag, self.get_prompt_tags(id)?),
+                    FilterField::Category => (
+                        SearchField::Category,
+                        self.conn
+                            .query_row("SELECT category FROM prompts WHERE id = ?", params![id], |row| {
+                                row.get::<_, Option<String>>(0)
+                            })
+                            .optional()?
+                            .flatten()
+                            .into_iter()
+                            .collect(),
+                    ),
+                    FilterField::Featured => continue,
+                };
+                for value in &filter.values {
+                    if found.iter().any(|f| f.to_lowercase() == *value)
+                        && !highlight.matches.iter().any(|m| m.field == field && m.term == *value)
+                    {
+                        highlight.matches.push(SearchMatch {
+                            field,
+                            term: value.clone(),
+                        });
+                    }
+                }
+            }
+            highlights.insert(id.clone(), highlight);
+        }
+        Ok(highlights)
+    }
+
+    /// Typo-tolerant search over ids, titles and tags (see `storage::fuzzy`)
+    ///
+    /// Honors the query's exclusions, filters and `registry`; scores are
+    /// similarities from `FUZZY_THRESHOLD` to 1, best first.
+    pub fn fuzzy_search(
+        &self,
+        query: &SearchQuery,
+        limit: usize,
+        registry: Option<&str>,
+    ) -> Result<Vec<(Prompt, f64)>> {
+        if query.terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut values = vec![
+            Value::Null,
+            Value::Null,
+            registry.map_or(Value::Null, |r| Value::Text(r.to_string())),
+        ];
+        let conditions = search_conditions(query, &mut values);
+        let sql = format!(
+            r#"
+            SELECT p.id, p.title, p.content, p.description, p.category,
+                   p.featured, p.version, p.author, p.saved_at, p.is_local, p.source
+            FROM prompts p
+            WHERE {}
+            "#,
+            conditions.join(" AND ")
+        );
+        let candidates = self
+            .conn
+            .prepare(&sql)?
+            .query_map(params_from_iter(values), prompt_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut results = Vec::new();
+        for mut prompt in candidates {
+            prompt.tags = self.get_prompt_tags(&prompt.id)?;
+            let words: Vec<String> = std::iter::once(prompt.id.as_str())
+                .chain(std::iter::once(prompt.title.as_str()))
+                .chain(prompt.tags.iter().map(String::as_str))
+                .flat_map(index_words)
+                .collect();
+            if let Some(score) = fuzzy_score(&query.terms, &words) {
+                results.push((prompt, score));
+            }
+        }
+        results.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Ids of prompts resembling an unknown `id`, most similar first
+    pub fn similar_ids(&self, id: &str, limit: usize) -> Result<Vec<String>> {
+        let words = id.replace(|c: char| !c.is_alphanumeric(), " ");
+        let query = SearchQuery::words(&words);
+        Ok(self
+            .fuzzy_search(&query, limit, None)?
+            .into_iter()
+            .map(|(prompt, _)| prompt.id)
+            .collect())
+    }
+
     /// Run integrity check
     pub fn integrity_check(&self) -> Result<bool> {
         let result: String = self
@@ -534,12 +1644,220 @@ impl Database {
     }
 
     /// Checkpoint WAL
-    #[allow(dead_code)]
     pub fn checkpoint(&self) -> Result<()> {
         self.conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
         Ok(())
     }
 
+    /// Problems `PRAGMA integrity_check` does not see: search indexes out of
+    /// step with their tables, broken foreign keys, an old schema version
+    pub fn consistency_issues(&self) -> Result<Vec<String>> {
+        let mut issues = Vec::new();
+
+        let drift = self.fts_drift()?;
+        let drift_checks = [
+            (drift.missing.len(), "prompts missing from the search index"),
+            (drift.orphaned.len(), "search index rows without a prompt"),
+            (drift.stale.len(), "search index rows out of date"),
+            (drift.duplicates, "duplicate search index rows"),
+        ];
+        for (count, problem) in drift_checks {
+            if count > 0 {
+                issues.push(format!("{} {}", count, problem));
+            }
+        }
+
+        let checks = [
+            (
+                "SELECT COUNT(*) FROM prompt_notes WHERE id NOT IN (SELECT note_id FROM prompt_notes_fts)",
+                "notes missing from the search index",
+            ),
+            (
+                "SELECT COUNT(*) FROM prompt_notes_fts WHERE note_id NOT IN (SELECT id FROM prompt_notes)",
+                "note search index rows without a note",
+            ),
+        ];
+        for (sql, problem) in checks {
+            let count: i64 = self.conn.query_row(sql, [], |row| row.get(0))?;
+            if count > 0 {
+                issues.push(format!("{} {}", count, problem));
+            }
+        }
+
+        // FTS5's own check of its internal structures
+        for table in ["prompts_fts", "prompt_notes_fts"] {
+            let sql = format!("INSERT INTO {table}({table}) VALUES ('integrity-check')");
+            if let Err(e) = self.conn.execute(&sql, []) {
+                issues.push(format!("{}: {}", table, e));
+            }
+        }
+
+        let broken_keys = self
+            .conn
+            .prepare("PRAGMA foreign_key_check")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .count();
+        if broken_keys > 0 {
+            issues.push(format!("{} rows with broken foreign keys", broken_keys));
+        }
+
+        let version = self.schema_version();
+        if version != SCHEMA_VERSION {
+            issues.push(format!(
+                "schema version {} (expected {})",
+                version, SCHEMA_VERSION
+            ));
+        }
+
+        Ok(issues)
+    }
+
+    /// Whether `prompts_fts` lacks a prompt or has extra rows
+    ///
+    /// A cheap check by ids and counts; `fts_drift` also finds rows whose
+    /// text is out of date.
+    pub fn fts_rows_out_of_step(&self) -> Result<bool> {
+        // Equal counts with every prompt present leave no room for extras
+        let out_of_step = self.conn.query_row(
+            r#"
+            SELECT (SELECT COUNT(*) FROM prompts) != (SELECT COUNT(*) FROM prompts_fts)
+                OR EXISTS (SELECT 1 FROM prompts WHERE id NOT IN (SELECT id FROM prompts_fts))
+            "#,
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(out_of_step)
+    }
+
+    /// Compare `prompts` with `prompts_fts` by id and by a hash of the
+    /// indexed text
+    pub fn fts_drift(&self) -> Result<FtsDrift> {
+        let prompts = fts_hashes(
+            &self.conn,
+            "SELECT id, title, description, content, tags_text FROM prompts",
+        )?;
+        let index = fts_hashes(
+            &self.conn,
+            "SELECT id, title, description, content, tags_text FROM prompts_fts",
+        )?;
+
+        let mut drift = FtsDrift::default();
+        let mut indexed: HashMap<&str, &str> = HashMap::with_capacity(index.len());
+        for (id, hash) in &index {
+            if indexed.insert(id, hash).is_some() {
+                drift.duplicates += 1;
+            }
+        }
+        let stored: HashMap<&str, &str> = prompts
+            .iter()
+            .map(|(id, hash)| (id.as_str(), hash.as_str()))
+            .collect();
+
+        for (id, hash) in &stored {
+            match indexed.get(id) {
+                None => drift.missing.push(id.to_string()),
+                Some(indexed_hash) if indexed_hash != hash => drift.stale.push(id.to_string()),
+                Some(_) => {}
+            }
+        }
+        drift.orphaned = indexed
+            .keys()
+            .filter(|id| !stored.contains_key(*id))
+            .map(|id| id.to_string())
+            .collect();
+
+        drift.missing.sort();
+        drift.orphaned.sort();
+        drift.stale.sort();
+        Ok(drift)
+    }
+
+    /// Rebuild the search indexes if `prompts_fts` has drifted from
+    /// `prompts`; returns the drift that was repaired
+    pub fn repair_fts_drift(&mut self) -> Result<Option<FtsDrift>> {
+        let drift = self.fts_drift()?;
+        if drift.is_empty() {
+            return Ok(None);
+        }
+        self.rebuild_fts()?;
+        Ok(Some(drift))
+    }
+
+    /// Rebuild the prompt and note search indexes from their tables;
+    /// returns the number of prompts and notes indexed
+    pub fn rebuild_fts(&mut self) -> Result<(usize, usize)> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM prompts_fts", [])?;
+        let prompts = tx.execute(
+            r#"
+            INSERT INTO prompts_fts (id, title, description, content, tags_text)
+            SELECT id, title, description, content, tags_text FROM prompts
+            "#,
+            [],
+        )?;
+        tx.execute("DELETE FROM prompt_notes_fts", [])?;
+        let notes = tx.execute(
+            "INSERT INTO prompt_notes_fts (body, note_id, prompt_id) SELECT body, id, prompt_id FROM prompt_notes",
+            [],
+        )?;
+        tx.commit()?;
+        Ok((prompts, notes))
+    }
+
+    /// Reclaim free pages and truncate the WAL
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        self.checkpoint()
+    }
+
+    /// Refresh the query planner's statistics
+    pub fn analyze(&self) -> Result<()> {
+        self.conn.execute_batch("ANALYZE")?;
+        Ok(())
+    }
+
+    /// Drop the registry-derived tables (prompts, bundles, search indexes,
+    /// embeddings and meta) and recreate them empty; local data is kept
+    pub fn reset(&mut self) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute_batch(DROP_REGISTRY_TABLES)?;
+        tx.commit()?;
+        self.init_schema(None)
+    }
+
+    /// Bytes used by live pages and by free pages
+    pub fn page_usage(&self) -> Result<(i64, i64)> {
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        let page_count: i64 = self.conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let freelist: i64 = self.conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        Ok(((page_count - freelist) * page_size, freelist * page_size))
+    }
+
+    /// Row counts of the main tables
+    pub fn table_counts(&self) -> Result<Vec<(&'static str, i64)>> {
+        let tables = [
+            "prompts",
+            "prompt_tags",
+            "prompt_variables",
+            "prompt_revisions",
+            "prompt_changes",
+            "prompt_usage",
+            "prompt_stars",
+            "prompt_notes",
+            "collections",
+            "collection_prompts",
+            "bundles",
+            "bundle_prompts",
+            "prompt_embeddings",
+        ];
+        let mut counts = Vec::with_capacity(tables.len());
+        for table in tables {
+            let sql = format!("SELECT COUNT(*) FROM {}", table);
+            counts.push((table, self.conn.query_row(&sql, [], |row| row.get(0))?));
+        }
+        Ok(counts)
+    }
+
     /// Get metadata value
     pub fn get_meta(&self, key: &str) -> Result<String> {
         let value: String = self.conn.query_row(
@@ -558,6 +1876,580 @@ impl Database {
         )?;
         Ok(())
     }
+
+    /// Remove a metadata value
+    pub fn delete_meta(&self, key: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM registry_meta WHERE key = ?",
+            rusqlite::params![key],
+        )?;
+        Ok(())
+    }
+}
+
+/// SQL conditions on `p` for a query's exclusions and filters, plus the
+/// registry (`?3`); their values are appended to `values`
+fn search_conditions(query: &SearchQuery, values: &mut Vec<Value>) -> Vec<String> {
+    let mut conditions = vec!["(?3 IS NULL OR p.source = ?3)".to_string()];
+    if let Some(exclude) = &query.exclude {
+        values.push(Value::Text(exclude.clone()));
+        let n = values.len();
+        conditions.push(format!(
+            "p.id NOT IN (SELECT id FROM prompts_fts WHERE prompts_fts MATCH ?{n} \
+             UNION SELECT prompt_id FROM prompt_notes_fts WHERE prompt_notes_fts MATCH ?{n})"
+        ));
+    }
+    for filter in &query.filters {
+        let first = values.len() + 1;
+        values.extend(filter.values.iter().cloned().map(Value::Text));
+        let placeholders = (first..=values.len())
+            .map(|n| format!("?{}", n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let not = if filter.negated { "NOT " } else { "" };
+        conditions.push(match filter.field {
+            FilterField::Tag => format!(
+                "p.id {not}IN (SELECT prompt_id FROM prompt_tags WHERE LOWER(tag) IN ({placeholders}))"
+            ),
+            FilterField::Category => {
+                format!("LOWER(COALESCE(p.category, '')) {not}IN ({placeholders})")
+            }
+            FilterField::Featured => {
+                format!("(CASE WHEN p.featured THEN 'true' ELSE 'false' END) {not}IN ({placeholders})")
+            }
+        });
+    }
+    conditions
+}
+
+/// Map a row selected as `id, title, content, description, category,
+/// featured, version, author, saved_at, is_local, source` (tags and
+/// variables are loaded separately)
+fn prompt_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Prompt> {
+    Ok(Prompt {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        content: row.get(2)?,
+        description: row.get(3)?,
+        category: row.get(4)?,
+        tags: Vec::new(),
+        variables: Vec::new(),
+        featured: row.get::<_, i32>(5)? != 0,
+        version: row.get(6)?,
+        author: row.get(7)?,
+        saved_at: row.get(8)?,
+        is_local: row.get::<_, i32>(9)? != 0,
+        source: row.get(10)?,
+    })
+}
+
+/// Write a prompt plus its tags, variables, FTS row and embedding
+///
+/// Shared by single and bulk upserts so every write path keeps the
+/// denormalized `tags_text` and the standalone `prompts_fts` table in sync.
+fn write_prompt(conn: &Connection, prompt: &Prompt) -> Result<()> {
+    let tags_text = prompt.tags.join(" ");
+
+    conn.execute(
+        r#"
+        INSERT INTO prompts (id, title, content, description, category, tags_text, featured, version, author, saved_at, is_local, source)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title,
+            content = excluded.content,
+            description = excluded.description,
+            category = excluded.category,
+            tags_text = excluded.tags_text,
+            featured = excluded.featured,
+            version = excluded.version,
+            author = excluded.author,
+            saved_at = excluded.saved_at,
+            is_local = excluded.is_local,
+            source = excluded.source,
+            updated_at = datetime('now')
+        "#,
+        params![
+            &prompt.id,
+            &prompt.title,
+            &prompt.content,
+            &prompt.description,
+            &prompt.category,
+            &tags_text,
+            prompt.featured as i32,
+            &prompt.version,
+            &prompt.author,
+            &prompt.saved_at,
+            prompt.is_local as i32,
+            prompt_source(prompt),
+        ],
+    )?;
+
+    // Update tags (normalized)
+    conn.execute(
+        "DELETE FROM prompt_tags WHERE prompt_id = ?",
+        params![&prompt.id],
+    )?;
+
+    for tag in &prompt.tags {
+        conn.execute(
+            "INSERT INTO prompt_tags (prompt_id, tag) VALUES (?, ?)",
+            params![&prompt.id, tag],
+        )?;
+    }
+
+    // Update variables
+    conn.execute(
+        "DELETE FROM prompt_variables WHERE prompt_id = ?",
+        params![&prompt.id],
+    )?;
+
+    for var in &prompt.variables {
+        conn.execute(
+            r#"
+            INSERT INTO prompt_variables (prompt_id, name, var_type, required, description, default_value)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            params![
+                &prompt.id,
+                &var.name,
+                var_type_to_str(&var.var_type),
+                var.required as i32,
+                &var.description,
+                &var.default,
+            ],
+        )?;
+    }
+
+    // Update FTS index
+    conn.execute(
+        "DELETE FROM prompts_fts WHERE id = ?",
+        params![&prompt.id],
+    )?;
+
+    conn.execute(
+        r#"
+        INSERT INTO prompts_fts (id, title, description, content, tags_text)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        params![
+            &prompt.id,
+            &prompt.title,
+            &prompt.description,
+            &prompt.content,
+            &tags_text,
+        ],
+    )?;
+
+    write_embedding(
+        conn,
+        &prompt.id,
+        &embedding_text(&prompt.title, prompt.description.as_deref(), &tags_text),
+    )?;
+
+    record_revision(conn, prompt, &tags_text)
+}
+
+/// Text a prompt's embedding is computed from: title, description and
+/// tags, as the TypeScript `suggest` embeds (content would drown them out)
+fn embedding_text(title: &str, description: Option<&str>, tags_text: &str) -> String {
+    format!("{} {} {}", title, description.unwrap_or_default(), tags_text)
+}
+
+/// Store the current model's embedding of `text` for a prompt
+fn write_embedding(conn: &Connection, prompt_id: &str, text: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO prompt_embeddings (prompt_id, model, vector) VALUES (?, ?, ?)",
+        params![prompt_id, EMBEDDING_MODEL, vector_to_blob(&hash_embed(text))],
+    )?;
+    Ok(())
+}
+
+/// `(id, hash)` of each row of `sql`, which selects id, title, description,
+/// content and tags_text
+fn fts_hashes(conn: &Connection, sql: &str) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map([], |row| {
+            let mut hasher = Sha256::new();
+            for column in 1..5 {
+                let text: Option<String> = row.get(column)?;
+                hasher.update(text.unwrap_or_default().as_bytes());
+                hasher.update([0]);
+            }
+            Ok((row.get(0)?, format!("{:x}", hasher.finalize())))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Prompt ids of a collection in order
+fn collection_order(conn: &Connection, name: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT prompt_id FROM collection_prompts WHERE collection = ? ORDER BY position, added_at",
+    )?;
+    let ids = stmt
+        .query_map(params![name], |row| row.get(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(ids)
+}
+
+/// Renumber a collection's positions to match `order`
+fn write_collection_order(conn: &Connection, name: &str, order: &[String]) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "UPDATE collection_prompts SET position = ? WHERE collection = ? AND prompt_id = ?",
+    )?;
+    for (position, id) in order.iter().enumerate() {
+        stmt.execute(params![position as i64, name, id])?;
+    }
+    Ok(())
+}
+
+fn touch_collection(conn: &Connection, name: &str, at: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE collections SET updated_at = ? WHERE name = ?",
+        params![at, name],
+    )?;
+    Ok(())
+}
+
+/// Restore stars from a backup, replacing the current set or adding to it
+fn restore_stars(conn: &Connection, stars: &[Star], replace: bool) -> Result<()> {
+    if replace {
+        conn.execute("DELETE FROM prompt_stars", [])?;
+    }
+    for star in stars {
+        conn.execute(
+            "INSERT OR IGNORE INTO prompt_stars (prompt_id, starred_at) VALUES (?, ?)",
+            params![&star.id, &star.starred_at],
+        )?;
+    }
+    Ok(())
+}
+
+/// Restore notes from a backup, replacing the current notes or adding
+/// those not already present (same prompt and creation time)
+fn restore_notes(conn: &Connection, notes: &[Note], replace: bool) -> Result<()> {
+    if replace {
+        conn.execute("DELETE FROM prompt_notes", [])?;
+        conn.execute("DELETE FROM prompt_notes_fts", [])?;
+    }
+    for note in notes {
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM prompt_notes WHERE prompt_id = ? AND created_at = ?",
+            params![&note.prompt_id, &note.created_at],
+            |row| row.get(0),
+        )?;
+        if exists {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO prompt_notes (prompt_id, body, created_at, updated_at) VALUES (?, ?, ?, ?)",
+            params![&note.prompt_id, &note.body, &note.created_at, &note.updated_at],
+        )?;
+        let restored = Note {
+            id: conn.last_insert_rowid(),
+            ..note.clone()
+        };
+        index_note(conn, &restored)?;
+    }
+    Ok(())
+}
+
+/// Add a note to `prompt_notes_fts`
+fn index_note(conn: &Connection, note: &Note) -> Result<()> {
+    conn.execute(
+        "INSERT INTO prompt_notes_fts (body, note_id, prompt_id) VALUES (?, ?, ?)",
+        params![&note.body, note.id, &note.prompt_id],
+    )?;
+    Ok(())
+}
+
+/// Columns read by `revision_from_row`
+const REVISION_COLUMNS: &str = "prompt_id, revision, title, content, description, category, \
+     tags_text, featured, version, author, source, registry_version, recorded_at, is_local";
+
+fn revision_from_row(row: &rusqlite::Row) -> rusqlite::Result<PromptRevision> {
+    let tags_text: Option<String> = row.get(6)?;
+    Ok(PromptRevision {
+        prompt_id: row.get(0)?,
+        revision: row.get(1)?,
+        title: row.get(2)?,
+        content: row.get(3)?,
+        description: row.get(4)?,
+        category: row.get(5)?,
+        tags: tags_text
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+        featured: row.get::<_, i32>(7)? != 0,
+        version: row.get(8)?,
+        author: row.get(9)?,
+        source: row.get(10)?,
+        registry_version: row.get(11)?,
+        recorded_at: row.get(12)?,
+    })
+}
+
+/// Append a revision if the prompt differs from its latest one
+///
+/// Registry prompts are tagged with `META_REGISTRY_VERSION`; local prompts
+/// have no registry version.
+fn record_revision(conn: &Connection, prompt: &Prompt, tags_text: &str) -> Result<()> {
+    let latest = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM prompt_revisions WHERE prompt_id = ? ORDER BY revision DESC LIMIT 1",
+                REVISION_COLUMNS
+            ),
+            params![&prompt.id],
+            |row| Ok((revision_from_row(row)?, row.get::<_, i32>(13)? != 0)),
+        )
+        .optional()?;
+
+    if let Some((latest, is_local)) = &latest {
+        let stored = metadata_signature(
+            &latest.title,
+            latest.description.as_deref(),
+            latest.category.as_deref(),
+            &latest.tags.join(" "),
+            latest.featured,
+            latest.version.as_deref(),
+            latest.author.as_deref(),
+            *is_local,
+            &latest.source,
+            // Revisions don't snapshot variables
+            &prompt.variables,
+        );
+        if latest.content == prompt.content && stored == prompt_metadata_signature(prompt) {
+            return Ok(());
+        }
+    }
+
+    let registry_version: Option<String> = if prompt.is_local {
+        None
+    } else {
+        conn.query_row(
+            "SELECT value FROM registry_meta WHERE key = ?",
+            params![META_REGISTRY_VERSION],
+            |row| row.get(0),
+        )
+        .optional()?
+    };
+
+    conn.execute(
+        r#"
+        INSERT INTO prompt_revisions
+            (prompt_id, revision, title, content, description, category, tags_text,
+             featured, version, author, is_local, source, registry_version, recorded_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        params![
+            &prompt.id,
+            latest.map_or(1, |(latest, _)| latest.revision + 1),
+            &prompt.title,
+            &prompt.content,
+            &prompt.description,
+            &prompt.category,
+            tags_text,
+            prompt.featured as i32,
+            &prompt.version,
+            &prompt.author,
+            prompt.is_local as i32,
+            prompt_source(prompt),
+            registry_version,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Stored fields that `diff_stored_prompts` compares
+struct StoredSnapshot {
+    title: String,
+    content: String,
+    metadata: String,
+    source: String,
+}
+
+/// Write the changes that make the stored prompts match `prompts`
+fn reconcile_stored_prompts(
+    conn: &Connection,
+    prompts: &[Prompt],
+    preserve_sources: &[String],
+) -> Result<ChangeSet> {
+    let change_set = diff_stored_prompts(conn, prompts, preserve_sources)?;
+
+    let by_id: HashMap<&str, &Prompt> = prompts.iter().map(|p| (p.id.as_str(), p)).collect();
+    for change in &change_set.changes {
+        match by_id.get(change.id.as_str()) {
+            Some(prompt) if change.kind != ChangeKind::Removed => write_prompt(conn, prompt)?,
+            _ => delete_prompt(conn, &change.id)?,
+        }
+    }
+
+    Ok(change_set)
+}
+
+/// Diff incoming prompts against the `prompts` table (see `reconcile_prompts`)
+fn diff_stored_prompts(
+    conn: &Connection,
+    prompts: &[Prompt],
+    preserve_sources: &[String],
+) -> Result<ChangeSet> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, title, content, description, category, tags_text, featured, version, author, is_local, source
+        FROM prompts
+        "#,
+    )?;
+    let mut stored_variables: HashMap<String, Vec<PromptVariable>> = HashMap::new();
+    let mut variables_stmt = conn.prepare(
+        "SELECT name, var_type, required, description, default_value, prompt_id \
+         FROM prompt_variables ORDER BY id",
+    )?;
+    let mut rows = variables_stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let prompt_id: String = row.get(5)?;
+        stored_variables
+            .entry(prompt_id)
+            .or_default()
+            .push(variable_from_row(row)?);
+    }
+
+    let existing: HashMap<String, StoredSnapshot> = stmt
+        .query_map([], |row| {
+            let id: String = row.get(0)?;
+            let title: String = row.get(1)?;
+            let is_local = row.get::<_, i32>(9)? != 0;
+            let source: String = row.get(10)?;
+            let metadata = metadata_signature(
+                &title,
+                row.get::<_, Option<String>>(3)?.as_deref(),
+                row.get::<_, Option<String>>(4)?.as_deref(),
+                &row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                row.get::<_, i32>(6)? != 0,
+                row.get::<_, Option<String>>(7)?.as_deref(),
+                row.get::<_, Option<String>>(8)?.as_deref(),
+                is_local,
+                &source,
+                stored_variables.get(&id).map(Vec::as_slice).unwrap_or_default(),
+            );
+            Ok((
+                id,
+                StoredSnapshot {
+                    title,
+                    content: row.get(2)?,
+                    metadata,
+                    source,
+                },
+            ))
+        })?
+        .collect::<std::result::Result<_, _>>()?;
+
+    let mut change_set = ChangeSet::default();
+    let mut incoming = HashSet::with_capacity(prompts.len());
+
+    for prompt in prompts {
+        incoming.insert(prompt.id.as_str());
+        let kind = match existing.get(&prompt.id) {
+            None => ChangeKind::Added,
+            Some(stored) if stored.content != prompt.content => ChangeKind::ContentChanged,
+            Some(stored) if stored.metadata != prompt_metadata_signature(prompt) => {
+                ChangeKind::MetadataChanged
+            }
+            Some(_) => {
+                change_set.unchanged += 1;
+                continue;
+            }
+        };
+        change_set.changes.push(PromptChange {
+            id: prompt.id.clone(),
+            title: prompt.title.clone(),
+            kind,
+        });
+    }
+
+    let mut removed: Vec<PromptChange> = existing
+        .into_iter()
+        .filter(|(id, stored)| {
+            !incoming.contains(id.as_str()) && !preserve_sources.contains(&stored.source)
+        })
+        .map(|(id, stored)| PromptChange {
+            id,
+            title: stored.title,
+            kind: ChangeKind::Removed,
+        })
+        .collect();
+    removed.sort_by(|a, b| a.id.cmp(&b.id));
+    change_set.changes.extend(removed);
+
+    Ok(change_set)
+}
+
+fn prompt_metadata_signature(prompt: &Prompt) -> String {
+    metadata_signature(
+        &prompt.title,
+        prompt.description.as_deref(),
+        prompt.category.as_deref(),
+        &prompt.tags.join(" "),
+        prompt.featured,
+        prompt.version.as_deref(),
+        prompt.author.as_deref(),
+        prompt.is_local,
+        prompt_source(prompt),
+        &prompt.variables,
+    )
+}
+
+/// Comparable snapshot of every diffed column except `content`, plus the
+/// prompt's variables
+#[allow(clippy::too_many_arguments)]
+fn metadata_signature(
+    title: &str,
+    description: Option<&str>,
+    category: Option<&str>,
+    tags_text: &str,
+    featured: bool,
+    version: Option<&str>,
+    author: Option<&str>,
+    is_local: bool,
+    source: &str,
+    variables: &[PromptVariable],
+) -> String {
+    serde_json::json!([
+        title,
+        description,
+        category,
+        tags_text,
+        featured,
+        version,
+        author,
+        is_local,
+        source,
+        variables
+    ])
+    .to_string()
+}
+
+/// Value stored in the `source` column
+fn prompt_source(prompt: &Prompt) -> &str {
+    match prompt.source.as_deref() {
+        Some(source) => source,
+        None if prompt.is_local => LOCAL_SOURCE,
+        None => DEFAULT_SOURCE,
+    }
+}
+
+/// Delete a prompt plus its tags, variables, FTS row and embedding
+fn delete_prompt(conn: &Connection, id: &str) -> Result<()> {
+    conn.execute("DELETE FROM prompt_tags WHERE prompt_id = ?", params![id])?;
+    conn.execute("DELETE FROM prompt_variables WHERE prompt_id = ?", params![id])?;
+    conn.execute("DELETE FROM prompts_fts WHERE id = ?", params![id])?;
+    conn.execute("DELETE FROM prompt_embeddings WHERE prompt_id = ?", params![id])?;
+    conn.execute("DELETE FROM prompts WHERE id = ?", params![id])?;
+    Ok(())
 }
 
 fn var_type_to_str(vt: &VariableType) -> &'static str {
@@ -570,6 +2462,18 @@ fn var_type_to_str(vt: &VariableType) -> &'static str {
     }
 }
 
+/// Map a row selected as `name, var_type, required, description,
+/// default_value`
+fn variable_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PromptVariable> {
+    Ok(PromptVariable {
+        name: row.get(0)?,
+        var_type: str_to_var_type(&row.get::<_, String>(1)?),
+        required: row.get::<_, i32>(2)? != 0,
+        description: row.get(3)?,
+        default: row.get(4)?,
+    })
+}
+
 fn str_to_var_type(s: &str) -> VariableType {
     match s {
         "multiline" => VariableType::Multiline,
@@ -608,6 +2512,7 @@ mod tests {
             author: Some("Test Author".to_string()),
             saved_at: None,
             is_local: false,
+            source: None,
         };
 
         db.upsert_prompt(&prompt)?;
@@ -643,6 +2548,7 @@ mod tests {
                 author: None,
                 saved_at: None,
                 is_local: false,
+                source: None,
             },
             Prompt {
                 id: "p2".to_string(),
@@ -657,6 +2563,7 @@ mod tests {
                 author: None,
                 saved_at: None,
                 is_local: false,
+                source: None,
             },
         ];
 
@@ -665,17 +2572,17 @@ mod tests {
         }
 
         // Filter by category
-        let cat1 = db.list_prompts_filtered(Some("cat1"), None, false)?;
+        let cat1 = db.list_prompts_filtered(Some("cat1"), None, false, false, None)?;
         assert_eq!(cat1.len(), 1);
         assert_eq!(cat1[0].id, "p1");
 
         // Filter by tag
-        let tag2 = db.list_prompts_filtered(None, Some("tag2"), false)?;
+        let tag2 = db.list_prompts_filtered(None, Some("tag2"), false, false, None)?;
         assert_eq!(tag2.len(), 1);
         assert_eq!(tag2[0].id, "p2");
 
         // Filter featured
-        let featured = db.list_prompts_filtered(None, None, true)?;
+        let featured = db.list_prompts_filtered(None, None, true, false, None)?;
         assert_eq!(featured.len(), 1);
         assert_eq!(featured[0].id, "p1");
         Ok(())
@@ -685,7 +2592,7 @@ mod tests {
     fn test_category_and_tag_counts() -> Result<()> {
         let db = Database::in_memory()?;
 
-        let prompts = vec![
+        let prompts = [
             Prompt::new("p1", "P1", "C1"),
             Prompt::new("p2", "P2", "C2"),
         ];
@@ -712,4 +2619,661 @@ mod tests {
         assert!(tags.iter().any(|(t, c)| t == "tag2" && *c == 1));
         Ok(())
     }
+
+    #[test]
+    fn test_reconcile_prunes_removed_prompts() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let mut tagged = Prompt::new("gone", "Gone Prompt", "obsolete content");
+        tagged.tags = vec!["old".to_string()];
+        tagged.variables = vec![PromptVariable {
+            name: "X".to_string(),
+            var_type: VariableType::Text,
+            required: false,
+            description: None,
+            default: None,
+        }];
+        let mut local = Prompt::new("mine", "Mine", "local content");
+        local.is_local = true;
+        db.bulk_upsert_prompts(&[
+            tagged,
+            Prompt::new("kept", "Kept", "same"),
+            Prompt::new("changed", "Changed", "before"),
+            local,
+        ])?;
+
+        let counts = db.reconcile_prompts(
+            &[
+                Prompt::new("kept", "Kept", "same"),
+                Prompt::new("changed", "Changed", "after"),
+                Prompt::new("new", "New", "fresh"),
+            ],
+            &[],
+        )?;
+
+        assert_eq!(
+            (counts.added(), counts.updated(), counts.removed(), counts.unchanged),
+            (1, 1, 2, 1)
+        );
+        assert!(db.get_prompt("gone")?.is_none());
+        assert!(db.get_prompt("mine")?.is_none());
+        assert!(db.search("obsolete", 10, None, false)?.is_empty());
+        assert!(db.tag_counts()?.iter().all(|(tag, _)| tag != "old"));
+        assert!(db.get_prompt_variables("gone")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reconcile_writes_variable_only_changes() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let variable = |name: &str, default: Option<&str>| PromptVariable {
+            name: name.to_string(),
+            var_type: VariableType::Multiline,
+            required: true,
+            description: Some("what to review".to_string()),
+            default: default.map(str::to_string),
+        };
+        let mut prompt = Prompt::new("review", "Review", "Review {{CODE}}");
+        prompt.variables = vec![variable("CODE", None), variable("LANG", Some("rust"))];
+        db.reconcile_prompts(std::slice::from_ref(&prompt), &[])?;
+
+        // Stored variables compare equal to the ones they were written from
+        let unchanged = db.reconcile_prompts(std::slice::from_ref(&prompt), &[])?;
+        assert!(unchanged.changes.is_empty());
+
+        prompt.variables[1].default = Some("go".to_string());
+        let changes = db.reconcile_prompts(std::slice::from_ref(&prompt), &[])?;
+
+        assert_eq!(changes.changes.len(), 1);
+        assert_eq!(changes.changes[0].kind, ChangeKind::MetadataChanged);
+        let stored = db.get_prompt("review")?.expect("prompt stored");
+        assert_eq!(stored.variables, prompt.variables);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reconcile_keeps_preserved_sources() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(&[Prompt::new("remote-only", "Remote", "content")])?;
+
+        let counts = db.reconcile_prompts(
+            &[Prompt::new("bundled", "Bundled", "content")],
+            &[DEFAULT_SOURCE.to_string()],
+        )?;
+
+        assert_eq!(counts.removed(), 0);
+        assert!(db.get_prompt("remote-only")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_backup_is_all_or_nothing() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(&[Prompt::new("kept", "Kept", "content")])?;
+        db.star_prompt("kept", "2026-01-01T00:00:00+00:00")?;
+        db.conn.execute_batch(
+            "CREATE TRIGGER fail_notes BEFORE INSERT ON prompt_notes
+             BEGIN SELECT RAISE(ABORT, 'notes unavailable'); END;",
+        )?;
+
+        let note = Note {
+            id: 0,
+            prompt_id: "restored".to_string(),
+            body: "remember".to_string(),
+            created_at: "2026-01-02T00:00:00+00:00".to_string(),
+            updated_at: "2026-01-02T00:00:00+00:00".to_string(),
+        };
+        let result = db.restore_backup(
+            &[Prompt::new("restored", "Restored", "content")],
+            &[],
+            Some(&[]),
+            Some(&[note]),
+            true,
+        );
+
+        assert!(result.is_err());
+        assert!(db.get_prompt("kept")?.is_some());
+        assert!(db.get_prompt("restored")?.is_none());
+        assert_eq!(db.stars()?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_classifies_changes_without_writing() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(&[
+            Prompt::new("content", "Content", "before"),
+            Prompt::new("meta", "Meta", "same"),
+            Prompt::new("gone", "Gone", "x"),
+        ])?;
+
+        let mut retitled = Prompt::new("meta", "Meta v2", "same");
+        retitled.tags = vec!["new-tag".to_string()];
+        let incoming = vec![
+            Prompt::new("content", "Content", "after"),
+            retitled,
+            Prompt::new("fresh", "Fresh", "y"),
+        ];
+
+        let diff = db.diff_prompts(&incoming, &[])?;
+        let kinds: Vec<_> = diff.changes.iter().map(|c| (c.id.as_str(), c.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("content", ChangeKind::ContentChanged),
+                ("meta", ChangeKind::MetadataChanged),
+                ("fresh", ChangeKind::Added),
+                ("gone", ChangeKind::Removed),
+            ]
+        );
+        assert!(db.get_prompt("gone")?.is_some());
+        assert!(db.get_prompt("fresh")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_changes_since_filters_by_timestamp() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let change = |id: &str| PromptChange {
+            id: id.to_string(),
+            title: id.to_string(),
+            kind: ChangeKind::Added,
+        };
+        let now = chrono::Utc::now();
+        let earlier = (now - chrono::Duration::days(2)).to_rfc3339();
+        db.record_changes(&[change("old")], &earlier)?;
+        db.record_changes(&[change("new")], &now.to_rfc3339())?;
+
+        assert_eq!(db.changes_since(None)?.len(), 2);
+        let since = (now - chrono::Duration::days(1)).to_rfc3339();
+        let recent = db.changes_since(Some(&since))?;
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].id, "new");
+        Ok(())
+    }
+
+    #[test]
+    fn test_usage_stats_and_frecency_boost() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(&[
+            Prompt::new("alpha", "Review alpha", "review the code"),
+            Prompt::new("beta", "Review beta", "review the code"),
+        ])?;
+        let now = chrono::Utc::now();
+        let long_ago = (now - chrono::Duration::days(200)).to_rfc3339();
+        db.record_usage("alpha", UsageAction::Show, &long_ago)?;
+        for _ in 0..3 {
+            db.record_usage("beta", UsageAction::Copy, &now.to_rfc3339())?;
+        }
+
+        let all = db.usage_stats(None)?;
+        assert_eq!(
+            all.iter().map(|s| (s.id.as_str(), s.uses)).collect::<Vec<_>>(),
+            vec![("beta", 3), ("alpha", 1)]
+        );
+        let week = (now - chrono::Duration::days(7)).to_rfc3339();
+        assert_eq!(db.usage_stats(Some(&week))?.len(), 1);
+
+        let scores = db.frecency_scores(now)?;
+        assert_eq!(scores.get("beta"), Some(&300.0));
+        assert_eq!(scores.get("alpha"), Some(&FRECENCY_FLOOR));
+
+        let plain = db.search("review", 10, None, false)?;
+        let boosted = db.search("review", 10, None, true)?;
+        assert_eq!(boosted[0].0.id, "beta");
+        let plain_beta = plain.iter().find(|(p, _)| p.id == "beta").map(|r| r.1);
+        assert!(Some(boosted[0].1) > plain_beta);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fts_drift_detection_and_repair() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        for (id, content) in [("a", "alpha"), ("b", "bravo"), ("c", "charlie")] {
+            db.upsert_prompt(&Prompt::new(id, id.to_uppercase(), content))?;
+        }
+        assert!(db.fts_drift()?.is_empty());
+        assert!(db.repair_fts_drift()?.is_none());
+
+        // Simulate a crash between writes and hand edits of the index
+        db.conn.execute("DELETE FROM prompts_fts WHERE id = 'a'", [])?;
+        db.conn.execute("UPDATE prompts SET content = 'bravissimo' WHERE id = 'b'", [])?;
+        db.conn.execute(
+            "INSERT INTO prompts_fts (id, title, description, content, tags_text) VALUES ('ghost', 'G', NULL, 'boo', '')",
+            [],
+        )?;
+        db.conn.execute(
+            "INSERT INTO prompts_fts (id, title, description, content, tags_text) SELECT id, title, description, content, tags_text FROM prompts WHERE id = 'c'",
+            [],
+        )?;
+
+        let drift = db.fts_drift()?;
+        assert_eq!(drift.missing, vec!["a"]);
+        assert_eq!(drift.stale, vec!["b"]);
+        assert_eq!(drift.orphaned, vec!["ghost"]);
+        assert_eq!(drift.duplicates, 1);
+        assert_eq!(drift.summary(), "1 missing, 1 orphaned, 1 stale, 1 duplicate");
+        assert!(db.search("bravissimo", 10, None, false)?.is_empty());
+
+        assert_eq!(db.repair_fts_drift()?, Some(drift));
+        assert!(db.fts_drift()?.is_empty());
+        assert_eq!(db.search("alpha", 10, None, false)?.len(), 1);
+        assert_eq!(db.search("bravissimo", 10, None, false)?.len(), 1);
+        assert!(db.search("boo", 10, None, false)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_repairs_fts_drift() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("jfp.db");
+        {
+            let db = Database::open_at(&path)?;
+            db.upsert_prompt(&Prompt::new("a", "Alpha", "searchable words"))?;
+        }
+        {
+            let conn = Connection::open(&path)?;
+            conn.execute("DELETE FROM prompts_fts", [])?;
+        }
+
+        // A maintenance open leaves the drift for `jfp db check` to report
+        let db = Database::connect(&path)?;
+        assert!(db.fts_rows_out_of_step()?);
+        assert_eq!(db.fts_drift()?.missing, vec!["a"]);
+        drop(db);
+
+        let db = Database::open_at(&path)?;
+        assert!(!db.fts_rows_out_of_step()?);
+        assert!(db.fts_drift()?.is_empty());
+        assert_eq!(db.search("searchable", 10, None, false)?.len(), 1);
+
+        // Changed text is only found by the full comparison
+        db.conn.execute("UPDATE prompts SET content = 'edited' WHERE id = 'a'", [])?;
+        drop(db);
+        let db = Database::open_at(&path)?;
+        assert!(!db.fts_rows_out_of_step()?);
+        assert_eq!(db.fts_drift()?.stale, vec!["a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_consistency_issues_rebuild_and_reset() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        db.upsert_prompt(&Prompt::new("a", "Alpha", "first"))?;
+        db.upsert_prompt(&Prompt::new("b", "Beta", "second"))?;
+        db.add_note("a", "note body", "2026-01-01T00:00:00Z")?;
+        assert!(db.consistency_issues()?.is_empty());
+
+        db.conn.execute("DELETE FROM prompts_fts WHERE id = 'a'", [])?;
+        db.conn.execute(
+            "INSERT INTO prompts_fts (id, title, description, content, tags_text) VALUES ('ghost', '', '', '', '')",
+            [],
+        )?;
+        db.conn.execute("DELETE FROM prompt_notes_fts", [])?;
+        let issues = db.consistency_issues()?;
+        assert_eq!(issues.len(), 3, "{:?}", issues);
+        assert!(db.search("first", 10, None, false)?.is_empty());
+
+        assert_eq!(db.rebuild_fts()?, (2, 1));
+        assert!(db.consistency_issues()?.is_empty());
+        assert_eq!(db.search("first", 10, None, false)?.len(), 1);
+
+        db.reset()?;
+        assert_eq!(db.prompt_count()?, 0);
+        assert_eq!(db.notes(None)?.len(), 1);
+        assert!(db.consistency_issues()?.is_empty());
+        assert_eq!(db.schema_version(), SCHEMA_VERSION);
+        assert!(db.integrity_check()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_namespaced_ids_and_registry_filter() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let mut team = Prompt::new("code-review", "Team Review", "team content");
+        team.source = Some("team".to_string());
+        let mut default = Prompt::new("default/code-review", "Default Review", "default content");
+        default.source = Some(DEFAULT_SOURCE.to_string());
+        db.bulk_upsert_prompts(&[team, default])?;
+
+        let by_name = db.get_prompt("team/code-review")?.expect("namespaced lookup");
+        assert_eq!(by_name.title, "Team Review");
+        assert_eq!(by_name.source.as_deref(), Some("team"));
+        assert_eq!(
+            db.get_prompt("default/code-review")?.map(|p| p.title),
+            Some("Default Review".to_string())
+        );
+        assert!(db.get_prompt("other/code-review")?.is_none());
+
+        let listed = db.list_prompts_filtered(None, None, false, false, Some("team"))?;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(db.search("review", 10, Some(DEFAULT_SOURCE), false)?.len(), 1);
+        assert_eq!(db.source_counts()?.get("team"), Some(&1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_query_language() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let mut rust = Prompt::new("rust-review", "Rust Review", "Review c++ and rust code");
+        rust.category = Some("Review".to_string());
+        rust.tags = vec!["Rust".to_string(), "code-review".to_string()];
+        rust.featured = true;
+        let mut tests = Prompt::new("write-tests", "Write Tests", "Write tests for rust code");
+        tests.category = Some("testing".to_string());
+        tests.tags = vec!["testing".to_string()];
+        let draft = Prompt::new("draft", "Draft", "An unfinished review draft");
+        db.bulk_upsert_prompts(&[rust, tests, draft])?;
+        db.conn.execute(
+            "INSERT INTO prompt_notes (prompt_id, body, created_at, updated_at) VALUES ('write-tests', 'flaky', '', '')",
+            [],
+        )?;
+        let note_id = db.conn.last_insert_rowid();
+        db.conn.execute(
+            "INSERT INTO prompt_notes_fts (body, note_id, prompt_id) VALUES ('flaky', ?, 'write-tests')",
+            params![note_id],
+        )?;
+
+        let ids = |query: &str| -> Result<Vec<String>> {
+            let mut ids: Vec<String> = db.search(query, 10, None, false)?.into_iter().map(|(p, _)| p.id).collect();
+            ids.sort();
+            Ok(ids)
+        };
+
+        // Punctuation is searched for, not parsed as FTS5 syntax
+        assert_eq!(ids("c++")?, vec!["rust-review"]);
+        assert_eq!(ids("code-review")?, vec!["rust-review"]);
+        assert_eq!(ids("rust -c++")?, vec!["write-tests"]);
+        assert_eq!(ids("-flaky review")?, vec!["draft", "rust-review"]);
+        assert_eq!(ids(r#""rust code""#)?, vec!["rust-review", "write-tests"]);
+        assert_eq!(ids("unfinished OR tests")?, vec!["draft", "write-tests"]);
+
+        // Filters, alone or with terms
+        assert_eq!(ids("tag:rust")?, vec!["rust-review"]);
+        assert_eq!(ids("tag:rust OR tag:testing")?, vec!["rust-review", "write-tests"]);
+        assert_eq!(ids("code -tag:rust")?, vec!["write-tests"]);
+        assert_eq!(ids("category:review")?, vec!["rust-review"]);
+        assert_eq!(ids("-category:review -category:testing")?, vec!["draft"]);
+        assert_eq!(ids("featured:true")?, vec!["rust-review"]);
+        assert_eq!(ids("review featured:false")?, vec!["draft"]);
+
+        let err = db.search(r#"review "open"#, 10, None, false).unwrap_err();
+        assert_eq!(err.downcast_ref::<crate::storage::QueryError>().map(|e| e.position), Some(7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_synonym_matches_rank_below_literal_ones() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(&[
+            Prompt::new("speed-up", "Performance Optimizer", "Make slow code faster"),
+            Prompt::new("perf-audit", "Perf Audit", "Audit perf regressions"),
+            Prompt::new("unrelated", "Unrelated", "Nothing to see"),
+        ])?;
+
+        let mut query = SearchQuery::parse("perf")?;
+        let ids = |query: &SearchQuery| -> Result<Vec<String>> {
+            Ok(db.search_query(query, 10, None, false)?.into_iter().map(|(p, _)| p.id).collect())
+        };
+        assert_eq!(ids(&query)?, vec!["perf-audit"]);
+
+        query.expand(&crate::storage::Synonyms::builtin());
+        assert_eq!(ids(&query)?, vec!["perf-audit", "speed-up"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_semantic_search_blends_keywords_and_embeddings() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let described = |id: &str, title: &str, description: &str, tag: &str| {
+            let mut prompt = Prompt::new(id, title, "...");
+            prompt.description = Some(description.to_string());
+            prompt.tags = vec![tag.to_string()];
+            prompt
+        };
+        let api = described("api-design", "API Design Review", "Review REST endpoints", "api");
+        let poem = described("poem", "Birthday Poem", "Write a cheerful verse", "writing");
+        let tests = described("write-tests", "Test Generator", "Generate unit tests", "testing");
+        db.bulk_upsert_prompts(&[api, poem, tests])?;
+
+        let count = |db: &Database| -> Result<i64> {
+            Ok(db.conn.query_row("SELECT COUNT(*) FROM prompt_embeddings", [], |row| row.get(0))?)
+        };
+        assert_eq!(count(&db)?, 3);
+
+        // No prompt has every word, but one means much the same
+        let task = "designing endpoints for a rest service";
+        let query = SearchQuery::words(task);
+        assert!(db.search_query(&query, 10, None, false)?.is_empty());
+        let results = db.semantic_search(task, &query, 10, false)?;
+        assert_eq!(results.first().map(|(p, _)| p.id.as_str()), Some("api-design"));
+        assert!(results.iter().all(|(p, _)| p.id != "poem"));
+        assert!(results[0].1 <= SEMANTIC_WEIGHT);
+
+        // A keyword hit adds its scaled BM25 share
+        let query = SearchQuery::words("generate tests");
+        let results = db.semantic_search("generate tests", &query, 1, false)?;
+        assert_eq!(results[0].0.id, "write-tests");
+        assert!(results[0].1 > 1.0 - SEMANTIC_WEIGHT);
+
+        // Vectors follow deletes and are recomputed when missing or stale
+        db.reconcile_prompts(&[], &[])?;
+        assert_eq!(count(&db)?, 0);
+        db.bulk_upsert_prompts(&[Prompt::new("a", "A", "first")])?;
+        db.conn.execute("UPDATE prompt_embeddings SET model = 'old'", [])?;
+        db.conn.execute("INSERT INTO prompts (id, title, content) VALUES ('b', 'B', 'second')", [])?;
+        assert_eq!(db.ensure_embeddings()?, 2);
+        assert_eq!(db.ensure_embeddings()?, 0);
+        assert_eq!(count(&db)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_highlights_mark_matches_by_field() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let mut rust = Prompt::new(
+            "rust-review",
+            "Rust Review",
+            "Check ownership first.\n\nThen review unsafe blocks and fix error handling.",
+        );
+        rust.category = Some("Review".to_string());
+        rust.tags = vec!["rust".to_string(), "quality".to_string()];
+        db.bulk_upsert_prompts(&[rust, Prompt::new("draft", "Draft", "Nothing here")])?;
+        let ids = vec!["rust-review".to_string(), "draft".to_string()];
+
+        let query = SearchQuery::parse("unsafe rust category:review")?;
+        let highlights = db.search_highlights(&query, &ids)?;
+        let rust = &highlights["rust-review"];
+        let matched = |field: SearchField| -> Vec<&str> {
+            rust.matches.iter().filter(|m| m.field == field).map(|m| m.term.as_str()).collect()
+        };
+        assert_eq!(matched(SearchField::Id), vec!["rust"]);
+        assert_eq!(matched(SearchField::Title), vec!["rust"]);
+        assert_eq!(matched(SearchField::Content), vec!["unsafe"]);
+        assert_eq!(matched(SearchField::Tag), vec!["rust"]);
+        assert_eq!(matched(SearchField::Category), vec!["review"]);
+        assert_eq!(
+            rust.title.as_deref().map(|t| crate::storage::render_marked(t, "[", "]")),
+            Some("[Rust] Review".to_string())
+        );
+        let snippet = rust.snippet.as_deref().map(|s| crate::storage::render_marked(s, "[", "]"));
+        assert!(snippet.is_some_and(|s| s.contains("review [unsafe] blocks") && !s.contains('\n')));
+        assert_eq!(highlights["draft"], SearchHighlight::default());
+
+        // Synonym hits are marked through the expanded query
+        let mut query = SearchQuery::parse("repair")?;
+        query.expand(&crate::storage::Synonyms::builtin());
+        assert_eq!(
+            db.search_highlights(&query, &ids)?["rust-review"].matches,
+            vec![SearchMatch { field: SearchField::Content, term: "fix".to_string() }]
+        );
+        let query = SearchQuery::parse("tag:quality")?;
+        assert_eq!(
+            db.search_highlights(&query, &ids)?["rust-review"].matches,
+            vec![SearchMatch { field: SearchField::Tag, term: "quality".to_string() }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy_search_and_similar_ids() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let mut refactor = Prompt::new("refactor", "Refactor Code", "Improve structure");
+        refactor.tags = vec!["cleanup".to_string()];
+        let mut review = Prompt::new("code-review", "Code Review", "Find bugs");
+        review.featured = true;
+        db.bulk_upsert_prompts(&[refactor, review])?;
+
+        assert!(db.search("refactr", 10, None, false)?.is_empty());
+        let fuzzy = db.fuzzy_search(&SearchQuery::parse("refactr")?, 10, None)?;
+        assert_eq!(fuzzy.len(), 1);
+        assert_eq!(fuzzy[0].0.id, "refactor");
+        assert_eq!(fuzzy[0].0.tags, vec!["cleanup".to_string()]);
+        assert!(fuzzy[0].1 >= super::super::fuzzy::FUZZY_THRESHOLD && fuzzy[0].1 < 1.0);
+
+        // Tags count, and the query's filters still apply
+        assert_eq!(db.fuzzy_search(&SearchQuery::parse("clenup")?, 10, None)?.len(), 1);
+        assert!(db.fuzzy_search(&SearchQuery::parse("refactr featured:true")?, 10, None)?.is_empty());
+        assert!(db.fuzzy_search(&SearchQuery::parse("refactr")?, 10, Some("team"))?.is_empty());
+
+        assert_eq!(db.similar_ids("code-reveiw", 3)?, vec!["code-review".to_string()]);
+        assert_eq!(db.similar_ids("refactoring", 3)?, vec!["refactor".to_string()]);
+        assert!(db.similar_ids("deploy", 3)?.is_empty());
+        assert_eq!(db.similar_ids("refac", 3)?, vec!["refactor".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_adds_source_column_to_older_schema() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("old.db");
+        {
+            // A v3 database: the v2 layout plus the change log
+            let conn = Connection::open(&path)?;
+            conn.execute_batch(include_str!("fixtures/schema_v2.sql"))?;
+            conn.execute_batch(
+                r#"
+                CREATE TABLE prompt_changes (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT, prompt_id TEXT NOT NULL,
+                    title TEXT NOT NULL, kind TEXT NOT NULL, changed_at TEXT NOT NULL
+                );
+                UPDATE registry_meta SET value = '3' WHERE key = 'schema_version';
+                INSERT INTO prompts (id, title, content) VALUES ('old', 'Old', 'body');
+                "#,
+            )?;
+        }
+
+        let db = Database::open_at(&path)?;
+        let stored = db.get_prompt("old")?.expect("existing prompt kept");
+        assert_eq!(stored.source.as_deref(), Some(DEFAULT_SOURCE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrations_are_ordered_and_end_at_schema_version() {
+        let versions: Vec<i32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let expected: Vec<i32> = (1..=SCHEMA_VERSION).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[test]
+    fn test_migrates_fixture_databases_to_latest() -> Result<()> {
+        let fixtures = [
+            (1, include_str!("fixtures/schema_v1.sql")),
+            (2, include_str!("fixtures/schema_v2.sql")),
+        ];
+
+        for (version, sql) in fixtures {
+            let dir = tempfile::tempdir()?;
+            let path = dir.path().join("jfp.db");
+            Connection::open(&path)?.execute_batch(sql)?;
+
+            let mut db = Database::open_at(&path)?;
+            assert_eq!(db.schema_version(), SCHEMA_VERSION, "from v{}", version);
+
+            let stored = db.get_prompt("idea-wizard")?.expect("fixture prompt kept");
+            assert_eq!(stored.tags.len(), 2);
+            assert_eq!(stored.variables.len(), 1);
+            assert!(!stored.is_local);
+            assert_eq!(stored.source.as_deref(), Some(DEFAULT_SOURCE));
+            assert_eq!(db.search("brainstorm*", 10, None, false)?.len(), 1);
+            assert_eq!(db.get_meta("last_sync")?, "2026-01-01T00:00:00+00:00");
+
+            // Existing prompts start their history at revision 1
+            let revisions = db.prompt_revisions("idea-wizard")?;
+            assert_eq!(revisions.len(), 1);
+            assert_eq!(revisions[0].content, stored.content);
+
+            // Tables from later migrations are usable
+            let changes = db.reconcile_prompts(&[Prompt::new("new", "New", "body")], &[])?;
+            db.record_changes(&changes.changes, &chrono::Utc::now().to_rfc3339())?;
+            assert!(!db.changes_since(Some("2026-01-01T00:00:00+00:00"))?.is_empty());
+            assert_eq!(db.prompt_revisions("new")?.len(), 1);
+            assert_eq!(db.prompt_revisions("idea-wizard")?.len(), 1, "history outlives removal");
+
+            // The pre-migration copy keeps the old version
+            let backups: Vec<_> = std::fs::read_dir(dir.path().join("backups"))?
+                .collect::<std::io::Result<_>>()?;
+            assert_eq!(backups.len(), 1);
+            let backup = Connection::open(backups[0].path())?;
+            let backed_up: String = backup.query_row(
+                "SELECT value FROM registry_meta WHERE key = 'schema_version'",
+                [],
+                |row| row.get(0),
+            )?;
+            assert_eq!(backed_up, version.to_string());
+
+            // Reopening at the latest version does not back up again
+            drop(db);
+            Database::open_at(&path)?;
+            assert_eq!(std::fs::read_dir(dir.path().join("backups"))?.count(), 1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_opens_migrate_once() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("jfp.db");
+        Connection::open(&path)?.execute_batch(include_str!("fixtures/schema_v1.sql"))?;
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| Database::open_at(&path))).collect();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().expect("thread panicked").map(drop))
+        })?;
+
+        assert_eq!(Database::open_at(&path)?.schema_version(), SCHEMA_VERSION);
+        assert_eq!(std::fs::read_dir(dir.path().join("backups"))?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_refuses_database_from_newer_jfp() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("jfp.db");
+        {
+            let conn = Connection::open(&path)?;
+            conn.execute_batch(
+                "CREATE TABLE registry_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+            )?;
+            conn.execute(
+                "INSERT INTO registry_meta VALUES ('schema_version', ?)",
+                params![(SCHEMA_VERSION + 1).to_string()],
+            )?;
+        }
+
+        let err = Database::open_at(&path).err().expect("newer schema refused");
+        assert!(format!("{:#}", err).contains("newer than this jfp supports"), "{:#}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_database_is_not_backed_up() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let db = Database::open_at(&dir.path().join("jfp.db"))?;
+        assert_eq!(db.schema_version(), SCHEMA_VERSION);
+        assert!(!dir.path().join("backups").exists());
+        Ok(())
+    }
 }
@@ -0,0 +1,140 @@
+//! Hash embeddings for semantic search
+//!
+//! A port of `packages/core/src/search/hash-embedder.ts`: each token and
+//! each of its character trigrams is hashed (FNV-1a) into a fixed-size
+//! vector, so texts sharing words or word parts point the same way. It is
+//! deterministic and needs no model, so vectors are computed when a prompt
+//! is written and compared by cosine similarity at query time.
+
+/// Vector length, as in the TypeScript default
+pub const EMBEDDING_DIMS: usize = 128;
+
+/// Stored with each vector; bump it when `hash_embed` changes so stored
+/// vectors are recomputed
+pub const EMBEDDING_MODEL: &str = "hash-v1";
+
+/// Dropped by the tokenizer, as in `tokenize.ts`
+const STOPWORDS: &[&str] = &["a", "an", "the", "and", "or", "but", "of", "is", "are", "was", "were", "be", "been"];
+
+/// Single-letter words kept by the tokenizer (language names)
+const SINGLE_LETTER_WORDS: &[&str] = &["c", "r", "v", "x", "k"];
+
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Embed `text` as a unit vector of `EMBEDDING_DIMS` (all zeros when it
+/// has no tokens)
+pub fn hash_embed(text: &str) -> Vec<f32> {
+    let mut vector = vec![0f32; EMBEDDING_DIMS];
+    for token in tokenize(text) {
+        // Whole tokens weigh double
+        let hash = fnv1a(&token);
+        vector[hash as usize % EMBEDDING_DIMS] += sign(hash) * 2.0;
+
+        // Trigrams catch shared stems and typos
+        let chars: Vec<char> = token.chars().collect();
+        for gram in chars.windows(3) {
+            let hash = fnv1a(&gram.iter().collect::<String>());
+            for k in 1..=3 {
+                let projected = hash.wrapping_mul(k);
+                vector[projected as usize % EMBEDDING_DIMS] += sign(projected);
+            }
+        }
+    }
+
+    let magnitude = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if magnitude > 0.0 {
+        for value in &mut vector {
+            *value /= magnitude;
+        }
+    }
+    vector
+}
+
+/// Cosine similarity of two vectors; 0 when either is zero or the lengths
+/// differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0f64, 0f64, 0f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(*x), f64::from(*y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Little-endian `f32`s, as stored in `prompt_embeddings.vector`
+pub fn vector_to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Inverse of `vector_to_blob`; trailing bytes are ignored
+pub fn vector_from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+/// Lowercase words of letters, digits, `+` and `#`, without stopwords
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '+' || c == '#'))
+        .filter(|word| {
+            (word.chars().count() > 1 || SINGLE_LETTER_WORDS.contains(word))
+                && !STOPWORDS.contains(word)
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// FNV-1a over UTF-16 code units, matching `charCodeAt` in the TypeScript
+fn fnv1a(text: &str) -> u32 {
+    text.encode_utf16().fold(FNV_OFFSET_BASIS, |hash, unit| {
+        (hash ^ u32::from(unit)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// +1 or -1 from bit 16 of a hash
+fn sign(hash: u32) -> f32 {
+    if (hash >> 16) & 1 == 1 { 1.0 } else { -1.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_embed_is_deterministic_and_normalized() {
+        let vector = hash_embed("Review my Rust code");
+        assert_eq!(vector.len(), EMBEDDING_DIMS);
+        assert_eq!(vector, hash_embed("review my rust code!"));
+        let norm: f32 = vector.iter().map(|v| v * v).sum();
+        assert!((norm - 1.0).abs() < 1e-5);
+
+        assert!(hash_embed("").iter().all(|v| *v == 0.0));
+        assert!(hash_embed("the a of").iter().all(|v| *v == 0.0));
+        assert_eq!(tokenize("C++ and C# or a Go-lang"), vec!["c++", "c#", "go", "lang"]);
+        assert_eq!(fnv1a(""), FNV_OFFSET_BASIS);
+        assert_eq!(fnv1a("a"), 0xe40c_292c);
+    }
+
+    #[test]
+    fn test_similar_texts_score_higher() {
+        let query = hash_embed("speed up slow database queries");
+        let related = hash_embed("Optimize slow SQL queries and database indexes");
+        let unrelated = hash_embed("Write a friendly birthday poem");
+        assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated));
+        assert!((cosine_similarity(&related, &related) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&query, &[]), 0.0);
+        assert_eq!(cosine_similarity(&query, &hash_embed("")), 0.0);
+
+        assert_eq!(vector_from_blob(&vector_to_blob(&related)), related);
+    }
+}
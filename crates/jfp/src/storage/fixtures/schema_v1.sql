@@ -0,0 +1,92 @@
+-- jfp.db as written by schema version 1
+
+-- Prompts table (with denormalized tags_text for FTS)
+CREATE TABLE IF NOT EXISTS prompts (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    description TEXT,
+    category TEXT,
+    tags_text TEXT, -- Denormalized tags for FTS
+    featured INTEGER NOT NULL DEFAULT 0,
+    version TEXT,
+    author TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Tags table (many-to-many, normalized source)
+CREATE TABLE IF NOT EXISTS prompt_tags (
+    prompt_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (prompt_id, tag),
+    FOREIGN KEY (prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+);
+
+-- Variables table
+CREATE TABLE IF NOT EXISTS prompt_variables (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    var_type TEXT NOT NULL DEFAULT 'text',
+    required INTEGER NOT NULL DEFAULT 0,
+    description TEXT,
+    default_value TEXT,
+    FOREIGN KEY (prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+);
+
+-- Bundles table
+CREATE TABLE IF NOT EXISTS bundles (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    version TEXT,
+    featured INTEGER NOT NULL DEFAULT 0,
+    author TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Bundle prompts (many-to-many)
+CREATE TABLE IF NOT EXISTS bundle_prompts (
+    bundle_id TEXT NOT NULL,
+    prompt_id TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (bundle_id, prompt_id),
+    FOREIGN KEY (bundle_id) REFERENCES bundles(id) ON DELETE CASCADE,
+    FOREIGN KEY (prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+);
+
+-- Registry metadata
+CREATE TABLE IF NOT EXISTS registry_meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- FTS5 for full-text search (standalone, not content-linked)
+-- We manage it manually in the upsert logic
+CREATE VIRTUAL TABLE IF NOT EXISTS prompts_fts USING fts5(
+    id,
+    title,
+    description,
+    content,
+    tags_text
+);
+
+-- Indexes
+CREATE INDEX IF NOT EXISTS idx_prompts_category ON prompts(category);
+CREATE INDEX IF NOT EXISTS idx_prompts_featured ON prompts(featured) WHERE featured = 1;
+CREATE INDEX IF NOT EXISTS idx_prompt_tags_tag ON prompt_tags(tag);
+
+INSERT INTO prompts (id, title, content, description, category, tags_text, featured)
+    VALUES ('idea-wizard', 'The Idea Wizard', 'Come up with 30 ideas, then pick the best 5.',
+            'Generate and rank ideas', 'ideation', 'ideation brainstorming', 1);
+
+INSERT INTO prompts_fts (id, title, description, content, tags_text)
+    VALUES ('idea-wizard', 'The Idea Wizard', 'Generate and rank ideas', 'Come up with 30 ideas, then pick the best 5.', 'ideation brainstorming');
+INSERT INTO prompt_tags (prompt_id, tag) VALUES ('idea-wizard', 'ideation'), ('idea-wizard', 'brainstorming');
+INSERT INTO prompt_variables (prompt_id, name, var_type, required, description)
+    VALUES ('idea-wizard', 'TOPIC', 'text', 1, 'What to brainstorm about');
+INSERT INTO bundles (id, title, description) VALUES ('starter', 'Starter', 'First prompts');
+INSERT INTO bundle_prompts (bundle_id, prompt_id, position) VALUES ('starter', 'idea-wizard', 0);
+INSERT INTO registry_meta (key, value) VALUES ('last_sync', '2026-01-01T00:00:00+00:00');
+INSERT INTO registry_meta (key, value) VALUES ('schema_version', '1');
@@ -0,0 +1,158 @@
+//! Typo-tolerant matching
+//!
+//! FTS5 only matches whole tokens (or prefixes), so `refactr` finds
+//! nothing. This scores query words against the words of a prompt's id,
+//! title and tags by edit distance. `jfp search` falls back to it when
+//! BM25 finds little, and `show` / `copy` / `render` use it to suggest ids.
+
+use super::query::QueryTerm;
+
+/// Lowest similarity (0..=1) at which a word counts as a match
+pub const FUZZY_THRESHOLD: f64 = 0.7;
+
+/// Similarity of a query word that a prompt word starts with
+const PREFIX_SIMILARITY: f64 = 0.9;
+
+/// Lowercase words of an id, title or tag, plus the whole text
+pub fn index_words(text: &str) -> Vec<String> {
+    let text = text.to_lowercase();
+    let mut words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect();
+    if words.len() > 1 {
+        words.push(text);
+    }
+    words
+}
+
+/// Similarity of `query` to `word`: 1 for equal, `PREFIX_SIMILARITY` when
+/// `word` starts with it, else `1 - distance / longer length`
+pub fn similarity(query: &str, word: &str) -> f64 {
+    if query == word {
+        return 1.0;
+    }
+    if query.chars().count() >= 3 && word.starts_with(query) {
+        return PREFIX_SIMILARITY;
+    }
+    let longer = query.chars().count().max(word.chars().count());
+    if longer == 0 {
+        return 0.0;
+    }
+    1.0 - edit_distance(query, word) as f64 / longer as f64
+}
+
+/// Score a prompt's words against query terms
+///
+/// Each entry of `terms` is an OR group; every group needs an alternative
+/// whose words all reach `FUZZY_THRESHOLD`. Returns the mean similarity of
+/// the best alternatives, or `None` if a group has no match.
+pub fn fuzzy_score(terms: &[Vec<QueryTerm>], words: &[String]) -> Option<f64> {
+    if terms.is_empty() {
+        return None;
+    }
+    let mut total = 0.0;
+    for group in terms {
+        let best = group
+            .iter()
+            .filter_map(|alternative| alternative_score(&alternative.text, words))
+            .fold(None, |best: Option<f64>, score| Some(best.map_or(score, |b| b.max(score))))?;
+        total += best;
+    }
+    Some(total / terms.len() as f64)
+}
+
+fn alternative_score(alternative: &str, words: &[String]) -> Option<f64> {
+    let query_words = index_words(alternative);
+    let query_words: Vec<&String> = match query_words.len() {
+        0 => return None,
+        1 => query_words.iter().collect(),
+        // Drop the whole-text entry `index_words` appends
+        n => query_words[..n - 1].iter().collect(),
+    };
+    let mut total = 0.0;
+    for query in &query_words {
+        let best = words
+            .iter()
+            .map(|word| similarity(query, word))
+            .fold(0.0, f64::max);
+        if best < FUZZY_THRESHOLD {
+            return None;
+        }
+        total += best;
+    }
+    Some(total / query_words.len() as f64)
+}
+
+/// Edit distance between two strings, by character, counting a swap of
+/// adjacent characters as one edit (`reveiw` -> `review`)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(groups: &[&[&str]]) -> Vec<Vec<QueryTerm>> {
+        groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|t| QueryTerm {
+                        text: t.to_string(),
+                        fts: String::new(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_edit_distance_and_similarity() {
+        assert_eq!(edit_distance("refactr", "refactor"), 1);
+        assert_eq!(edit_distance("reveiw", "review"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(similarity("refactor", "refactor"), 1.0);
+        assert_eq!(similarity("refac", "refactoring"), PREFIX_SIMILARITY);
+        assert!(similarity("refactr", "refactor") >= FUZZY_THRESHOLD);
+        assert!(similarity("debug", "design") < FUZZY_THRESHOLD);
+    }
+
+    #[test]
+    fn test_fuzzy_score_needs_every_group() {
+        let words: Vec<String> = ["code-review", "Code Review Assistant", "quality"]
+            .iter()
+            .flat_map(|text| index_words(text))
+            .collect();
+        assert!(words.contains(&"code-review".to_string()));
+
+        assert!(fuzzy_score(&terms(&[&["reveiw"]]), &words).is_some());
+        assert!(fuzzy_score(&terms(&[&["code-reveiw"]]), &words).is_some());
+        assert!(fuzzy_score(&terms(&[&["asistant"], &["qualty"]]), &words).is_some());
+        assert!(fuzzy_score(&terms(&[&["asistant"], &["banana"]]), &words).is_none());
+        assert!(fuzzy_score(&terms(&[&["banana", "reviw"]]), &words).is_some());
+        assert!(fuzzy_score(&[], &words).is_none());
+    }
+}
@@ -0,0 +1,72 @@
+//! Why a search hit matched
+//!
+//! `Database::search_highlights` asks FTS5 for a `snippet()` excerpt and a
+//! `highlight()` of each indexed column. Matched tokens come back wrapped in
+//! `MATCH_START` / `MATCH_END`, control characters that never occur in
+//! indexed text, so callers can render them as ANSI, markup or plain text.
+
+use crate::types::{SearchField, SearchMatch};
+
+/// Marks the start of a matched token in `SearchHighlight` text
+pub const MATCH_START: char = '\u{2}';
+
+/// Marks the end of a matched token in `SearchHighlight` text
+pub const MATCH_END: char = '\u{3}';
+
+/// FTS5 context for one search hit
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchHighlight {
+    /// Excerpt around a match in the content (else the description),
+    /// with matches marked
+    pub snippet: Option<String>,
+    /// Title with matches marked, when the title matched
+    pub title: Option<String>,
+    /// Matched terms by field
+    pub matches: Vec<SearchMatch>,
+}
+
+impl SearchHighlight {
+    /// Add the marked terms of `text` as matches in `field`, once each
+    pub(crate) fn add_marked(&mut self, field: SearchField, text: &str) {
+        for term in marked_terms(text) {
+            if !self.matches.iter().any(|m| m.field == field && m.term == term) {
+                self.matches.push(SearchMatch { field, term });
+            }
+        }
+    }
+}
+
+/// Lowercased text between each `MATCH_START` and `MATCH_END`
+pub fn marked_terms(text: &str) -> Vec<String> {
+    text.split(MATCH_START)
+        .skip(1)
+        .filter_map(|rest| rest.split_once(MATCH_END))
+        .map(|(term, _)| term.to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// Replace the markers in `text` with `open` and `close`
+pub fn render_marked(text: &str, open: &str, close: &str) -> String {
+    text.replace(MATCH_START, open).replace(MATCH_END, close)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marked_terms_and_rendering() {
+        let text = format!("Fix {MATCH_START}Rust{MATCH_END} and {MATCH_START}code{MATCH_END}.");
+        assert_eq!(marked_terms(&text), vec!["rust", "code"]);
+        assert_eq!(render_marked(&text, "[", "]"), "Fix [Rust] and [code].");
+        assert_eq!(render_marked(&text, "", ""), "Fix Rust and code.");
+        assert!(marked_terms("no matches").is_empty());
+
+        let mut highlight = SearchHighlight::default();
+        highlight.add_marked(SearchField::Title, &text);
+        highlight.add_marked(SearchField::Title, &text);
+        highlight.add_marked(SearchField::Tag, &text);
+        assert_eq!(highlight.matches.len(), 4);
+    }
+}
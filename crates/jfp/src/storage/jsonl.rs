@@ -4,19 +4,29 @@
 //! - Atomic JSONL write (temp + fsync + rename)
 //! - Version markers in both stores
 //! - One-way sync only
+//!
+//! Reached through `jfp backup` and `jfp restore`. Automatic backups (no
+//! path given) go to `backups/` in the cache dir and are rotated.
+//!
+//! Layout: a `_meta` header, one prompt per line, then local data as
+//! tagged records (`{"_star": {...}}`, `{"_note": {...}}`).
 
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::Database;
+use super::{ChangeSet, Database, Note, Star, SCHEMA_VERSION};
 use crate::types::Prompt;
 
+/// File name prefix of automatic backups
+const AUTO_BACKUP_PREFIX: &str = "jfp-backup-";
+
 /// JSONL metadata header (first line)
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JsonlMeta {
@@ -24,12 +34,32 @@ pub struct JsonlMeta {
     pub meta: MetaInfo,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetaInfo {
     pub version: String,
     pub count: usize,
     pub exported_at: String,
     pub schema_version: i32,
+    /// Number of `_star` records; absent in backups that predate stars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stars: Option<usize>,
+    /// Number of `_note` records; absent in backups that predate notes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<usize>,
+}
+
+/// A starred prompt record
+#[derive(Debug, Serialize, Deserialize)]
+struct StarRecord {
+    #[serde(rename = "_star")]
+    star: Star,
+}
+
+/// A prompt note record
+#[derive(Debug, Serialize, Deserialize)]
+struct NoteRecord {
+    #[serde(rename = "_note")]
+    note: Note,
 }
 
 /// Export prompts to JSONL file
@@ -44,8 +74,10 @@ pub fn export_jsonl(db: &Database, path: &Path) -> Result<usize> {
         fs::create_dir_all(parent)?;
     }
 
-    // Get all prompts
-    let prompts = db.list_prompts()?;
+    // Get all prompts, complete with variables, and local data
+    let prompts = db.export_prompts()?;
+    let stars = db.stars()?;
+    let notes = db.notes(None)?;
     let count = prompts.len();
 
     // Create temp file in same directory for atomic rename
@@ -62,7 +94,9 @@ pub fn export_jsonl(db: &Database, path: &Path) -> Result<usize> {
                 version: get_data_version(db),
                 count,
                 exported_at: Utc::now().to_rfc3339(),
-                schema_version: crate::storage::SCHEMA_VERSION,
+                schema_version: SCHEMA_VERSION,
+                stars: Some(stars.len()),
+                notes: Some(notes.len()),
             },
         };
         serde_json::to_writer(&mut writer, &meta)?;
@@ -74,6 +108,16 @@ pub fn export_jsonl(db: &Database, path: &Path) -> Result<usize> {
             writeln!(writer)?;
         }
 
+        for star in stars {
+            serde_json::to_writer(&mut writer, &StarRecord { star })?;
+            writeln!(writer)?;
+        }
+
+        for note in notes {
+            serde_json::to_writer(&mut writer, &NoteRecord { note })?;
+            writeln!(writer)?;
+        }
+
         // Flush and fsync
         writer.flush()?;
         writer.into_inner()?.sync_all()?;
@@ -98,21 +142,60 @@ pub fn export_jsonl(db: &Database, path: &Path) -> Result<usize> {
     Ok(count)
 }
 
-/// Import prompts from JSONL file
+/// How `import_jsonl` treats stored prompts missing from the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreMode {
+    /// Make the database match the file exactly
+    Replace,
+    /// Add and update prompts from the file, keep everything else
+    Merge,
+}
+
+impl RestoreMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "replace" => Some(RestoreMode::Replace),
+            "merge" => Some(RestoreMode::Merge),
+            _ => None,
+        }
+    }
+}
+
+/// Contents of a JSONL file and everything wrong with it
+#[derive(Debug, Default)]
+pub struct JsonlVerification {
+    pub meta: Option<MetaInfo>,
+    pub prompts: Vec<Prompt>,
+    pub stars: Vec<Star>,
+    pub notes: Vec<Note>,
+    /// Problems that make the file unsafe to restore
+    pub errors: Vec<String>,
+    /// Problems worth mentioning that do not block a restore
+    pub warnings: Vec<String>,
+}
+
+impl JsonlVerification {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Read and check every line of a JSONL file without touching the database
 ///
-/// Replaces all prompts in database with contents of JSONL file.
-/// Uses transaction for atomicity.
-pub fn import_jsonl(db: &mut Database, path: &Path) -> Result<usize> {
+/// Checks that each line is a prompt or a local-data record, that ids are
+/// unique and that the `_meta` counts match. Only I/O failures are returned as `Err`.
+pub fn verify_jsonl(path: &Path) -> Result<JsonlVerification> {
     let file =
         File::open(path).with_context(|| format!("Failed to open JSONL file: {:?}", path))?;
     let reader = BufReader::new(file);
 
-    let mut prompts = Vec::new();
-    let mut line_num = 0;
+    let mut verification = JsonlVerification::default();
+    let mut seen = HashSet::new();
     let mut saw_first_non_empty = false;
 
-    for line in reader.lines() {
-        line_num += 1;
+    for (index, line) in reader.lines().enumerate() {
+        let line_num = index + 1;
         let line = line.with_context(|| format!("Failed to read line {}", line_num))?;
 
         let trimmed = line.trim();
@@ -120,34 +203,190 @@ pub fn import_jsonl(db: &mut Database, path: &Path) -> Result<usize> {
             continue;
         }
 
+        let value = match serde_json::from_str::<Value>(trimmed) {
+            Ok(value) => value,
+            Err(e) => {
+                verification
+                    .errors
+                    .push(format!("line {}: invalid JSON: {}", line_num, e));
+                continue;
+            }
+        };
+
         // The first non-empty line may be metadata. Only treat it as metadata
         // when it is an object with a top-level "_meta" key.
         if !saw_first_non_empty {
             saw_first_non_empty = true;
-            let parsed_first_line = serde_json::from_str::<Value>(trimmed)
-                .with_context(|| format!("Failed to parse JSON at line {}", line_num))?;
-            if parsed_first_line.get("_meta").is_some() {
-                let _meta: JsonlMeta =
-                    serde_json::from_value(parsed_first_line).with_context(|| {
-                        format!("Failed to parse JSONL metadata at line {}", line_num)
-                    })?;
+            if value.get("_meta").is_some() {
+                match serde_json::from_value::<JsonlMeta>(value) {
+                    Ok(meta) => verification.meta = Some(meta.meta),
+                    Err(e) => verification
+                        .errors
+                        .push(format!("line {}: invalid metadata: {}", line_num, e)),
+                }
                 continue;
             }
         }
 
-        // Parse prompt
-        let prompt: Prompt = serde_json::from_str(trimmed)
-            .with_context(|| format!("Failed to parse prompt at line {}", line_num))?;
-        prompts.push(prompt);
+        if value.get("_star").is_some() {
+            match serde_json::from_value::<StarRecord>(value) {
+                Ok(record) => verification.stars.push(record.star),
+                Err(e) => verification
+                    .errors
+                    .push(format!("line {}: invalid star: {}", line_num, e)),
+            }
+            continue;
+        }
+
+        if value.get("_note").is_some() {
+            match serde_json::from_value::<NoteRecord>(value) {
+                Ok(record) => verification.notes.push(record.note),
+                Err(e) => verification
+                    .errors
+                    .push(format!("line {}: invalid note: {}", line_num, e)),
+            }
+            continue;
+        }
+
+        match serde_json::from_value::<Prompt>(value) {
+            Ok(prompt) if prompt.id.trim().is_empty() => {
+                verification
+                    .errors
+                    .push(format!("line {}: prompt has an empty id", line_num));
+            }
+            Ok(prompt) => {
+                if !seen.insert(prompt.id.clone()) {
+                    verification
+                        .errors
+                        .push(format!("line {}: duplicate prompt id '{}'", line_num, prompt.id));
+                }
+                verification.prompts.push(prompt);
+            }
+            Err(e) => verification
+                .errors
+                .push(format!("line {}: invalid prompt: {}", line_num, e)),
+        }
+    }
+
+    match &verification.meta {
+        Some(meta) => {
+            if meta.count != verification.prompts.len() {
+                verification.errors.push(format!(
+                    "metadata lists {} prompts but the file has {}",
+                    meta.count,
+                    verification.prompts.len()
+                ));
+            }
+            if let Some(stars) = meta.stars
+                && stars != verification.stars.len()
+            {
+                verification.errors.push(format!(
+                    "metadata lists {} stars but the file has {}",
+                    stars,
+                    verification.stars.len()
+                ));
+            }
+            if let Some(notes) = meta.notes
+                && notes != verification.notes.len()
+            {
+                verification.errors.push(format!(
+                    "metadata lists {} notes but the file has {}",
+                    notes,
+                    verification.notes.len()
+                ));
+            }
+            if meta.schema_version > SCHEMA_VERSION {
+                verification.warnings.push(format!(
+                    "written by a newer jfp (schema {}, this jfp uses {})",
+                    meta.schema_version, SCHEMA_VERSION
+                ));
+            }
+        }
+        None => verification
+            .warnings
+            .push("no _meta header; prompt count not checked".to_string()),
+    }
+
+    Ok(verification)
+}
+
+/// Import prompts from JSONL file
+///
+/// The whole file is verified first; nothing is written if any line is
+/// invalid. `Replace` makes the prompts (and stars and notes, if the file has them)
+/// match the file, `Merge` only adds and updates. Prompts, stars and notes
+/// are written in one transaction, so a failed import changes nothing.
+pub fn import_jsonl(db: &mut Database, path: &Path, mode: RestoreMode) -> Result<ChangeSet> {
+    let verification = verify_jsonl(path)?;
+    if !verification.is_valid() {
+        bail!(
+            "{} is not a valid backup: {}",
+            path.display(),
+            verification.errors.join("; ")
+        );
     }
 
-    // Bulk import with transaction
-    db.bulk_upsert_prompts(&prompts)?;
+    let preserve_sources: Vec<String> = match mode {
+        RestoreMode::Replace => Vec::new(),
+        RestoreMode::Merge => db.source_counts()?.into_keys().collect(),
+    };
+
+    // Backups from before stars existed leave the current stars alone
+    let has_stars = verification.meta.as_ref().is_some_and(|m| m.stars.is_some());
+    let stars = (has_stars || !verification.stars.is_empty()).then_some(&verification.stars[..]);
+    let has_notes = verification.meta.as_ref().is_some_and(|m| m.notes.is_some());
+    let notes = (has_notes || !verification.notes.is_empty()).then_some(&verification.notes[..]);
+
+    let changes = db.restore_backup(
+        &verification.prompts,
+        &preserve_sources,
+        stars,
+        notes,
+        mode == RestoreMode::Replace,
+    )?;
 
     // Update version marker
     update_data_version(db)?;
 
-    Ok(prompts.len())
+    Ok(changes)
+}
+
+/// Path for a new automatic backup in `dir`
+pub fn auto_backup_path(dir: &Path) -> PathBuf {
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    dir.join(format!("{}{}.jsonl", AUTO_BACKUP_PREFIX, timestamp))
+}
+
+/// Delete all but the newest `keep` automatic backups in `dir`
+///
+/// Only files named like `auto_backup_path` are touched. Returns the
+/// removed paths.
+pub fn rotate_backups(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(AUTO_BACKUP_PREFIX) && name.ends_with(".jsonl"))
+        })
+        .collect();
+
+    // Timestamped names sort oldest first
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+
+    let mut removed = Vec::with_capacity(excess);
+    for path in backups.into_iter().take(excess) {
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove old backup {}", path.display()))?;
+        removed.push(path);
+    }
+
+    Ok(removed)
 }
 
 /// Get current data version from DB
@@ -186,8 +425,8 @@ mod tests {
 
         // Create new DB and import
         let mut db2 = Database::in_memory()?;
-        let imported = import_jsonl(&mut db2, &jsonl_path)?;
-        assert_eq!(imported, 2);
+        let imported = import_jsonl(&mut db2, &jsonl_path, RestoreMode::Replace)?;
+        assert_eq!(imported.added(), 2);
 
         // Verify
         let loaded = db2.list_prompts()?;
@@ -233,8 +472,8 @@ mod tests {
         fs::write(&jsonl_path, format!("{}\n{}\n", first_line, second_line))?;
 
         let mut db = Database::in_memory()?;
-        let imported = import_jsonl(&mut db, &jsonl_path)?;
-        assert_eq!(imported, 2);
+        let imported = import_jsonl(&mut db, &jsonl_path, RestoreMode::Replace)?;
+        assert_eq!(imported.added(), 2);
 
         let loaded = db.list_prompts()?;
         assert!(loaded.iter().any(|p| p.id == "first"));
@@ -256,8 +495,140 @@ mod tests {
         assert_eq!(exported, 2);
 
         let mut imported_db = Database::in_memory()?;
-        let imported = import_jsonl(&mut imported_db, &jsonl_path)?;
-        assert_eq!(imported, 2);
+        let imported = import_jsonl(&mut imported_db, &jsonl_path, RestoreMode::Replace)?;
+        assert_eq!(imported.added(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_and_merge_restore() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+
+        let mut db = Database::in_memory()?;
+        let mut kept = Prompt::new("kept", "Kept", "original");
+        kept.tags = vec!["zeta".into(), "alpha".into()];
+        db.bulk_upsert_prompts(&[kept])?;
+        export_jsonl(&db, &jsonl_path)?;
+
+        // A backup of the current state restores without changes
+        let unchanged = import_jsonl(&mut db, &jsonl_path, RestoreMode::Merge)?;
+        assert!(unchanged.changes.is_empty(), "{:?}", unchanged.changes);
+
+        db.bulk_upsert_prompts(&[Prompt::new("extra", "Extra", "added later")])?;
+        let merged = import_jsonl(&mut db, &jsonl_path, RestoreMode::Merge)?;
+        assert_eq!(merged.removed(), 0);
+        assert!(db.get_prompt("extra")?.is_some());
+
+        let replaced = import_jsonl(&mut db, &jsonl_path, RestoreMode::Replace)?;
+        assert_eq!(replaced.removed(), 1);
+        assert!(db.get_prompt("extra")?.is_none());
+        assert!(db.get_prompt("kept")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stars_survive_reseed_and_roundtrip() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+
+        let mut db = Database::in_memory()?;
+        let prompt = Prompt::new("fav", "Favorite", "body");
+        db.bulk_upsert_prompts(std::slice::from_ref(&prompt))?;
+        db.star_prompt("fav", "2026-01-01T00:00:00+00:00")?;
+
+        // A refresh that drops and re-adds the prompt keeps the star
+        db.reconcile_prompts(&[], &[])?;
+        db.reconcile_prompts(std::slice::from_ref(&prompt), &[])?;
+        assert_eq!(db.list_prompts_filtered(None, None, false, true, None)?.len(), 1);
+
+        export_jsonl(&db, &jsonl_path)?;
+        let verification = verify_jsonl(&jsonl_path)?;
+        assert!(verification.is_valid(), "{:?}", verification.errors);
+        assert_eq!(verification.prompts.len(), 1);
+        assert_eq!(verification.meta.and_then(|m| m.stars), Some(1));
+
+        db.unstar_prompt("fav")?;
+        db.star_prompt("other", "2026-02-01T00:00:00+00:00")?;
+        import_jsonl(&mut db, &jsonl_path, RestoreMode::Merge)?;
+        assert_eq!(db.stars()?.len(), 2);
+
+        import_jsonl(&mut db, &jsonl_path, RestoreMode::Replace)?;
+        let stars = db.stars()?;
+        assert_eq!(stars.len(), 1);
+        assert_eq!(stars[0].id, "fav");
+        Ok(())
+    }
+
+    #[test]
+    fn test_notes_roundtrip() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+
+        let mut db = Database::in_memory()?;
+        db.upsert_prompt(&Prompt::new("fav", "Favorite", "body"))?;
+        db.add_note("fav", "Use with the staging diff", "2026-01-01T00:00:00+00:00")?;
+        export_jsonl(&db, &jsonl_path)?;
+
+        let verification = verify_jsonl(&jsonl_path)?;
+        assert!(verification.is_valid(), "{:?}", verification.errors);
+        assert_eq!(verification.meta.and_then(|m| m.notes), Some(1));
+
+        // Merge skips notes that are already there and keeps newer ones
+        db.add_note("fav", "Newer note", "2026-02-01T00:00:00+00:00")?;
+        import_jsonl(&mut db, &jsonl_path, RestoreMode::Merge)?;
+        assert_eq!(db.notes(Some("fav"))?.len(), 2);
+
+        import_jsonl(&mut db, &jsonl_path, RestoreMode::Replace)?;
+        let notes = db.notes(None)?;
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].body, "Use with the staging diff");
+        assert_eq!(db.search("staging", 10, None, false)?.len(), 1);
+        assert!(db.search("newer", 10, None, false)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_reports_bad_lines_and_count_mismatch() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+        let prompt = serde_json::to_string(&Prompt::new("a", "A", "alpha"))?;
+        fs::write(
+            &jsonl_path,
+            format!(
+                "{}\n{}\n{}\nnot json\n",
+                r#"{"_meta": {"version": "v", "count": 3, "exported_at": "now", "schema_version": 1}}"#,
+                prompt,
+                prompt
+            ),
+        )?;
+
+        let verification = verify_jsonl(&jsonl_path)?;
+        assert!(!verification.is_valid());
+        assert_eq!(verification.errors.len(), 3, "{:?}", verification.errors);
+        assert!(verification.errors[0].contains("line 3: duplicate prompt id 'a'"));
+        assert!(verification.errors[1].starts_with("line 4:"));
+        assert!(verification.errors[2].contains("lists 3 prompts"));
+
+        let mut db = Database::in_memory()?;
+        assert!(import_jsonl(&mut db, &jsonl_path, RestoreMode::Merge).is_err());
+        assert_eq!(db.prompt_count()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotate_keeps_newest_automatic_backups() -> Result<()> {
+        let dir = tempdir()?;
+        for stamp in ["20260101T000000", "20260102T000000", "20260103T000000"] {
+            fs::write(dir.path().join(format!("{}{}.jsonl", AUTO_BACKUP_PREFIX, stamp)), "")?;
+        }
+        fs::write(dir.path().join("jfp-v2-20250101T000000.db"), "")?;
+
+        let removed = rotate_backups(dir.path(), 2)?;
+
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].to_string_lossy().contains("20260101"));
+        assert_eq!(fs::read_dir(dir.path())?.count(), 3);
         Ok(())
     }
 }
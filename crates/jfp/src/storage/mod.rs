@@ -7,8 +7,17 @@
 //! - JSONL for backup/export
 
 mod database;
+mod embedding;
+mod fuzzy;
+mod highlight;
 mod jsonl;
+mod query;
 mod schema;
+mod synonyms;
 
 pub use database::*;
+pub use highlight::*;
+pub use jsonl::*;
+pub use query::*;
 pub use schema::*;
+pub use synonyms::*;
@@ -0,0 +1,496 @@
+//! Search query language
+//!
+//! `jfp search` queries are parsed here rather than handed to FTS5 as-is:
+//! - bare words and `"exact phrases"` must all match; `word*` is a prefix
+//! - `-word` / `-"phrase"` exclude prompts that contain them
+//! - `a OR b` matches either side (`tag:a OR tag:b` works too)
+//! - `tag:rust`, `category:testing`, `featured:true` filter on metadata and
+//!   may be negated (`-tag:draft`)
+//!
+//! Every term is quoted before it reaches FTS5, so punctuation such as
+//! `c++` or `code-review` is searched for rather than parsed as syntax.
+//! Unknown `field:` prefixes are ordinary text.
+//!
+//! `SearchQuery::expand` adds synonyms of single-word terms as a separate
+//! expression, which `Database::search_query` scores below literal hits.
+
+use thiserror::Error;
+
+use super::synonyms::Synonyms;
+
+/// A query the parser rejected, with the byte offset it points at
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message}")]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+/// Prompt metadata a query can filter on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterField {
+    Tag,
+    Category,
+    Featured,
+}
+
+impl FilterField {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "tag" => Some(Self::Tag),
+            "category" => Some(Self::Category),
+            "featured" => Some(Self::Featured),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tag => "tag",
+            Self::Category => "category",
+            Self::Featured => "featured",
+        }
+    }
+}
+
+/// `field:value`, matching any of `values` (case-insensitive)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryFilter {
+    pub field: FilterField,
+    pub values: Vec<String>,
+    pub negated: bool,
+}
+
+/// A parsed query, ready for `Database::search_query`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// FTS5 expression prompts must match; `None` when the query has no
+    /// positive terms (filters or exclusions only)
+    pub fts: Option<String>,
+    /// FTS5 expression for excluded terms
+    pub exclude: Option<String>,
+    pub filters: Vec<QueryFilter>,
+    /// Positive terms; each entry lists the alternatives of one OR group
+    pub terms: Vec<Vec<QueryTerm>>,
+    /// `fts` with synonyms added as alternatives, set by `expand`
+    pub expanded: Option<String>,
+    /// Synonyms `expand` added
+    pub expansions: Vec<String>,
+}
+
+/// A positive word or phrase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTerm {
+    /// Lowercase text, for typo-tolerant matching and synonyms
+    pub text: String,
+    /// Escaped FTS5 form
+    pub fts: String,
+}
+
+/// One parsed token
+#[derive(Debug)]
+enum Atom {
+    /// Escaped FTS5 term or phrase, with its lowercase text
+    Text { fts: String, text: String, negated: bool },
+    Filter(QueryFilter),
+    /// A term with nothing FTS5 would index, e.g. `++`
+    Empty,
+}
+
+impl SearchQuery {
+    /// Parse the search query language
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        // Groups of atoms joined by OR; groups are ANDed together
+        let mut groups: Vec<Vec<(usize, Atom)>> = Vec::new();
+        let mut pending_or: Option<usize> = None;
+
+        let mut rest = input;
+        loop {
+            let trimmed = rest.trim_start();
+            if trimmed.is_empty() {
+                break;
+            }
+            let start = input.len() - trimmed.len();
+
+            if let Some(after) = trimmed.strip_prefix("OR")
+                && after.chars().next().is_none_or(char::is_whitespace)
+            {
+                if pending_or.is_some() || groups.is_empty() {
+                    return Err(QueryError::new("OR needs a term on each side", start));
+                }
+                pending_or = Some(start);
+                rest = after;
+                continue;
+            }
+
+            let (atom, consumed) = read_atom(trimmed, start)?;
+            rest = &trimmed[consumed..];
+
+            match pending_or.take() {
+                Some(_) => groups.last_mut().expect("OR follows a group").push((start, atom)),
+                None => groups.push(vec![(start, atom)]),
+            }
+        }
+        if let Some(position) = pending_or {
+            return Err(QueryError::new("OR needs a term on each side", position));
+        }
+
+        let mut query = SearchQuery::default();
+        let mut terms = Vec::new();
+        let mut excluded = Vec::new();
+
+        for group in groups {
+            if group.len() == 1 {
+                match group.into_iter().next().map(|(_, atom)| atom) {
+                    Some(Atom::Text { fts, text, negated: false }) => {
+                        terms.push(fts.clone());
+                        query.terms.push(vec![QueryTerm { text, fts }]);
+                    }
+                    Some(Atom::Text { fts, negated: true, .. }) => excluded.push(fts),
+                    Some(Atom::Filter(filter)) => query.filters.push(filter),
+                    _ => {}
+                }
+                continue;
+            }
+
+            let mut alternatives = Vec::new();
+            let mut texts = Vec::new();
+            let mut filter: Option<QueryFilter> = None;
+            for (position, atom) in group {
+                match atom {
+                    Atom::Text { negated: true, .. } | Atom::Filter(QueryFilter { negated: true, .. }) => {
+                        return Err(QueryError::new("OR cannot join an excluded term", position));
+                    }
+                    Atom::Text { fts, text, .. } if filter.is_none() => {
+                        alternatives.push(fts.clone());
+                        texts.push(QueryTerm { text, fts });
+                    }
+                    Atom::Filter(next) if alternatives.is_empty() => match &mut filter {
+                        None => filter = Some(next),
+                        Some(existing) if existing.field == next.field => {
+                            existing.values.extend(next.values)
+                        }
+                        Some(existing) => {
+                            return Err(QueryError::new(
+                                format!(
+                                    "OR can only join filters on the same field ({}: and {}:)",
+                                    existing.field.as_str(),
+                                    next.field.as_str()
+                                ),
+                                position,
+                            ));
+                        }
+                    },
+                    Atom::Empty => {}
+                    _ => {
+                        return Err(QueryError::new(
+                            "OR cannot join a search term and a filter",
+                            position,
+                        ));
+                    }
+                }
+            }
+            if !texts.is_empty() {
+                query.terms.push(texts);
+            }
+            match (filter, alternatives.len()) {
+                (Some(filter), _) => query.filters.push(filter),
+                (None, 0) => {}
+                (None, 1) => terms.extend(alternatives),
+                (None, _) => terms.push(format!("({})", alternatives.join(" OR "))),
+            }
+        }
+
+        if terms.is_empty() && excluded.is_empty() && query.filters.is_empty() {
+            return Err(QueryError::new("query has nothing to search for", 0));
+        }
+        query.fts = Some(terms.join(" AND ")).filter(|fts| !fts.is_empty());
+        query.exclude = Some(excluded.join(" OR ")).filter(|fts| !fts.is_empty());
+        Ok(query)
+    }
+
+    /// Every word of `text` as a literal term, with no operators
+    ///
+    /// For free text such as `jfp suggest` task descriptions.
+    pub fn words(text: &str) -> Self {
+        let words: Vec<&str> = text
+            .split_whitespace()
+            .filter(|word| has_index_chars(word))
+            .collect();
+        let terms: Vec<String> = words.iter().map(|word| quote(word, false)).collect();
+        Self {
+            fts: Some(terms.join(" AND ")).filter(|fts| !fts.is_empty()),
+            terms: words
+                .iter()
+                .zip(&terms)
+                .map(|(word, fts)| {
+                    vec![QueryTerm {
+                        text: word.to_lowercase(),
+                        fts: fts.clone(),
+                    }]
+                })
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Add synonyms of single-word terms as alternatives in `expanded`
+    ///
+    /// Prefix terms and phrases are left alone. `expanded` stays `None`
+    /// when no term has synonyms.
+    pub fn expand(&mut self, synonyms: &Synonyms) {
+        let mut groups = Vec::with_capacity(self.terms.len());
+        let mut expansions: Vec<String> = Vec::new();
+        for group in &self.terms {
+            let mut alternatives: Vec<String> = group.iter().map(|term| term.fts.clone()).collect();
+            for term in group {
+                if term.text.contains(char::is_whitespace) || term.fts.ends_with('*') {
+                    continue;
+                }
+                for synonym in synonyms.lookup(&term.text) {
+                    let in_query = self.terms.iter().flatten().any(|t| t.text == synonym);
+                    if !in_query && !expansions.contains(&synonym) && has_index_chars(&synonym) {
+                        alternatives.push(quote(&synonym, false));
+                        expansions.push(synonym);
+                    }
+                }
+            }
+            groups.push(match alternatives.len() {
+                1 => alternatives.remove(0),
+                _ => format!("({})", alternatives.join(" OR ")),
+            });
+        }
+        if !expansions.is_empty() {
+            self.expanded = Some(groups.join(" AND "));
+            self.expansions = expansions;
+        }
+    }
+
+    /// Whether the query can match anything at all
+    pub fn is_empty(&self) -> bool {
+        self.fts.is_none() && self.exclude.is_none() && self.filters.is_empty()
+    }
+}
+
+/// Read one token from the start of `text`; returns it and the bytes used
+fn read_atom(text: &str, start: usize) -> Result<(Atom, usize), QueryError> {
+    let (negated, body, offset) = match text.strip_prefix('-') {
+        Some(body) if body.chars().next().is_some_and(|c| !c.is_whitespace()) => (true, body, 1),
+        _ => (false, text, 0),
+    };
+
+    if body.starts_with('"') {
+        let (phrase, used) = read_quoted(body, start + offset)?;
+        let atom = if has_index_chars(phrase) {
+            Atom::Text {
+                fts: quote(phrase, false),
+                text: phrase.to_lowercase(),
+                negated,
+            }
+        } else {
+            Atom::Empty
+        };
+        return Ok((atom, offset + used));
+    }
+
+    if let Some((name, value)) = body.split_once(':')
+        && !name.contains(char::is_whitespace)
+        && let Some(field) = FilterField::parse(name)
+    {
+        let value_start = start + offset + name.len() + 1;
+        let (value, used) = if value.starts_with('"') {
+            read_quoted(value, value_start)?
+        } else {
+            let end = value.find(char::is_whitespace).unwrap_or(value.len());
+            (&value[..end], end)
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(QueryError::new(format!("{}: needs a value", field.as_str()), value_start));
+        }
+
+        let mut filter = QueryFilter {
+            field,
+            values: vec![value.to_lowercase()],
+            negated,
+        };
+        if field == FilterField::Featured {
+            let featured = match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => true,
+                "false" | "no" | "0" => false,
+                _ => {
+                    return Err(QueryError::new(
+                        format!("featured: expects true or false, not '{}'", value),
+                        value_start,
+                    ));
+                }
+            };
+            filter.values = vec![featured.to_string()];
+        }
+        return Ok((Atom::Filter(filter), offset + name.len() + 1 + used));
+    }
+
+    let end = body.find(char::is_whitespace).unwrap_or(body.len());
+    let word = &body[..end];
+    let prefix = word.ends_with('*');
+    let word = word.trim_end_matches('*');
+    let atom = if has_index_chars(word) {
+        Atom::Text {
+            fts: quote(word, prefix),
+            text: word.to_lowercase(),
+            negated,
+        }
+    } else {
+        Atom::Empty
+    };
+    Ok((atom, offset + end))
+}
+
+/// Read a `"..."` string; returns its contents and the bytes used
+fn read_quoted(text: &str, start: usize) -> Result<(&str, usize), QueryError> {
+    match text[1..].find('"') {
+        Some(end) => Ok((&text[1..end + 1], end + 2)),
+        None => Err(QueryError::new("unterminated quote", start)),
+    }
+}
+
+/// Whether FTS5's tokenizer would find a token in `text`
+fn has_index_chars(text: &str) -> bool {
+    text.chars().any(|c| c.is_alphanumeric() || !c.is_ascii())
+}
+
+/// `text` as an FTS5 string, so it is matched literally
+fn quote(text: &str, prefix: bool) -> String {
+    let quoted = format!("\"{}\"", text.replace('"', "\"\""));
+    if prefix { quoted + "*" } else { quoted }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(field: FilterField, values: &[&str], negated: bool) -> QueryFilter {
+        QueryFilter {
+            field,
+            values: values.iter().map(|v| v.to_string()).collect(),
+            negated,
+        }
+    }
+
+    #[test]
+    fn test_parses_terms_phrases_and_operators() {
+        let query = SearchQuery::parse(r#"review "pull request" -draft brain* c++ OR rust"#).unwrap();
+        assert_eq!(
+            query.fts.as_deref(),
+            Some(r#""review" AND "pull request" AND "brain"* AND ("c++" OR "rust")"#)
+        );
+        assert_eq!(query.exclude.as_deref(), Some(r#""draft""#));
+        assert!(query.filters.is_empty());
+        let texts: Vec<Vec<&str>> = query
+            .terms
+            .iter()
+            .map(|group| group.iter().map(|t| t.text.as_str()).collect())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![vec!["review"], vec!["pull request"], vec!["brain"], vec!["c++", "rust"]]
+        );
+
+        // Quotes inside a word are escaped, not parsed
+        let query = SearchQuery::parse(r#"say"hi code-review std::vector"#).unwrap();
+        assert_eq!(
+            query.fts.as_deref(),
+            Some(r#""say""hi" AND "code-review" AND "std::vector""#)
+        );
+    }
+
+    #[test]
+    fn test_parses_filters() {
+        let query = SearchQuery::parse(
+            r#"tag:Rust OR tag:go category:"code review" -featured:yes -tag:draft"#,
+        )
+        .unwrap();
+        assert_eq!(query.fts, None);
+        assert_eq!(
+            query.filters,
+            vec![
+                filter(FilterField::Tag, &["rust", "go"], false),
+                filter(FilterField::Category, &["code review"], false),
+                filter(FilterField::Featured, &["true"], true),
+                filter(FilterField::Tag, &["draft"], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_parse_errors_with_positions() {
+        let cases = [
+            (r#"review "unclosed"#, "unterminated quote", 7),
+            ("OR review", "OR needs a term on each side", 0),
+            ("review OR", "OR needs a term on each side", 7),
+            ("a OR OR b", "OR needs a term on each side", 5),
+            ("a OR -b", "OR cannot join an excluded term", 5),
+            ("a OR tag:x", "OR cannot join a search term and a filter", 5),
+            ("tag:x OR category:y", "OR can only join filters on the same field (tag: and category:)", 9),
+            ("tag: review", "tag: needs a value", 4),
+            ("featured:maybe", "featured: expects true or false, not 'maybe'", 9),
+            ("++ --", "query has nothing to search for", 0),
+        ];
+        for (input, message, position) in cases {
+            let err = SearchQuery::parse(input).unwrap_err();
+            assert_eq!((err.message.as_str(), err.position), (message, position), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_expand_adds_synonym_alternatives() {
+        let synonyms = Synonyms::builtin();
+
+        let mut query = SearchQuery::parse(r#"perf tuning OR docs "fix this" api*"#).unwrap();
+        query.expand(&synonyms);
+        assert_eq!(
+            query.expanded.as_deref(),
+            Some(concat!(
+                r#"("perf" OR "performance" OR "speed" OR "optimize" OR "fast") AND "#,
+                r#"("tuning" OR "docs" OR "documentation" OR "readme" OR "doc" OR "guide") AND "#,
+                r#""fix this" AND "api"*"#
+            ))
+        );
+        assert_eq!(query.expansions.len(), 8);
+        // The literal expression is untouched
+        assert_eq!(
+            query.fts.as_deref(),
+            Some(r#""perf" AND ("tuning" OR "docs") AND "fix this" AND "api"*"#)
+        );
+
+        // Synonyms already in the query are not added again
+        let mut query = SearchQuery::parse("fix debug").unwrap();
+        query.expand(&synonyms);
+        assert!(!query.expansions.contains(&"debug".to_string()));
+        assert!(!query.expansions.contains(&"fix".to_string()));
+
+        let mut query = SearchQuery::parse("kubernetes").unwrap();
+        query.expand(&synonyms);
+        assert_eq!(query.expanded, None);
+        assert!(query.expansions.is_empty());
+    }
+
+    #[test]
+    fn test_words_are_literal() {
+        let query = SearchQuery::words("fix the c++ build - OR tag:x");
+        assert_eq!(
+            query.fts.as_deref(),
+            Some(r#""fix" AND "the" AND "c++" AND "build" AND "OR" AND "tag:x""#)
+        );
+        assert!(SearchQuery::words("-- ++").is_empty());
+    }
+}
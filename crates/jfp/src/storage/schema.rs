@@ -1,10 +1,227 @@
 //! Database schema and migrations
+//!
+//! The schema is built by replaying `MIGRATIONS`. To change it, append a
+//! migration with the next version and bump `SCHEMA_VERSION`; a database
+//! opened by an older binary is backed up and migrated forward, and one
+//! written by a newer binary is refused.
 
-/// Current schema version
-pub const SCHEMA_VERSION: i32 = 2;
+/// Current schema version (the last entry in `MIGRATIONS`)
+pub const SCHEMA_VERSION: i32 = 10;
 
-/// SQL to create the database schema
-pub const CREATE_SCHEMA: &str = r#"
+/// One step of a migration
+pub enum MigrationStep {
+    /// SQL batch run as-is
+    Sql(&'static str),
+    /// `ALTER TABLE ... ADD COLUMN`, skipped if the table is missing or
+    /// already has the column
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+/// A numbered schema upgrade
+///
+/// `Database::open` applies every migration newer than the stored
+/// `schema_version`, in order, inside one transaction.
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub steps: &'static [MigrationStep],
+}
+
+/// Every schema change, oldest first. Append only: never edit a migration
+/// that has shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        steps: &[MigrationStep::Sql(INITIAL_SCHEMA)],
+    },
+    Migration {
+        version: 2,
+        description: "saved and local prompts",
+        steps: &[
+            MigrationStep::AddColumn {
+                table: "prompts",
+                column: "saved_at",
+                definition: "TEXT",
+            },
+            MigrationStep::AddColumn {
+                table: "prompts",
+                column: "is_local",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "change log for jfp whatsnew",
+        steps: &[MigrationStep::Sql(
+            r#"
+-- Change log written on registry sync (read by `jfp whatsnew`)
+-- No foreign key: removed prompts are logged too
+CREATE TABLE IF NOT EXISTS prompt_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    changed_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_prompt_changes_changed_at ON prompt_changes(changed_at);
+"#,
+        )],
+    },
+    Migration {
+        version: 4,
+        description: "registry source column",
+        steps: &[
+            // Registry name, or 'local'
+            MigrationStep::AddColumn {
+                table: "prompts",
+                column: "source",
+                definition: "TEXT NOT NULL DEFAULT 'default'",
+            },
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_prompts_source ON prompts(source);"),
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "prompt revision history",
+        steps: &[MigrationStep::Sql(
+            r#"
+-- Every distinct content/metadata version of a prompt (read by `jfp history`)
+-- No foreign key: history outlives prompts removed upstream
+CREATE TABLE IF NOT EXISTS prompt_revisions (
+    prompt_id TEXT NOT NULL,
+    revision INTEGER NOT NULL, -- 1, 2, ... per prompt
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    description TEXT,
+    category TEXT,
+    tags_text TEXT,
+    featured INTEGER NOT NULL DEFAULT 0,
+    version TEXT,
+    author TEXT,
+    is_local INTEGER NOT NULL DEFAULT 0,
+    source TEXT NOT NULL DEFAULT 'default',
+    registry_version TEXT, -- registry payload version the revision came from
+    recorded_at TEXT NOT NULL,
+    PRIMARY KEY (prompt_id, revision)
+);
+
+-- Existing prompts start with their current text as revision 1
+INSERT OR IGNORE INTO prompt_revisions
+    (prompt_id, revision, title, content, description, category, tags_text,
+     featured, version, author, is_local, source, recorded_at)
+SELECT id, 1, title, content, description, category, tags_text,
+       featured, version, author, is_local, source,
+       strftime('%Y-%m-%dT%H:%M:%SZ', updated_at)
+FROM prompts;
+"#,
+        )],
+    },
+    Migration {
+        version: 6,
+        description: "local usage events",
+        steps: &[MigrationStep::Sql(
+            r#"
+-- Local usage events (read by `jfp stats` and frecency ranking); never uploaded
+CREATE TABLE IF NOT EXISTS prompt_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id TEXT NOT NULL,
+    action TEXT NOT NULL, -- copy, render, export, show
+    used_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_prompt_usage_prompt ON prompt_usage(prompt_id);
+CREATE INDEX IF NOT EXISTS idx_prompt_usage_used_at ON prompt_usage(used_at);
+"#,
+        )],
+    },
+    Migration {
+        version: 7,
+        description: "starred prompts",
+        steps: &[MigrationStep::Sql(
+            r#"
+-- Local favorites; no foreign key so stars survive refreshes that drop
+-- and re-add a prompt
+CREATE TABLE IF NOT EXISTS prompt_stars (
+    prompt_id TEXT PRIMARY KEY,
+    starred_at TEXT NOT NULL
+);
+"#,
+        )],
+    },
+    Migration {
+        version: 8,
+        description: "personal notes",
+        steps: &[MigrationStep::Sql(
+            r#"
+-- Private notes on prompts (`jfp note`); no foreign key, like stars
+CREATE TABLE IF NOT EXISTS prompt_notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_prompt_notes_prompt ON prompt_notes(prompt_id);
+
+-- FTS5 over note bodies, maintained alongside prompt_notes
+CREATE VIRTUAL TABLE IF NOT EXISTS prompt_notes_fts USING fts5(
+    body,
+    note_id UNINDEXED,
+    prompt_id UNINDEXED
+);
+"#,
+        )],
+    },
+    Migration {
+        version: 9,
+        description: "local collections",
+        steps: &[MigrationStep::Sql(
+            r#"
+-- Named, ordered sets of prompts (`jfp collection`)
+CREATE TABLE IF NOT EXISTS collections (
+    name TEXT PRIMARY KEY,
+    description TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Members keep their prompt id without a foreign key, like stars
+CREATE TABLE IF NOT EXISTS collection_prompts (
+    collection TEXT NOT NULL,
+    prompt_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    added_at TEXT NOT NULL,
+    PRIMARY KEY (collection, prompt_id),
+    FOREIGN KEY (collection) REFERENCES collections(name) ON DELETE CASCADE
+);
+"#,
+        )],
+    },
+    Migration {
+        version: 10,
+        description: "prompt embeddings",
+        steps: &[MigrationStep::Sql(
+            r#"
+-- Hash embeddings for `jfp suggest --semantic`, written with each prompt;
+-- rows from an older `model` (or missing rows) are recomputed on use
+CREATE TABLE IF NOT EXISTS prompt_embeddings (
+    prompt_id TEXT PRIMARY KEY,
+    model TEXT NOT NULL,
+    vector BLOB NOT NULL,
+    FOREIGN KEY (prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+);
+"#,
+        )],
+    },
+];
+
+/// Schema version 1
+const INITIAL_SCHEMA: &str = r#"
 -- Prompts table (with denormalized tags_text for FTS)
 CREATE TABLE IF NOT EXISTS prompts (
     id TEXT PRIMARY KEY,
@@ -16,8 +233,6 @@ CREATE TABLE IF NOT EXISTS prompts (
     featured INTEGER NOT NULL DEFAULT 0,
     version TEXT,
     author TEXT,
-    saved_at TEXT,
-    is_local INTEGER NOT NULL DEFAULT 0,
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
@@ -85,9 +300,13 @@ CREATE INDEX IF NOT EXISTS idx_prompts_featured ON prompts(featured) WHERE featu
 CREATE INDEX IF NOT EXISTS idx_prompt_tags_tag ON prompt_tags(tag);
 "#;
 
-/// SQL to drop all tables (for reset)
-#[allow(dead_code)]
-pub const DROP_SCHEMA: &str = r#"
+/// SQL to drop the tables rebuilt from the registry (for reset)
+///
+/// Local data (stars, notes, collections, usage, revisions and the change
+/// log) is kept; replaying `MIGRATIONS` recreates the dropped tables. This
+/// replaces the drop-everything `DROP_SCHEMA` (see RECOVERY_RUNBOOK.md).
+pub const DROP_REGISTRY_TABLES: &str = r#"
+DROP TABLE IF EXISTS prompt_embeddings;
 DROP TABLE IF EXISTS bundle_prompts;
 DROP TABLE IF EXISTS bundles;
 DROP TABLE IF EXISTS prompt_variables;
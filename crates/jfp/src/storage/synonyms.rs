@@ -0,0 +1,119 @@
+//! Synonym expansion for search
+//!
+//! The built-in table matches `packages/core/src/search/synonyms.ts`; the
+//! config's `[search.synonyms]` adds to it. A word expands to its synonyms
+//! and to the words that list it as a synonym (`perf` → `performance`,
+//! `performance` → `perf`).
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Built-in synonyms, as in the TypeScript core
+const BUILTIN_SYNONYMS: &[(&str, &[&str])] = &[
+    // Common abbreviations and alternatives
+    ("fix", &["repair", "resolve", "debug", "patch", "correct"]),
+    ("docs", &["documentation", "readme", "doc", "guide"]),
+    ("perf", &["performance", "speed", "optimize", "fast"]),
+    ("cli", &["command-line", "terminal", "shell", "console"]),
+    ("api", &["interface", "endpoint", "service"]),
+    // Concept synonyms
+    ("brainstorm", &["ideate", "generate", "create", "think"]),
+    ("improve", &["enhance", "optimize", "upgrade", "better"]),
+    ("refactor", &["restructure", "clean", "reorganize", "rewrite"]),
+    ("test", &["testing", "spec", "unit", "integration"]),
+    ("debug", &["troubleshoot", "diagnose", "fix", "investigate"]),
+    // Action synonyms
+    ("add", &["create", "insert", "include", "implement"]),
+    ("remove", &["delete", "drop", "eliminate", "clear"]),
+    ("update", &["modify", "change", "edit", "revise"]),
+    // Domain terms
+    ("agent", &["bot", "assistant", "ai", "llm"]),
+    ("prompt", &["instruction", "query", "request"]),
+    ("code", &["programming", "software", "implementation"]),
+    // Technology specific
+    ("nodejs", &["node", "js"]),
+    ("reactjs", &["react"]),
+];
+
+/// Lowercase synonym table
+#[derive(Debug, Clone, Default)]
+pub struct Synonyms {
+    map: HashMap<String, Vec<String>>,
+}
+
+impl Synonyms {
+    /// The built-in table
+    pub fn builtin() -> Self {
+        let mut synonyms = Self::default();
+        for (word, list) in BUILTIN_SYNONYMS {
+            synonyms.add(word, list.iter().copied());
+        }
+        synonyms
+    }
+
+    /// The built-in table plus the config's `[search.synonyms]`
+    pub fn with_extra(extra: &BTreeMap<String, Vec<String>>) -> Self {
+        let mut synonyms = Self::builtin();
+        for (word, list) in extra {
+            synonyms.add(word, list.iter().map(String::as_str));
+        }
+        synonyms
+    }
+
+    fn add<'a>(&mut self, word: &str, list: impl Iterator<Item = &'a str>) {
+        let word = word.trim().to_lowercase();
+        if word.is_empty() {
+            return;
+        }
+        let entry = self.map.entry(word.clone()).or_default();
+        for synonym in list {
+            let synonym = synonym.trim().to_lowercase();
+            if !synonym.is_empty() && synonym != word && !entry.contains(&synonym) {
+                entry.push(synonym);
+            }
+        }
+    }
+
+    /// Synonyms of `word` in both directions, without `word` itself
+    pub fn lookup(&self, word: &str) -> Vec<String> {
+        let word = word.to_lowercase();
+        let mut found: Vec<String> = self.map.get(&word).cloned().unwrap_or_default();
+        let mut reverse: Vec<&String> = self
+            .map
+            .iter()
+            .filter(|(_, list)| list.contains(&word))
+            .map(|(key, _)| key)
+            .collect();
+        // HashMap order is random; keep expansions stable
+        reverse.sort();
+        for key in reverse {
+            if !found.contains(key) {
+                found.push(key.clone());
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_is_bidirectional_and_extensible() {
+        let builtin = Synonyms::builtin();
+        assert_eq!(builtin.lookup("PERF"), vec!["performance", "speed", "optimize", "fast"]);
+        assert_eq!(builtin.lookup("optimize"), vec!["improve", "perf"]);
+        // `debug` lists `fix` and `fix` lists `debug`: no duplicates
+        assert_eq!(builtin.lookup("debug").iter().filter(|s| *s == "fix").count(), 1);
+        assert!(builtin.lookup("kubernetes").is_empty());
+
+        let extra = BTreeMap::from([(
+            "K8s".to_string(),
+            vec!["Kubernetes".to_string(), "k8s".to_string(), " ".to_string()],
+        )]);
+        let synonyms = Synonyms::with_extra(&extra);
+        assert_eq!(synonyms.lookup("k8s"), vec!["kubernetes"]);
+        assert_eq!(synonyms.lookup("kubernetes"), vec!["k8s"]);
+        assert_eq!(synonyms.lookup("perf"), builtin.lookup("perf"));
+    }
+}
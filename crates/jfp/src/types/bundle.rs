@@ -20,8 +20,9 @@ pub struct Bundle {
     pub featured: bool,
     #[serde(default)]
     pub author: Option<String>,
-    /// List of prompt IDs in this bundle
-    #[serde(default)]
+    /// List of prompt IDs in this bundle, in order (`promptIds` in the
+    /// registry payload)
+    #[serde(default, alias = "promptIds")]
     pub prompts: Vec<String>,
 }
 
@@ -3,35 +3,29 @@
 //! From EXISTING_JFP_STRUCTURE.md section 3 (Config System)
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub registry: RegistryConfig,
+    /// Additional named registries (`[[registries]]`), merged over the default
+    pub registries: Vec<NamedRegistryConfig>,
     pub updates: UpdatesConfig,
     pub skills: SkillsConfig,
     pub output: OutputConfig,
     #[serde(rename = "localPrompts")]
     pub local_prompts: LocalPromptsConfig,
     pub analytics: AnalyticsConfig,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            registry: RegistryConfig::default(),
-            updates: UpdatesConfig::default(),
-            skills: SkillsConfig::default(),
-            output: OutputConfig::default(),
-            local_prompts: LocalPromptsConfig::default(),
-            analytics: AnalyticsConfig::default(),
-        }
-    }
+    pub network: NetworkConfig,
+    pub search: SearchConfig,
 }
 
 /// Registry configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RegistryConfig {
     pub url: String,
     pub remote: String,
@@ -47,6 +41,14 @@ pub struct RegistryConfig {
     pub cache_ttl: u64,
     #[serde(rename = "timeoutMs")]
     pub timeout_ms: u64,
+    /// Accept registry updates when the manifest cannot be fetched, marked
+    /// unverified, instead of keeping the cached data
+    #[serde(rename = "allowUnverified")]
+    pub allow_unverified: bool,
+    /// Priority of the default registry against named registries
+    pub priority: i64,
+    /// Extra headers sent with registry and manifest requests
+    pub headers: BTreeMap<String, String>,
 }
 
 impl Default for RegistryConfig {
@@ -61,12 +63,39 @@ impl Default for RegistryConfig {
             auto_refresh: true,
             cache_ttl: 3600,
             timeout_ms: 2000,
+            allow_unverified: false,
+            priority: 0,
+            headers: BTreeMap::new(),
         }
     }
 }
 
+/// An additional registry, cached separately from the default one
+///
+/// When two registries ship the same prompt id, the higher-priority registry
+/// keeps the bare id and the other copy is namespaced as `<name>/<id>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedRegistryConfig {
+    pub name: String,
+    pub url: String,
+    #[serde(rename = "manifestUrl", default)]
+    pub manifest_url: Option<String>,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Extra headers sent with this registry's requests (e.g. an auth token)
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Update checking configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UpdatesConfig {
     #[serde(rename = "autoCheck")]
     pub auto_check: bool,
@@ -93,6 +122,7 @@ impl Default for UpdatesConfig {
 
 /// Skills installation configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SkillsConfig {
     #[serde(rename = "personalDir")]
     pub personal_dir: PathBuf,
@@ -115,6 +145,7 @@ impl Default for SkillsConfig {
 
 /// Output formatting configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OutputConfig {
     pub color: bool,
     pub json: bool,
@@ -131,6 +162,7 @@ impl Default for OutputConfig {
 
 /// Local prompts configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LocalPromptsConfig {
     pub enabled: bool,
     pub dir: PathBuf,
@@ -147,13 +179,74 @@ impl Default for LocalPromptsConfig {
 }
 
 /// Analytics configuration
+///
+/// Usage events are stored in the local database only; nothing is sent.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnalyticsConfig {
+    /// Record copy/render/export/show events for `jfp stats`
     pub enabled: bool,
+    /// Boost recently and frequently used prompts in search ranking
+    pub frecency: bool,
 }
 
 impl Default for AnalyticsConfig {
     fn default() -> Self {
-        Self { enabled: false }
+        Self {
+            enabled: false,
+            frecency: true,
+        }
+    }
+}
+
+/// Search configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Expand query words with synonyms (`--no-expand` turns it off once)
+    pub expand: bool,
+    /// Extra synonyms, added to the built-in ones: `k8s = ["kubernetes"]`
+    pub synonyms: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            expand: true,
+            synonyms: BTreeMap::new(),
+        }
+    }
+}
+
+/// Network configuration shared by every HTTP request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy for all requests; unset uses `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that bypass the proxy; unset uses `NO_PROXY`
+    #[serde(rename = "noProxy")]
+    pub no_proxy: Option<String>,
+    /// PEM bundle trusted in addition to the built-in roots
+    #[serde(rename = "caBundle")]
+    pub ca_bundle: Option<PathBuf>,
+    /// Retries after a connection error, timeout, 429 or 5xx
+    pub retries: u32,
+    #[serde(rename = "retryBaseMs")]
+    pub retry_base_ms: u64,
+    #[serde(rename = "retryMaxMs")]
+    pub retry_max_ms: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: None,
+            ca_bundle: None,
+            retries: 2,
+            retry_base_ms: 250,
+            retry_max_ms: 4000,
+        }
     }
 }
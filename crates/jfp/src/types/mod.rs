@@ -3,8 +3,14 @@
 //! These types are derived from EXISTING_JFP_STRUCTURE.md spec.
 //! Do not modify without updating the spec document.
 
+mod bundle;
+mod config;
 mod prompt;
 mod registry;
+mod search;
 
+pub use bundle::*;
+pub use config::*;
 pub use prompt::*;
 pub use registry::*;
+pub use search::*;
@@ -57,6 +57,9 @@ pub struct Prompt {
     /// Local prompt indicator
     #[serde(default)]
     pub is_local: bool,
+    /// Name of the registry the prompt came from (`local` for local prompts)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 impl Prompt {
@@ -75,19 +78,10 @@ impl Prompt {
             author: None,
             saved_at: None,
             is_local: false,
+            source: None,
         }
     }
 
-    /// Get content preview (first N lines)
-    #[allow(dead_code)]
-    pub fn preview(&self, lines: usize) -> String {
-        self.content
-            .lines()
-            .take(lines)
-            .collect::<Vec<_>>()
-            .join("\n")
-    }
-
     /// Check if prompt matches a category filter
     pub fn matches_category(&self, category: &str) -> bool {
         self.category
@@ -99,6 +93,53 @@ impl Prompt {
     pub fn has_tag(&self, tag: &str) -> bool {
         self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
     }
+
+    /// Validate the fields a user-authored prompt must get right
+    ///
+    /// Mirrors the core of PromptSchema: kebab-case id and tags, a title of
+    /// 1-100 characters, non-empty content, and UPPER_SNAKE variable names.
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_kebab_case(&self.id) {
+            return Err("id must be lowercase kebab-case".to_string());
+        }
+
+        let title_len = self.title.trim().chars().count();
+        if title_len == 0 || title_len > 100 {
+            return Err("title must be 1-100 characters".to_string());
+        }
+
+        if self.content.trim().is_empty() {
+            return Err("content must not be empty".to_string());
+        }
+
+        if let Some(tag) = self.tags.iter().find(|tag| !is_kebab_case(tag)) {
+            return Err(format!("tag '{}' must be lowercase kebab-case", tag));
+        }
+
+        if let Some(var) = self.variables.iter().find(|v| !is_upper_snake_case(&v.name)) {
+            return Err(format!("variable '{}' must be UPPER_SNAKE_CASE", var.name));
+        }
+
+        Ok(())
+    }
+}
+
+/// `^[a-z0-9]+(-[a-z0-9]+)*$`
+pub(crate) fn is_kebab_case(value: &str) -> bool {
+    !value.is_empty()
+        && value.split('-').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        })
+}
+
+/// `^[A-Z][A-Z0-9_]*$`
+fn is_upper_snake_case(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
 }
 
 /// Summary view of a prompt for list output
@@ -113,6 +154,8 @@ pub struct PromptSummary {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
     pub featured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 impl From<&Prompt> for PromptSummary {
@@ -124,6 +167,7 @@ impl From<&Prompt> for PromptSummary {
             category: p.category.clone(),
             tags: p.tags.clone(),
             featured: p.featured,
+            source: p.source.clone(),
         }
     }
 }
@@ -159,4 +203,18 @@ mod tests {
         assert!(p.has_tag("cli"));
         assert!(!p.has_tag("python"));
     }
+
+    #[test]
+    fn test_validate() {
+        let mut p = Prompt::new("code-review-2", "Title", "content");
+        p.tags = vec!["rust".to_string()];
+        assert!(p.validate().is_ok());
+
+        assert!(Prompt::new("Code_Review", "t", "c").validate().is_err());
+        assert!(Prompt::new("id", "", "c").validate().is_err());
+        assert!(Prompt::new("id", "t", "  ").validate().is_err());
+
+        p.tags = vec!["Bad Tag".to_string()];
+        assert!(p.validate().is_err());
+    }
 }
@@ -4,7 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::Prompt;
+use super::{Bundle, Prompt};
 
 /// Registry metadata for cache management
 ///
@@ -36,6 +36,8 @@ impl RegistryMeta {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Registry {
     pub prompts: Vec<Prompt>,
+    #[serde(default)]
+    pub bundles: Vec<Bundle>,
     #[serde(flatten)]
     pub meta: RegistryMeta,
 }
@@ -46,10 +48,25 @@ impl Registry {
         let count = prompts.len();
         Self {
             prompts,
+            bundles: Vec::new(),
             meta: RegistryMeta::new(count),
         }
     }
 
+    /// Attach the registry's bundles
+    pub fn with_bundles(mut self, bundles: Vec<Bundle>) -> Self {
+        self.bundles = bundles;
+        self
+    }
+
+    /// Use the payload's version, when it has one
+    pub fn with_version(mut self, version: Option<String>) -> Self {
+        if let Some(version) = version {
+            self.meta.version = version;
+        }
+        self
+    }
+
     /// Find a prompt by ID
     pub fn get(&self, id: &str) -> Option<&Prompt> {
         self.prompts.iter().find(|p| p.id == id)
@@ -79,7 +96,7 @@ impl Registry {
             }
         }
         let mut result: Vec<_> = counts.into_iter().collect();
-        result.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by count descending
+        result.sort_by_key(|entry| std::cmp::Reverse(entry.1)); // Sort by count descending
         result
     }
 
@@ -111,6 +128,33 @@ pub enum RegistrySource {
     Cache,
     Bundled,
     Local,
+    /// Imported from a `jfp registry pack` archive
+    Packed,
+}
+
+impl RegistrySource {
+    /// Stable lowercase name, matching the serde representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegistrySource::Remote => "remote",
+            RegistrySource::Cache => "cache",
+            RegistrySource::Bundled => "bundled",
+            RegistrySource::Local => "local",
+            RegistrySource::Packed => "packed",
+        }
+    }
+
+    /// Parse the name produced by `as_str`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "remote" => Some(RegistrySource::Remote),
+            "cache" => Some(RegistrySource::Cache),
+            "bundled" => Some(RegistrySource::Bundled),
+            "local" => Some(RegistrySource::Local),
+            "packed" => Some(RegistrySource::Packed),
+            _ => None,
+        }
+    }
 }
 
 /// Registry load result with source info
@@ -119,4 +163,8 @@ pub struct RegistryLoadResult {
     pub registry: Registry,
     pub source: RegistrySource,
     pub stale: bool,
+    /// Why the remote registry could not be used, when this is a fallback
+    pub fallback_reason: Option<String>,
+    /// Problems that did not stop the load (such as skipped source files)
+    pub warnings: Vec<String>,
 }
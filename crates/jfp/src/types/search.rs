@@ -31,7 +31,7 @@ impl SearchResult {
 }
 
 /// Where a search term matched
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct SearchMatch {
     pub field: SearchField,
     pub term: String,
@@ -57,6 +57,7 @@ pub enum SearchField {
     Content,
 }
 
+#[allow(dead_code)]
 impl SearchField {
     /// Base score for this field (from spec)
     pub fn base_score(&self) -> f64 {
@@ -87,6 +88,7 @@ impl SearchField {
 /// - Description: 2x weight
 /// - Tags: 2x weight
 /// - Content: 1x weight
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct Bm25Weights {
     pub id: f64,
@@ -109,6 +111,7 @@ impl Default for Bm25Weights {
 }
 
 /// Search options
+#[allow(dead_code)]
 #[derive(Debug, Clone, Default)]
 pub struct SearchOptions {
     pub limit: usize,
@@ -117,6 +120,7 @@ pub struct SearchOptions {
     pub include_personal: bool,
 }
 
+#[allow(dead_code)]
 impl SearchOptions {
     pub fn new(limit: usize) -> Self {
         Self {